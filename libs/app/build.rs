@@ -0,0 +1,21 @@
+//! Packs `examples/default` into the pak that is used when no other one is passed
+//! in, so that it goes through the same code as any other pak.
+use std::path::{Path, PathBuf};
+
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/default");
+
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let mut packed = std::io::Cursor::new(Vec::new());
+
+    pak::pack(&dir, &mut packed)
+        .unwrap_or_else(|e| panic!("Could not pack {}:\n{e}", dir.display()));
+
+    let out_path = PathBuf::from(
+        std::env::var_os("OUT_DIR").expect("cargo should set OUT_DIR for build scripts")
+    ).join("default.pak");
+
+    std::fs::write(&out_path, packed.into_inner())
+        .unwrap_or_else(|e| panic!("Could not write {}:\n{e}", out_path.display()));
+}
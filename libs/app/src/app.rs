@@ -1,16 +1,17 @@
 use features::invariant_assert;
 use gfx::{Commands, AddDrawCommands, nine_slice, next_arrow, speech, to_tile};
-use gfx_sizes::{ARGB, GFX_WIDTH};
-use pak_types::{Specs};
-use platform_types::{unscaled, Button, Dir, Input, PakReader, Speaker, SFX};
+use pak_types::{music, script, sound, sprite::{self, BaseFont}, Scripts, Specs};
+use platform_types::{command, unscaled, Button, Dir, Input, PakSource, Speaker};
 pub use platform_types::StateParams;
-use game::{FadeMessageSpec, HallwayState, Mode, TalkingState, PostTalkingAction};
-use models::{Entity, i_to_xy, Pak, Speech, Speeches, Spritesheet, TileSprite, XY};
+use std::io::Read;
+use game::{FadeMessageSpec, Mode, TalkingState, PostTalkingAction};
+use models::{Entity, i_to_xy, Speech, Speeches, Spritesheet, Spritesheets, TileSprite, XY};
 
 #[derive(Debug)]
 pub enum Error {
     Pak(pak::Error),
     Game(game::Error),
+    Script(script::Error),
 }
 
 impl core::fmt::Display for Error {
@@ -19,6 +20,26 @@ impl core::fmt::Display for Error {
         match self {
             Pak(error) => write!(f, "Pak Error:\n{error}"),
             Game(error) => write!(f, "Game Error:\n{error:#?}"),
+            Script(error) => write!(f, "Script Error:\n{error}"),
+        }
+    }
+}
+
+/// How much of the error text fits on the screen, in characters of the base font.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ErrorLayout {
+    columns: usize,
+    rows: usize,
+}
+
+impl ErrorLayout {
+    fn new(base_font: &sprite::Spec<BaseFont>) -> Self {
+        let (columns, rows) = base_font.max_tile_counts();
+
+        Self {
+            columns: usize::try_from(columns).unwrap_or(0).max(1),
+            // Leaves room for a gap, then the footer, at the bottom of the screen.
+            rows: usize::try_from(rows).unwrap_or(0).saturating_sub(2).max(1),
         }
     }
 }
@@ -27,13 +48,67 @@ impl core::fmt::Display for Error {
 pub struct ErrorState {
     pub error: Error,
     pub show_are_you_sure: bool,
+    /// `error`, lowercased and wrapped to fit on the screen.
+    pub text: Vec<u8>,
+    pub line_count: usize,
+    /// The index of the line shown at the top of the screen.
+    pub top_line: usize,
+    pub copied_to_log: bool,
+    /// What `text` was last wrapped for, so a different font gets it wrapped again.
+    layout: ErrorLayout,
+}
+
+impl ErrorState {
+    fn fit(&mut self, layout: ErrorLayout) {
+        if self.layout == layout {
+            return
+        }
+        self.layout = layout;
+
+        let full_text = format!("{}", self.error).to_lowercase();
+
+        self.text.clear();
+
+        for (i, line) in text::byte_slice::lines(full_text.as_bytes()).enumerate() {
+            if i > 0 {
+                self.text.push(b'\n');
+            }
+
+            // Reflowing collapses whitespace, so only do it when needed, to
+            // keep things like the markers under source spans lined up.
+            if line.len() > layout.columns {
+                self.text.extend(text::byte_slice::reflow(line, layout.columns));
+            } else {
+                self.text.extend(line);
+            }
+        }
+
+        self.line_count = text::byte_slice::lines(&self.text).count();
+        self.top_line = self.top_line.min(self.max_top_line());
+    }
+
+    fn max_top_line(&self) -> usize {
+        self.line_count.saturating_sub(self.layout.rows)
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.top_line = self.top_line
+            .saturating_add_signed(delta)
+            .min(self.max_top_line());
+    }
 }
 
 impl From<Error> for ErrorState {
     fn from(error: Error) -> Self {
+        // The text is filled in by `fit`, once we know the font it will be shown in.
         Self {
             error,
             show_are_you_sure: <_>::default(),
+            text: <_>::default(),
+            line_count: 0,
+            top_line: 0,
+            copied_to_log: false,
+            layout: <_>::default(),
         }
     }
 }
@@ -45,12 +120,36 @@ pub struct State {
     pub commands: Commands,
     pub specs: Specs,
     pub input: Input,
+    /// Set once the mouse or a touch screen has been used, at which point we
+    /// start drawing the on-screen buttons.
+    pub touch_controls_shown: bool,
+    /// Where the pointer went down, while it is still down, so that where it is let
+    /// go can be checked against it.
+    pub pointer_down_xy: Option<unscaled::XY>,
+    /// Set when the pointer went down since the last frame, so that if it is let
+    /// go before the next one, that frame still sees it held.
+    pub pointer_down_since_frame: bool,
+    /// Set when the pak is reloaded, so that the new game does not get saved over
+    /// the one that was being played, until the player does something in it.
+    pub saving_held_until_input: bool,
     pub speaker: Speaker,
-    pub spritesheet: Spritesheet,
+    pub spritesheets: Spritesheets,
+    pub sounds: sound::Banks,
+    pub music: music::Tracks,
+    pub scripts: Scripts,
     // Retained for restarting in error scenarios
     pub params: StateParams,
+    // Retained so that saves and replays can check they are used with the same pak
+    pub pak_hash: PakHash,
 }
 
+/// The content hash of the pak, or 0 if it failed to load.
+pub type PakHash = pak_types::ContentHash;
+
+/// The pak used when no other one is passed in. This is `examples/default`,
+/// packed by the build script.
+const DEFAULT_PAK: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/default.pak"));
+
 impl State {
     pub fn new(params: StateParams) -> Self {
         unsafe {
@@ -64,47 +163,65 @@ impl State {
         // not the macro.
         features::log(&format!("{:?}", seed));
 
-        const HARDCODED_CONFIG: &str = include_str!("../../../examples/default/config.rn");
-
-        let get_hardcoded_spritesheet = || Spritesheet {
-            cells: assets::GFX.try_into().expect("assets::GFX should always have a length greater than 0"),
-            width: GFX_WIDTH,
-        };
+        // Used to show the error when a pak fails to load.
+        let get_default_spritesheets = || Spritesheets::singleton(
+            pak::spritesheet_from_reader(std::io::Cursor::new(DEFAULT_PAK))
+                .expect("DEFAULT_PAK should have been checked by the build script")
+        );
 
-        let pak_reader_opt: Option<Box<dyn PakReader>> = params.pak_loader.and_then(|f| f());
+        let pak_source_opt: Option<PakSource> = params.pak_loader.and_then(|f| f());
 
-        let pak_result = match pak_reader_opt {
-            Some(reader) => {
-                pak::from_reader(reader)
-                    .map_err(Error::Pak)
+        let pak_result = match pak_source_opt {
+            Some(PakSource::Dir(dir)) => {
+                pak::from_dir(&dir, seed).map_err(Error::Pak)
             },
-            None => {
-                // TODO Can we construct a Zip in code? Or maybe we should embed one at compile time?
-                match config::parse(HARDCODED_CONFIG) {
-                    Ok(config) => Ok(Pak {
-                        config,
-                        spritesheet: get_hardcoded_spritesheet(),
-                        specs: Specs::default(),
-                    }),
+            Some(PakSource::Packed(mut reader)) => {
+                let mut pak_bytes = Vec::new();
+                match reader.read_to_end(&mut pak_bytes) {
+                    Ok(_) => {
+                        pak::from_reader(std::io::Cursor::new(pak_bytes), seed)
+                            .map_err(Error::Pak)
+                    },
                     Err(err) => {
-                        Err(Error::Pak(pak::Error::Config(err)))
+                        Err(Error::Pak(pak::Error::Zip(err.into())))
                     }
                 }
             },
+            None => {
+                pak::from_reader(std::io::Cursor::new(DEFAULT_PAK), seed)
+                    .map_err(Error::Pak)
+            },
         };
 
-        let (game_state, spritesheet, specs) = match pak_result {
-            Ok(pak) => (
-                game::State::new(&pak.specs, seed, pak.config)
-                    .map_err(Error::Game)
-                    .map_err(ErrorState::from),
-                pak.spritesheet,
-                pak.specs,
-            ),
+        let (game_state, spritesheets, specs, sounds, music, scripts, pak_hash) = match pak_result {
+            Ok(pak) => {
+                let pak_types::config::Metadata { title, author, .. } = &pak.metadata;
+                if !title.is_empty() {
+                    features::log(&format!("Loaded {title:?} by {author:?}"));
+                }
+
+                let scripts = pak.config.scripts.clone();
+
+                (
+                    game::State::new(&pak.specs, seed, pak.config)
+                        .map_err(Error::Game)
+                        .map_err(ErrorState::from),
+                    pak.spritesheets,
+                    pak.specs,
+                    pak.sounds,
+                    pak.music,
+                    scripts,
+                    pak.content_hash,
+                )
+            },
             Err(e) => (
                 Err(ErrorState::from(e)),
-                get_hardcoded_spritesheet(),
+                get_default_spritesheets(),
                 Specs::default(),
+                <_>::default(),
+                <_>::default(),
+                <_>::default(),
+                0,
             )
         };
 
@@ -114,43 +231,82 @@ impl State {
             commands: Commands::new(seed, specs.base_font.clone(), specs.base_ui.clone()),
             specs,
             input: Input::default(),
+            touch_controls_shown: false,
+            pointer_down_xy: None,
+            pointer_down_since_frame: false,
+            saving_held_until_input: false,
             speaker: Speaker::default(),
-            spritesheet,
+            spritesheets,
+            sounds,
+            music,
+            scripts,
             params,
+            pak_hash,
         }
     }
 }
 
+/// Starts over with the pak loaded again, for when it has changed.
+pub fn reload(state: &State) -> State {
+    let mut reloaded = State::new(state.params);
+    reloaded.touch_controls_shown = state.touch_controls_shown;
+    reloaded.saving_held_until_input = true;
+
+    reloaded
+}
+
 #[cfg_attr(feature = "reload", unsafe(no_mangle))]
-pub fn frame(state: &mut State) -> (&[platform_types::Command], (&[ARGB], usize), &[SFX]) {
+pub fn frame(state: &mut State) -> (&[platform_types::Command], &[Spritesheet], &Speaker) {
     let mut shake_amount_fallback = 0;
     let shake_amount = match &mut state.game_state {
         Ok(s) => &mut s.shake_amount,
         Err(_) => &mut shake_amount_fallback
     };
 
+    let input = if state.touch_controls_shown {
+        touch::apply(state.input)
+    } else {
+        state.input
+    };
+
     state.commands.begin_frame(shake_amount);
     state.speaker.clear();
     let effect = update_and_render(
         &mut state.commands,
         &state.specs,
+        &state.scripts,
         &mut state.game_state,
-        state.input,
+        input,
         &mut state.speaker,
     );
 
+    if state.touch_controls_shown {
+        touch::render(&mut state.commands, input);
+    }
+
     match effect {
         Effect::NoOp => {},
         Effect::Reload => {
-            *state = State::new(state.params);
+            *state = reload(state);
         },
     }
 
+    state.speaker.request_track(
+        state.game_state.as_ref().ok().and_then(game::State::current_track)
+    );
+
     state.commands.end_frame();
 
     state.input.previous_gamepad = state.input.gamepad;
+    state.input.previous_pointer = state.input.pointer;
+    state.input.tap = None;
+    state.pointer_down_since_frame = false;
+
+    if state.pointer_down_xy.is_none() {
+        state.input.pointer = None;
+    }
 
-    (state.commands.slice(), state.spritesheet.slice(), state.speaker.slice())
+    (state.commands.slice(), state.spritesheets.slice(), &state.speaker)
 }
 
 pub fn press(state: &mut State, button: Button) {
@@ -161,17 +317,476 @@ pub fn press(state: &mut State, button: Button) {
     }
 
     state.input.gamepad.insert(button);
+    state.saving_held_until_input = false;
 }
 
 pub fn release(state: &mut State, button: Button) {
     state.input.gamepad.remove(button);
 }
 
+/// Called when the mouse button or a finger goes down, and again each time it
+/// moves while down. `xy` should already be mapped onto the unscaled screen, as
+/// `render::unscaled_xy` does.
+pub fn pointer_down(state: &mut State, xy: unscaled::XY) {
+    state.touch_controls_shown = true;
+    if state.pointer_down_xy.is_none() {
+        state.pointer_down_xy = Some(xy);
+        state.pointer_down_since_frame = true;
+    }
+    state.input.pointer = Some(xy);
+    state.saving_held_until_input = false;
+}
+
+/// Called when the mouse button or a finger comes up. This counts as a tap if it
+/// comes up over the same on-screen button, or lack of one, that it went down on.
+pub fn pointer_up(state: &mut State) {
+    let Some(down_xy) = state.pointer_down_xy.take() else { return };
+
+    if let Some(up_xy) = state.input.pointer
+        .filter(|&up_xy| touch::button_at(down_xy) == touch::button_at(up_xy)) {
+        state.input.tap = Some(up_xy);
+    }
+
+    // If it went down and came up between frames, the next frame still sees it
+    // held, or an on-screen button pressed this quickly would do nothing.
+    if !state.pointer_down_since_frame {
+        state.input.pointer = None;
+    }
+}
+
+mod touch {
+    //! On-screen buttons for playing with a mouse or a touch screen.
+
+    use super::*;
+    use platform_types::command::{WIDTH_SIGNED, HEIGHT_SIGNED};
+
+    const MARGIN: unscaled::Inner = 4;
+    const GAP: unscaled::Inner = 2;
+    const SIZE: unscaled::Inner = 28;
+    const STEP: unscaled::Inner = SIZE + GAP;
+    const SMALL_W: unscaled::Inner = 52;
+    const SMALL_H: unscaled::Inner = 16;
+
+    const fn rect(x: unscaled::Inner, y: unscaled::Inner, w: unscaled::Inner, h: unscaled::Inner) -> unscaled::Rect {
+        unscaled::Rect {
+            x: unscaled::X(x),
+            y: unscaled::Y(y),
+            w: unscaled::W::new(w),
+            h: unscaled::H::new(h),
+        }
+    }
+
+    const BOTTOM: unscaled::Inner = HEIGHT_SIGNED - MARGIN - SIZE;
+    const RIGHT: unscaled::Inner = WIDTH_SIGNED - MARGIN - SIZE;
+
+    const BUTTONS: [(Button, &[u8], unscaled::Rect); 8] = [
+        (Button::UP, b"^", rect(MARGIN + STEP, BOTTOM - STEP * 2, SIZE, SIZE)),
+        (Button::LEFT, b"<", rect(MARGIN, BOTTOM - STEP, SIZE, SIZE)),
+        (Button::RIGHT, b">", rect(MARGIN + STEP * 2, BOTTOM - STEP, SIZE, SIZE)),
+        (Button::DOWN, b"v", rect(MARGIN + STEP, BOTTOM, SIZE, SIZE)),
+        (Button::A, b"a", rect(RIGHT, BOTTOM - STEP, SIZE, SIZE)),
+        (Button::B, b"b", rect(RIGHT - STEP, BOTTOM, SIZE, SIZE)),
+        (Button::SELECT, b"select", rect(WIDTH_SIGNED / 2 - GAP - SMALL_W, HEIGHT_SIGNED - MARGIN - SMALL_H, SMALL_W, SMALL_H)),
+        (Button::START, b"start", rect(WIDTH_SIGNED / 2 + GAP, HEIGHT_SIGNED - MARGIN - SMALL_H, SMALL_W, SMALL_H)),
+    ];
+
+    pub fn button_at(xy: unscaled::XY) -> Option<Button> {
+        BUTTONS.iter()
+            .find(|(_, _, rect)| rect.contains(xy))
+            .map(|(button, _, _)| *button)
+    }
+
+    /// Returns `input` with any on-screen buttons under the pointer held down.
+    /// The pointer, and taps, are hidden from the game while over a button, so that
+    /// pressing a button does not also count as tapping what is underneath it.
+    pub fn apply(mut input: Input) -> Input {
+        if input.tap.and_then(button_at).is_some() {
+            input.tap = None;
+        }
+
+        if let Some(button) = input.pointer.and_then(button_at) {
+            input.gamepad.insert(button);
+            input.pointer = None;
+        }
+
+        if let Some(button) = input.previous_pointer.and_then(button_at) {
+            input.previous_gamepad.insert(button);
+            input.previous_pointer = None;
+        }
+
+        input
+    }
+
+    pub fn render(commands: &mut Commands, input: Input) {
+        let edge_wh = commands.ui_edge_wh();
+
+        for (button, label, rect) in BUTTONS {
+            commands.nine_slice(
+                if input.gamepad.contains(button) {
+                    nine_slice::SELECTRUM
+                } else {
+                    nine_slice::CONTEXT_MENU
+                },
+                rect,
+            );
+
+            commands.print_line(
+                label,
+                nine_slice::inner_rect(edge_wh, rect).xy(),
+                6,
+            );
+        }
+    }
+
+    #[test]
+    fn holding_a_button_does_not_also_tap() {
+        let (_, _, up_rect) = BUTTONS[0];
+
+        let input = apply(Input {
+            pointer: Some(up_rect.xy()),
+            tap: Some(up_rect.xy()),
+            ..<_>::default()
+        });
+
+        assert!(input.pressed_this_frame(Button::UP));
+        assert_eq!(input.tapped_this_frame(), None);
+
+        let input = apply(Input {
+            tap: Some(ELSEWHERE),
+            ..<_>::default()
+        });
+
+        assert_eq!(input.gamepad, Button::default());
+        assert_eq!(input.tapped_this_frame(), Some(ELSEWHERE));
+    }
+
+    #[cfg(test)]
+    const ELSEWHERE: unscaled::XY = unscaled::XY { x: unscaled::X(WIDTH_SIGNED / 2), y: unscaled::Y(HEIGHT_SIGNED / 2) };
+
+    #[cfg(test)]
+    fn test_state() -> State {
+        State::new(StateParams {
+            pak_loader: None,
+            logger: None,
+            error_logger: None,
+            seed: [5; 16],
+        })
+    }
+
+    #[test]
+    fn dragging_from_a_button_onto_the_game_does_not_tap() {
+        let (_, _, up_rect) = BUTTONS[0];
+        let mut state = test_state();
+
+        pointer_down(&mut state, up_rect.xy());
+        frame(&mut state);
+        pointer_down(&mut state, ELSEWHERE);
+        pointer_up(&mut state);
+
+        assert_eq!(apply(state.input).tapped_this_frame(), None);
+    }
+
+    #[test]
+    fn pressing_and_letting_go_between_frames_is_still_seen() {
+        let (_, _, up_rect) = BUTTONS[0];
+        let mut state = test_state();
+
+        pointer_down(&mut state, ELSEWHERE);
+        pointer_up(&mut state);
+
+        assert_eq!(apply(state.input).tapped_this_frame(), Some(ELSEWHERE));
+        frame(&mut state);
+        assert_eq!(apply(state.input).tapped_this_frame(), None);
+
+        pointer_down(&mut state, up_rect.xy());
+        pointer_up(&mut state);
+
+        assert!(apply(state.input).pressed_this_frame(Button::UP));
+        frame(&mut state);
+        assert!(apply(state.input).released_this_frame(Button::UP));
+    }
+
+    #[test]
+    fn the_buttons_do_not_overlap() {
+        for (i, (_, _, a)) in BUTTONS.iter().enumerate() {
+            for (_, _, b) in &BUTTONS[i + 1..] {
+                assert!(!a.contains(b.xy()) && !b.contains(a.xy()), "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+}
+
+/// Returns `None` when there is no game in progress to save, for example when an
+/// error is being shown, or when the pak was just reloaded and nothing has been
+/// done in the new game yet.
+pub fn save(state: &State) -> Option<Result<Vec<u8>, game::save::Error>> {
+    if state.saving_held_until_input {
+        return None
+    }
+
+    let game_state = state.game_state.as_ref().ok()?;
+
+    Some(game::save::to_bytes(state.params.seed, state.pak_hash, game_state))
+}
+
+/// Replaces the current game with the one in `bytes`. On error, the current game
+/// is left as it was.
+pub fn load(state: &mut State, bytes: &[u8]) -> Result<(), game::save::Error> {
+    let (seed, game_state) = game::save::from_bytes(bytes, state.pak_hash)?;
+
+    features::log(&format!("Loaded save with seed {:?}", seed));
+
+    state.game_state = Ok(game_state);
+    state.params.seed = seed;
+    state.input = Input::default();
+
+    Ok(())
+}
+
 const INVENTORY_WIDTH_CELLS: usize = 13;
 const INVENTORY_HEIGHT_CELLS: usize = 8;
 const INVENTORY_MAX_INDEX: usize = (INVENTORY_WIDTH_CELLS * INVENTORY_HEIGHT_CELLS) - 1;
 
-fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State, input: Input, speaker: &mut Speaker) {
+pub mod replay {
+    //! Recordings of every button press and release, along with everything else
+    //! that `frame` depends on, so that a run can be reproduced exactly.
+
+    use super::{frame, press, release, PakHash, State, StateParams};
+    use platform_types::Button;
+    use xs::Seed;
+
+    pub const FORMAT_VERSION: u32 = 1;
+
+    const MAGIC: [u8; 4] = *b"RIPR";
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum EventKind {
+        Press,
+        Release,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Event {
+        /// The index of the frame that this event happened before.
+        pub frame: u32,
+        pub button: Button,
+        pub kind: EventKind,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Recording {
+        pub seed: Seed,
+        pub pak_hash: PakHash,
+        pub frame_count: u32,
+        pub events: Vec<Event>,
+    }
+
+    #[derive(Debug)]
+    pub enum Error {
+        NotARecording,
+        UnsupportedVersion(u32),
+        Truncated,
+        UnknownButton(u8),
+        PakMismatch { expected: PakHash, actual: PakHash },
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use Error::*;
+            match self {
+                NotARecording => write!(f, "That does not look like a recording."),
+                UnsupportedVersion(version) => write!(
+                    f,
+                    "Recording format version {version} is not supported. Only version {FORMAT_VERSION} is."
+                ),
+                Truncated => write!(f, "The recording ended unexpectedly."),
+                UnknownButton(index) => write!(f, "The recording contains an unknown button: {index}"),
+                PakMismatch { expected, actual } => write!(
+                    f,
+                    "The recording was made with a pak with hash {expected:016x}, but the loaded pak has hash {actual:016x}."
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    impl Recording {
+        /// Call this right after `State::new`, since the recording assumes the run
+        /// starts from there.
+        pub fn new(state: &State) -> Self {
+            Self {
+                seed: state.params.seed,
+                pak_hash: state.pak_hash,
+                frame_count: 0,
+                events: Vec::new(),
+            }
+        }
+
+        pub fn press(&mut self, state: &mut State, button: Button) {
+            self.push(button, EventKind::Press);
+            press(state, button);
+        }
+
+        pub fn release(&mut self, state: &mut State, button: Button) {
+            self.push(button, EventKind::Release);
+            release(state, button);
+        }
+
+        /// Call this once after each call to `frame`.
+        pub fn end_frame(&mut self) {
+            self.frame_count += 1;
+        }
+
+        fn push(&mut self, button: Button, kind: EventKind) {
+            self.events.push(Event {
+                frame: self.frame_count,
+                button,
+                kind,
+            });
+        }
+
+        pub fn to_bytes(&self) -> Vec<u8> {
+            let mut output = Vec::with_capacity(48 + self.events.len() * 5);
+
+            output.extend_from_slice(&MAGIC);
+            output.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+            output.extend_from_slice(&self.seed);
+            output.extend_from_slice(&self.pak_hash.to_le_bytes());
+            output.extend_from_slice(&self.frame_count.to_le_bytes());
+            output.extend_from_slice(&(self.events.len() as u32).to_le_bytes());
+
+            for event in &self.events {
+                output.extend_from_slice(&event.frame.to_le_bytes());
+
+                let index = Button::ALL.iter()
+                    .position(|&b| b == event.button)
+                    .expect("Only single buttons should be recorded") as u8;
+
+                // The high bit marks presses, which leaves plenty of room for buttons.
+                output.push(match event.kind {
+                    EventKind::Press => index | 0x80,
+                    EventKind::Release => index,
+                });
+            }
+
+            output
+        }
+
+        pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+            let mut reader = Reader { bytes };
+
+            if reader.take::<4>()? != MAGIC {
+                return Err(Error::NotARecording);
+            }
+
+            let version = u32::from_le_bytes(reader.take()?);
+            if version != FORMAT_VERSION {
+                return Err(Error::UnsupportedVersion(version));
+            }
+
+            let seed = reader.take()?;
+            let pak_hash = PakHash::from_le_bytes(reader.take()?);
+            let frame_count = u32::from_le_bytes(reader.take()?);
+            let event_count = u32::from_le_bytes(reader.take()?);
+
+            // Don't trust the count for the allocation, since it may be corrupted.
+            let mut events = Vec::with_capacity(bytes.len() / 5);
+
+            for _ in 0..event_count {
+                let frame = u32::from_le_bytes(reader.take()?);
+                let [byte] = reader.take()?;
+
+                let index = byte & !0x80;
+                let button = *Button::ALL.get(usize::from(index))
+                    .ok_or(Error::UnknownButton(index))?;
+
+                let kind = if byte & 0x80 != 0 {
+                    EventKind::Press
+                } else {
+                    EventKind::Release
+                };
+
+                events.push(Event { frame, button, kind });
+            }
+
+            Ok(Self {
+                seed,
+                pak_hash,
+                frame_count,
+                events,
+            })
+        }
+    }
+
+    struct Reader<'bytes> {
+        bytes: &'bytes [u8],
+    }
+
+    impl Reader<'_> {
+        fn take<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+            if self.bytes.len() < N {
+                return Err(Error::Truncated);
+            }
+
+            let (taken, rest) = self.bytes.split_at(N);
+            self.bytes = rest;
+
+            let mut output = [0; N];
+            output.copy_from_slice(taken);
+            Ok(output)
+        }
+    }
+
+    /// Runs through the whole recording without any rendering or sound, and returns
+    /// the resulting state. The seed from `params` is replaced by the recorded one.
+    pub fn run(recording: &Recording, mut params: StateParams) -> Result<State, Error> {
+        params.seed = recording.seed;
+
+        let mut state = State::new(params);
+
+        if state.pak_hash != recording.pak_hash {
+            return Err(Error::PakMismatch {
+                expected: recording.pak_hash,
+                actual: state.pak_hash,
+            });
+        }
+
+        fn apply(state: &mut State, event: &Event) {
+            match event.kind {
+                EventKind::Press => press(state, event.button),
+                EventKind::Release => release(state, event.button),
+            }
+        }
+
+        let mut events = recording.events.iter().peekable();
+
+        for frame_index in 0..recording.frame_count {
+            while let Some(event) = events.next_if(|e| e.frame <= frame_index) {
+                apply(&mut state, event);
+            }
+
+            frame(&mut state);
+        }
+
+        // Events after the last frame, like a press right before the recording
+        // stopped, still changed the recorded state, so they go in too.
+        for event in events {
+            apply(&mut state, event);
+        }
+
+        Ok(state)
+    }
+}
+
+fn game_update(
+    commands: &mut Commands,
+    specs: &Specs,
+    scripts: &Scripts,
+    state: &mut game::State,
+    input: Input,
+    speaker: &mut Speaker,
+) -> Result<(), script::Error> {
     #[derive(Clone, Copy, PartialEq, Eq)]
     enum TalkingUpdateState {
         StillTalking,
@@ -191,7 +806,8 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
         }
 
         if input.pressed_this_frame(Button::A)
-        || input.pressed_this_frame(Button::B) {
+        || input.pressed_this_frame(Button::B)
+        || input.tapped_this_frame().is_some() {
             talking.speech_index += 1;
             return output
         }
@@ -207,25 +823,25 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
         Mode::DoorTo(_target, _animation) => {
             // TODO? Allow cancelling going in the door?
         },
-        Mode::Hallway{ source, target } => {
-            macro_rules! u_and_r_call { 
-                ($obj: ident) => ({
-                    $obj.update_and_render(
-                        commands,
-                        specs,
-                        input,
-                        speaker,
-                    );
-                })
+        Mode::Hallway{ source, target, give_up_frames } => {
+            if input.gamepad.contains(Button::SELECT) {
+                *give_up_frames = give_up_frames.saturating_add(1);
+            } else {
+                *give_up_frames = 0;
+            }
+
+            if *give_up_frames >= game::GIVE_UP_HOLD_FRAMES {
+                state.give_up_on_hallway(specs);
+                return Ok(())
             }
+
             match state.hallway_states.get_mut(*source, *target) {
-                Some(HallwayState::IcePuzzle(s)) => u_and_r_call!(s),
-                Some(HallwayState::SWORD(s)) => u_and_r_call!(s),
-                Some(HallwayState::BOLD(s)) => u_and_r_call!(s),
-                Some(HallwayState::HexHop(s)) => u_and_r_call!(s),
-                Some(HallwayState::HexTwiddle(s)) => u_and_r_call!(s),
-                Some(HallwayState::CubeMaze(s)) => u_and_r_call!(s),
-                Some(HallwayState::KeycardShuffle(s)) => u_and_r_call!(s),
+                Some(hallway) => hallway.update_and_render(
+                    commands,
+                    specs,
+                    input,
+                    speaker,
+                ),
                 None => {
                     invariant_assert!(false, "Hallway was not found while in Hallway mode!");
                     state.mode = Mode::Walking;
@@ -249,18 +865,24 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
                     dir_count: <_>::default(),
                     description_talking: <_>::default(),
                 };
-                return
+                return Ok(())
             }
 
             if let Some(dir) = input.dir_pressed_this_frame() {
-                state.walk(dir);
+                state.walk(dir, scripts)?;
             }
 
             if input.pressed_this_frame(Button::A) {
                 if let Some(dir) = input.contains_dir() {
-                    state.interact(dir)
+                    state.interact(dir, scripts)?;
                 }
             }
+
+            if let Some(dir) = input.tapped_this_frame()
+                .and_then(|xy| to_tile::containing(&specs.base_tiles, xy))
+                .and_then(|xy| state.dir_to_adjacent(xy)) {
+                state.walk_or_interact(dir, scripts)?;
+            }
         },
         Mode::Inventory {
             current_index,
@@ -270,7 +892,7 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
         } => {
             if input.pressed_this_frame(Button::START) {
                 state.mode = Mode::Walking;
-                return
+                return Ok(())
             }
 
             if let Some(talking) = description_talking {
@@ -278,7 +900,21 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
                     *description_talking = None;
                 }
 
-                return
+                return Ok(())
+            }
+
+            if let Some(xy) = input.tapped_this_frame() {
+                let layout = InventoryLayout::new(commands.ui_edge_wh(), specs);
+
+                if let Some((index, _)) = layout.cells().find(|(_, rect)| rect.contains(xy)) {
+                    if index == *current_index {
+                        if let Some(item) = state.world.player.inventory.get(index) {
+                            *description_talking = Some(TalkingState::new(item.speeches_key()));
+                        }
+                    } else {
+                        *current_index = index;
+                    }
+                }
             }
 
             if input.pressed_this_frame(Button::A) {
@@ -344,61 +980,8 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
             if let Finished = talking_update(talking, &state.speeches, input) {
                 match talking.post_action {
                     PostTalkingAction::NoOp => {},
-                    PostTalkingAction::TakeItem(receiveing_entity_key, def_id) => 'take_item: {
-                        let giving_entity_key = state.world.player_key();
-                        let Some(giving_entity_len) = state.world.get_entity(giving_entity_key)
-                            .map(|g_e| g_e.inventory.len())
-                        else {
-                            break 'take_item
-                        };
-
-                        // TODO? Worth checking if it's not there?
-                        // TODO? Do we want to give every entity an inventory, and preserve every item?
-                        // Iterate backward so we can remove without indexing errors
-                        for i in (0..giving_entity_len).rev() {
-                            let Some(item_def_id) = state.world.get_entity(giving_entity_key)
-                                .map(|g_e| g_e.inventory[i].transformable.id)
-                            else {
-                                break 'take_item
-                            };
-
-                            if item_def_id == def_id {
-                                let Some(taken) = state.world.get_entity_mut(giving_entity_key)
-                                    .map(|g_e| g_e.inventory.remove(i)) else {
-                                        break 'take_item
-                                    };
-
-                                // This option is a bit less hassle than implmenting a way to get mut refs to
-                                // two distinct entities.
-                                let mut reward_opt = None;
-
-                                if let Some(receiveing_entity) = state.world.get_entity_mut(receiveing_entity_key) {
-                                    for desire in &mut receiveing_entity.transformable.wants {
-                                        if desire.def_id == def_id {
-                                            desire.state = models::DesireState::Satisfied;
-                                            break
-                                        }
-                                    }
-
-                                    // TODO? Allow associating particular desires with particular rewards
-                                    //       from the same NPC?
-
-                                    // Extract the reward before putting the taken item into the receiveing_entity's
-                                    // inventory, so they don't give it back.
-                                    if let Some(reward) = receiveing_entity.inventory.pop() {
-                                        reward_opt = Some(reward);
-                                    }
-
-                                    receiveing_entity.inventory.push(taken);
-                                } else {
-                                    debug_assert!(false, "Why did the item get taken if no one wants it?!");
-                                }
-
-                                if let Some(reward) = reward_opt {
-                                    state.push_inventory(giving_entity_key, reward);
-                                }
-                            }
-                        }
+                    PostTalkingAction::TakeItem(receiveing_entity_key, def_id) => {
+                        state.take_item(receiveing_entity_key, def_id);
                     },
                 }
 
@@ -406,6 +989,77 @@ fn game_update(commands: &mut Commands, specs: &Specs, state: &mut game::State,
             }
         },
     }
+
+    Ok(())
+}
+
+/// Where the parts of the inventory screen go. Shared between rendering and
+/// figuring out what was tapped.
+struct InventoryLayout {
+    inv_outer_rect: unscaled::Rect,
+    goal_outer_rect: unscaled::Rect,
+    inv_inner_rect: unscaled::Rect,
+    cell_wh: unscaled::WH,
+}
+
+impl InventoryLayout {
+    fn new(edge_wh: unscaled::WH, specs: &Specs) -> Self {
+        // TODO should this be derived from the sprite specs?
+        const SPACING: unscaled::Inner = 20;
+
+        let menu_y = unscaled::Y(SPACING);
+        let menu_h = unscaled::H::new(platform_types::command::HEIGHT_SIGNED - 120);
+
+        let goal_outer_w = unscaled::W::new(120 - SPACING);
+
+        let inv_outer_rect = unscaled::Rect {
+            x: unscaled::X(SPACING),
+            y: menu_y,
+            w: unscaled::W::new(platform_types::command::WIDTH_SIGNED - (SPACING * 3)) - goal_outer_w,
+            h: menu_h,
+        };
+
+        let goal_outer_rect = unscaled::Rect {
+            x: inv_outer_rect.x + inv_outer_rect.w + unscaled::W::new(SPACING),
+            y: menu_y,
+            w: goal_outer_w,
+            h: menu_h,
+        };
+
+        Self {
+            inv_outer_rect,
+            goal_outer_rect,
+            inv_inner_rect: nine_slice::inner_rect(edge_wh, inv_outer_rect),
+            cell_wh: edge_wh + specs.base_tiles.tile() + edge_wh,
+        }
+    }
+
+    /// The index and rect of each inventory cell that fits on screen.
+    fn cells(&self) -> impl Iterator<Item = (usize, unscaled::Rect)> {
+        let Self { inv_inner_rect, cell_wh, .. } = *self;
+
+        let inv_x_max = inv_inner_rect.x + inv_inner_rect.w;
+        let inv_y_max = inv_inner_rect.y + inv_inner_rect.h;
+
+        let mut at = inv_inner_rect.xy();
+
+        // TODO implement scrolling for the inventory, depending on the sprite spec's values
+        (0..).map_while(move |inventory_index| {
+            if at.x >= inv_x_max || at.y >= inv_y_max {
+                return None
+            }
+
+            let cell_rect = unscaled::Rect::xy_wh(at, cell_wh);
+
+            at.x += cell_wh.w;
+            if at.x + cell_wh.w >= inv_x_max {
+                at.y += cell_wh.h;
+                at.x = inv_inner_rect.x;
+            }
+
+            Some((inventory_index, cell_rect))
+        })
+    }
 }
 
 #[inline]
@@ -478,10 +1132,36 @@ fn game_render(commands: &mut Commands, specs: &Specs, state: &game::State) {
     //
 
     match &state.mode {
-        Mode::Hallway { source, target } => {
+        Mode::Hallway { source, target, give_up_frames } => {
             let source: &game::EntityKey = source;
-            if let Some(_hallway) = state.hallway_states.get(*source, *target) {
-                // The hallway is expected to be rendered elsewhere
+            if let Some(hallway) = state.hallway_states.get(*source, *target) {
+                // The hallway is expected to be rendered elsewhere, but the
+                // progress towards giving up on it is shared by all of them.
+                if *give_up_frames > 0 {
+                    const TEXT: &[u8] = b"giving up....";
+                    const DOT_COUNT: usize = 4;
+
+                    let dots_shown = usize::from(*give_up_frames) * DOT_COUNT
+                        / usize::from(game::GIVE_UP_HOLD_FRAMES);
+
+                    let mut text = TEXT[..TEXT.len() - DOT_COUNT + dots_shown].to_vec();
+
+                    // So the player can see how far they got, before deciding to
+                    // let go or not.
+                    if let Some(progress) = hallway.progress() {
+                        text.extend(format!("\n{}/{} done", progress.done, progress.total).bytes());
+                    }
+
+                    commands.print_lines(
+                        unscaled::XY {
+                            x: unscaled::X(4),
+                            y: unscaled::Y(4),
+                        },
+                        0,
+                        &text,
+                        6,
+                    );
+                }
             } else {
                 commands.print_lines(
                     unscaled::XY {
@@ -538,30 +1218,11 @@ fn game_render(commands: &mut Commands, specs: &Specs, state: &game::State) {
         } => {
             render_walking(commands, state);
 
-            // TODO should this be derived from the sprite specs?
-            const SPACING: unscaled::Inner = 20;
-
-            let menu_y = unscaled::Y(SPACING);
-            let menu_h = unscaled::H::new(platform_types::command::HEIGHT_SIGNED - 120);
-
-            let goal_outer_w = unscaled::W::new(120 - SPACING);
-
-            let inv_outer_rect = unscaled::Rect {
-                x: unscaled::X(SPACING),
-                y: menu_y,
-                w: unscaled::W::new(platform_types::command::WIDTH_SIGNED - (SPACING * 3)) - goal_outer_w,
-                h: menu_h,
-            };
-
-            let goal_outer_rect = unscaled::Rect {
-                x: inv_outer_rect.x + inv_outer_rect.w + unscaled::W::new(SPACING),
-                y: menu_y,
-                w: goal_outer_w,
-                h: menu_h,
-            };
-
             let edge_wh = commands.ui_edge_wh();
 
+            let layout = InventoryLayout::new(edge_wh, specs);
+            let InventoryLayout { inv_outer_rect, goal_outer_rect, .. } = layout;
+
             //
             //  Draw the goal description
             //
@@ -590,44 +1251,17 @@ fn game_render(commands: &mut Commands, specs: &Specs, state: &game::State) {
             //  Draw the inventory
             //
 
-            let cell_wh = edge_wh + specs.base_tiles.tile() + edge_wh;
-
             commands.nine_slice(nine_slice::INVENTORY, inv_outer_rect);
 
-            let inv_inner_rect = nine_slice::inner_rect(edge_wh, inv_outer_rect);
-
-            let mut inventory_index = 0;
-
-            let mut at = inv_inner_rect.xy();
-
-            let inv_x_max = inv_inner_rect.x + inv_inner_rect.w;
-            let inv_y_max = inv_inner_rect.y + inv_inner_rect.h;
-
-            // TODO implement scrolling for the inventory, depending on the sprite spec's values
-            while at.x < inv_x_max && at.y < inv_y_max {
+            for (inventory_index, cell_rect) in layout.cells() {
                 // draw selectrum
                 if inventory_index == *current_index {
-                    commands.nine_slice(
-                        nine_slice::SELECTRUM,
-                        unscaled::Rect {
-                            x: at.x,
-                            y: at.y,
-                            w: cell_wh.w,
-                            h: cell_wh.h,
-                        },
-                    );
+                    commands.nine_slice(nine_slice::SELECTRUM, cell_rect);
                 }
 
                 if let Some(item) = state.world.player.inventory.get(inventory_index) {
-                    draw_tile_sprite(commands, at + edge_wh, item.transformable.tile_sprite);
+                    draw_tile_sprite(commands, cell_rect.xy() + edge_wh, item.transformable.tile_sprite);
                 };
-
-                at.x += cell_wh.w;
-                if at.x + cell_wh.w >= inv_x_max {
-                    at.y += cell_wh.h;
-                    at.x = inv_inner_rect.x;
-                }
-                inventory_index += 1;
             }
 
             if let Some(talking) = description_talking {
@@ -673,6 +1307,7 @@ enum Effect {
 fn update_and_render(
     commands: &mut Commands,
     specs: &Specs,
+    scripts: &Scripts,
     game_state: &mut GameState,
     input: Input,
     speaker: &mut Speaker,
@@ -686,7 +1321,12 @@ fn update_and_render(
 
     match game_state {
         Ok(state) => {
-            game_update(commands, specs, state, input, speaker);
+            if let Err(error) = game_update(commands, specs, scripts, state, input, speaker) {
+                *game_state = Err(ErrorState::from(Error::Script(error)));
+
+                return <_>::default();
+            }
+
             // Empty message queue
             for FadeMessageSpec { message, xy } in state.fade_message_specs.drain(..) {
                 commands.push_fade_message(
@@ -699,8 +1339,11 @@ fn update_and_render(
             <_>::default()
         },
         Err(err_state) => {
+            let layout = ErrorLayout::new(&specs.base_font);
+            err_state.fit(layout);
+
             let effect = err_update(err_state, input, speaker);
-            err_render(commands, err_state);
+            err_render(commands, &specs.base_font, err_state);
 
             effect
         }
@@ -722,9 +1365,21 @@ fn err_update(
         } else if input.pressed_this_frame(Button::B) {
             error_state.show_are_you_sure = false;
         }
+    } else if input.pressed_this_frame(Button::A) {
+        error_state.show_are_you_sure = true;
+    } else if input.pressed_this_frame(Button::SELECT) {
+        // The on-screen text is lowercased and wrapped, so log the original.
+        features::log_error(&format!("{}", error_state.error));
+        error_state.copied_to_log = true;
     } else {
-        if input.pressed_this_frame(Button::A) {
-            error_state.show_are_you_sure = true;
+        let page = error_state.layout.rows as isize;
+
+        match input.dir_pressed_this_frame() {
+            Some(Dir::Up) => error_state.scroll_by(-1),
+            Some(Dir::Down) => error_state.scroll_by(1),
+            Some(Dir::Left) => error_state.scroll_by(-page),
+            Some(Dir::Right) => error_state.scroll_by(page),
+            None => {},
         }
     }
 
@@ -732,19 +1387,35 @@ fn err_update(
 }
 
 #[inline]
-fn err_render(commands: &mut Commands, error_state: &ErrorState) {
-    let error = &error_state.error;
-
-    // TODO allow scrolling the text by allowing changing this.
-    let top_index_with_offset = 0;
-
+fn err_render(commands: &mut Commands, base_font: &sprite::Spec<BaseFont>, error_state: &ErrorState) {
     // TODO? Maybe cache this so we aren't allocating every frame?
-    let error_text = format!("{error}").to_lowercase();
+    let shown_lines: Vec<&[u8]> = text::byte_slice::lines(&error_state.text)
+        .skip(error_state.top_line)
+        .take(error_state.layout.rows)
+        .collect();
 
     commands.print_lines(
         <_>::default(),
-        top_index_with_offset,
-        error_text.as_bytes(),
+        0,
+        &shown_lines.join(&b'\n'),
+        6,
+    );
+
+    let footer = format!(
+        "lines {}-{} of {}  ^v<>: scroll  {}",
+        error_state.top_line + 1,
+        error_state.top_line + shown_lines.len(),
+        error_state.line_count,
+        if error_state.copied_to_log { "copied to log" } else { "select: copy to log" },
+    );
+
+    commands.print_lines(
+        unscaled::XY {
+            x: unscaled::X(0),
+            y: unscaled::Y(command::HEIGHT_SIGNED - base_font.tile().h.get()),
+        },
+        0,
+        footer.as_bytes(),
         6,
     );
 
@@ -758,6 +1429,80 @@ fn err_render(commands: &mut Commands, error_state: &ErrorState) {
     }
 }
 
+#[test]
+fn the_error_screen_wraps_long_lines_and_keeps_short_ones() {
+    let layout = ErrorLayout::new(&crate::Specs::default().base_font);
+    let long_line = "word ".repeat(layout.columns * layout.rows);
+    let path = format!("{long_line}\n  ^^ short");
+
+    let mut error_state = ErrorState::from(Error::Pak(pak::Error::EmptyFile(path.into())));
+    error_state.fit(layout);
+
+    assert!(error_state.line_count > layout.rows);
+    for line in text::byte_slice::lines(&error_state.text) {
+        assert!(line.len() <= layout.columns, "{}", String::from_utf8_lossy(line));
+    }
+    assert!(error_state.text.ends_with(b"\n  ^^ short is empty."));
+}
+
+#[test]
+fn the_error_screen_wraps_to_the_font_it_is_shown_in() {
+    let specs = crate::Specs::default();
+    let small = ErrorLayout::new(&specs.base_font);
+    let large_font: sprite::Spec<BaseFont> = sprite::spec(sprite::SpecPieces {
+        tile: specs.base_font.tile() + specs.base_font.tile(),
+        ..specs.base_font.pieces()
+    });
+    let large = ErrorLayout::new(&large_font);
+    assert!(large.columns < small.columns);
+    assert!(large.rows < small.rows);
+
+    let long_line = "word ".repeat(small.columns * small.rows);
+    let mut error_state = ErrorState::from(Error::Pak(pak::Error::EmptyFile(long_line.into())));
+
+    error_state.fit(small);
+    let small_line_count = error_state.line_count;
+    error_state.top_line = error_state.max_top_line();
+
+    error_state.fit(large);
+    assert!(error_state.line_count > small_line_count);
+    for line in text::byte_slice::lines(&error_state.text) {
+        assert!(line.len() <= large.columns, "{}", String::from_utf8_lossy(line));
+    }
+    assert!(error_state.top_line <= error_state.max_top_line());
+}
+
+#[test]
+fn the_error_screen_scrolls_within_the_text() {
+    let layout = ErrorLayout::new(&crate::Specs::default().base_font);
+    let long_line = "word ".repeat(layout.columns * layout.rows);
+
+    let mut error_state = ErrorState::from(Error::Pak(pak::Error::EmptyFile(long_line.into())));
+    error_state.fit(layout);
+    let mut speaker = <_>::default();
+
+    let press = |button| Input { gamepad: button, ..<_>::default() };
+
+    err_update(&mut error_state, press(Button::UP), &mut speaker);
+    assert_eq!(error_state.top_line, 0);
+
+    err_update(&mut error_state, press(Button::DOWN), &mut speaker);
+    assert_eq!(error_state.top_line, 1);
+
+    err_update(&mut error_state, press(Button::RIGHT), &mut speaker);
+    assert_eq!(error_state.top_line, 1 + layout.rows);
+
+    for _ in 0..error_state.line_count {
+        err_update(&mut error_state, press(Button::RIGHT), &mut speaker);
+    }
+    assert_eq!(error_state.top_line, error_state.line_count - layout.rows);
+
+    for _ in 0..error_state.line_count {
+        err_update(&mut error_state, press(Button::LEFT), &mut speaker);
+    }
+    assert_eq!(error_state.top_line, 0);
+}
+
 #[test]
 fn something_gets_drawn_for_ice_puzzle_alone() {
     let specs = crate::Specs::default();
@@ -766,9 +1511,9 @@ fn something_gets_drawn_for_ice_puzzle_alone() {
 
     let mut rng  = xs::from_seed(seed);
 
-    let mut state = ice_puzzle::State::new(&mut rng, &specs.ice_puzzles);
+    let mut state = ice_puzzle::State::new(&mut rng, &specs, <_>::default());
 
-    let mut commands = Commands::new(seed, specs.base_font, specs.base_ui);
+    let mut commands = Commands::new(seed, specs.base_font.clone(), specs.base_ui.clone());
     let input = <_>::default();
     let mut speaker = <_>::default();
 
@@ -776,7 +1521,7 @@ fn something_gets_drawn_for_ice_puzzle_alone() {
 
     state.update_and_render(
         &mut commands,
-        &specs.ice_puzzles,
+        &specs,
         input,
         &mut speaker,
     );
@@ -803,11 +1548,11 @@ fn something_gets_drawn_for_ice_puzzle_within_app_state() {
     let mut target = game::EntityKey::default();
     target.xy.x = models::xy::x(1);
 
-    state.game_state.as_mut().expect("should not be in an error state").mode = Mode::Hallway { source, target };
+    state.game_state.as_mut().expect("should not be in an error state").mode = Mode::Hallway { source, target, give_up_frames: 0 };
 
     let mut rng = xs::from_seed(seed);
 
-    state.game_state.as_mut().expect("should not be in an error state").hallway_states.insert(source, target, HallwayState::IcePuzzle(ice_puzzle::State::new(&mut rng, &specs.ice_puzzles)));
+    state.game_state.as_mut().expect("should not be in an error state").hallway_states.insert(source, target, game::HallwayState::IcePuzzle(ice_puzzle::State::new(&mut rng, &specs, <_>::default())));
 
     assert!(state.commands.slice().len() <= 0, "precondition failure");
 
@@ -817,8 +1562,8 @@ fn something_gets_drawn_for_ice_puzzle_within_app_state() {
     let mut count_of_20s = 0;
     let mut sizes = Vec::with_capacity(state.commands.slice().len());
     for command in state.commands.slice() {
-        let w = (command.rect.x_max.get() - command.rect.x_min.get()).get();
-        let h = (command.rect.y_max.get() - command.rect.y_min.get()).get();
+        let w = (command.rect().x_max - command.rect().x_min).get();
+        let h = (command.rect().y_max - command.rect().y_min).get();
         if w == 19 && h == 19 {
             count_of_20s += 1;
         }
@@ -829,3 +1574,244 @@ fn something_gets_drawn_for_ice_puzzle_within_app_state() {
     assert!(count_of_20s > 0, "{:#?}", sizes);
 }
 
+#[test]
+fn holding_select_gives_up_on_a_hallway() {
+    let specs = crate::Specs::default();
+
+    let seed = <_>::default();
+
+    let params = StateParams {
+        pak_loader: None,
+        logger: None,
+        error_logger: None,
+        seed,
+    };
+
+    let mut state = State::new(params);
+
+    let source = <_>::default();
+    let mut target = game::EntityKey::default();
+    target.xy.x = models::xy::x(1);
+
+    let mut rng = xs::from_seed(seed);
+
+    let game_state = state.game_state.as_mut().expect("should not be in an error state");
+    game_state.mode = Mode::Hallway { source, target, give_up_frames: 0 };
+    game_state.hallway_states.insert(source, target, game::HallwayState::IcePuzzle(ice_puzzle::State::new(&mut rng, &specs, <_>::default())));
+    let player_key = game_state.world.player_key();
+
+    // Letting go early should not count.
+    press(&mut state, Button::SELECT);
+    for _ in 1..game::GIVE_UP_HOLD_FRAMES {
+        frame(&mut state);
+    }
+    release(&mut state, Button::SELECT);
+    frame(&mut state);
+
+    assert!(
+        matches!(
+            state.game_state.as_ref().unwrap().mode,
+            Mode::Hallway { give_up_frames: 0, .. }
+        ),
+        "{:?}",
+        state.game_state.as_ref().unwrap().mode
+    );
+
+    press(&mut state, Button::SELECT);
+    for _ in 0..game::GIVE_UP_HOLD_FRAMES {
+        frame(&mut state);
+    }
+
+    let game_state = state.game_state.as_ref().unwrap();
+    assert!(matches!(game_state.mode, Mode::Walking), "{:?}", game_state.mode);
+    assert_eq!(game_state.world.player_key(), player_key);
+    assert!(game_state.hallway_states.get(source, target).is_some());
+}
+
+
+#[test]
+fn a_pak_that_fails_to_load_is_shown_with_the_default_spritesheet() {
+    fn broken() -> Option<PakSource> {
+        Some(PakSource::Packed(Box::new(std::io::Cursor::new(b"not a pak".to_vec()))))
+    }
+
+    let states = [Some(broken as fn() -> Option<PakSource>), None].map(|pak_loader| State::new(StateParams {
+        pak_loader,
+        logger: None,
+        error_logger: None,
+        seed: [3; 16],
+    }));
+
+    assert!(states[0].game_state.is_err());
+    assert_eq!(states[0].spritesheets, states[1].spritesheets);
+}
+
+#[test]
+fn a_pak_directory_loads_the_same_as_the_packed_pak() {
+    fn packed() -> Option<PakSource> {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/default/default.pak");
+
+        Some(PakSource::Packed(Box::new(std::fs::File::open(path).ok()?)))
+    }
+
+    fn dir() -> Option<PakSource> {
+        Some(PakSource::Dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/default").into()))
+    }
+
+    let states = [packed as fn() -> Option<PakSource>, dir].map(|pak_loader| State::new(StateParams {
+        pak_loader: Some(pak_loader),
+        logger: None,
+        error_logger: None,
+        seed: [3; 16],
+    }));
+
+    for state in &states {
+        if let Err(error_state) = &state.game_state {
+            panic!("{}", error_state.error);
+        }
+    }
+
+    assert_eq!(states[0].spritesheets, states[1].spritesheets);
+}
+
+#[test]
+fn reloading_the_pak_does_not_overwrite_the_save() {
+    let (_, mut state) = record_some_inputs();
+
+    // Standing in for the save file.
+    let mut saved = save(&state).expect("should have a game to save").unwrap();
+    let before_reload = saved.clone();
+
+    fn autosave(saved: &mut Vec<u8>, state: &State) {
+        if let Some(result) = save(state) {
+            *saved = result.unwrap();
+        }
+    }
+
+    state = reload(&state);
+    for _ in 0..10 {
+        frame(&mut state);
+        autosave(&mut saved, &state);
+    }
+
+    assert!(saved == before_reload, "the fresh game was saved over the old one");
+
+    press(&mut state, Button::RIGHT);
+    frame(&mut state);
+    autosave(&mut saved, &state);
+
+    assert!(saved != before_reload, "saving should resume once the player does something");
+}
+
+#[cfg(test)]
+fn replay_test_params() -> StateParams {
+    StateParams {
+        pak_loader: None,
+        logger: None,
+        error_logger: None,
+        seed: [42; 16],
+    }
+}
+
+#[cfg(test)]
+fn record_some_inputs() -> (replay::Recording, State) {
+    let mut state = State::new(replay_test_params());
+
+    let mut recording = replay::Recording::new(&state);
+
+    for (button, frames_held) in [
+        (Button::RIGHT, 20),
+        (Button::DOWN, 3),
+        (Button::A, 1),
+        (Button::LEFT, 7),
+        (Button::UP, 30),
+        (Button::B, 2),
+    ] {
+        recording.press(&mut state, button);
+        for _ in 0..frames_held {
+            frame(&mut state);
+            recording.end_frame();
+        }
+        recording.release(&mut state, button);
+        frame(&mut state);
+        recording.end_frame();
+    }
+
+    (recording, state)
+}
+
+#[test]
+fn replays_round_trip_through_bytes() {
+    let (recording, _) = record_some_inputs();
+
+    let bytes = recording.to_bytes();
+
+    assert_eq!(replay::Recording::from_bytes(&bytes).unwrap(), recording);
+}
+
+#[test]
+fn replaying_a_recording_reproduces_the_recorded_state() {
+    let (recording, recorded_state) = record_some_inputs();
+
+    let replayed_state = replay::run(&recording, replay_test_params()).unwrap();
+
+    let recorded = recorded_state.game_state.as_ref().expect("should not be in an error state");
+    let replayed = replayed_state.game_state.as_ref().expect("should not be in an error state");
+
+    assert_eq!(replayed.rng, recorded.rng);
+    assert_eq!(replayed.world.player_key(), recorded.world.player_key());
+    assert_eq!(format!("{:?}", replayed.mode), format!("{:?}", recorded.mode));
+}
+
+#[test]
+fn replaying_a_recording_includes_events_after_the_last_frame() {
+    let (mut recording, mut recorded_state) = record_some_inputs();
+    recording.press(&mut recorded_state, Button::A);
+
+    let replayed_state = replay::run(&recording, replay_test_params()).unwrap();
+
+    assert_eq!(replayed_state.input.gamepad, recorded_state.input.gamepad);
+    assert!(replayed_state.input.gamepad.contains(Button::A));
+}
+
+#[test]
+fn replaying_with_a_different_pak_is_an_error() {
+    let (mut recording, _) = record_some_inputs();
+    recording.pak_hash = !recording.pak_hash;
+
+    assert!(matches!(
+        replay::run(&recording, replay_test_params()),
+        Err(replay::Error::PakMismatch { .. })
+    ));
+}
+
+/// Recordings of bugs that have been fixed can be dropped into the `replays` folder
+/// next to this crate's `Cargo.toml`, to make sure they stay fixed. Recordings made
+/// with a pak other than the built-in one are skipped.
+#[test]
+fn replays_in_the_replays_folder_do_not_end_in_an_error() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/replays");
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        // No replays yet.
+        return
+    };
+
+    for entry in entries {
+        let path = entry.unwrap().path();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let recording = replay::Recording::from_bytes(&bytes)
+            .unwrap_or_else(|e| panic!("{}: {e}", path.display()));
+
+        match replay::run(&recording, replay_test_params()) {
+            Ok(state) => {
+                if let Err(error_state) = state.game_state {
+                    panic!("{}: {}", path.display(), error_state.error);
+                }
+            },
+            Err(replay::Error::PakMismatch { .. }) => continue,
+            Err(e) => panic!("{}: {e}", path.display()),
+        }
+    }
+}
@@ -4,37 +4,42 @@
 /// Boulders Often Lope Downwards
 
 use gfx::{Commands, AddDrawCommands};
-use platform_types::{sprite, unscaled, Button, Dir, Input, Speaker};
+use hallway::Hallway;
+use platform_types::{config::BOLDParams, sprite, unscaled, Button, Dir, Input, Speaker};
 use vec1::{Grid1, Grid1Spec, vec1};
 use xs::{Seed, Xs};
 
+use serde::{Deserialize, Serialize};
+
 use std::collections::BTreeMap;
 use std::num::TryFromIntError;
 
 type Index = usize;
 
 pub mod xy {
+    use serde::{Deserialize, Serialize};
+
     pub type Inner = u16;
     /// A signed type large enough to hold the difference between two Inner
     /// values.
     pub type Diff = i32;
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct X(pub Inner);
 
     pub fn x(inner: Inner) -> X { X(inner) }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct Y(pub Inner);
 
     pub fn y(inner: Inner) -> Y { Y(inner) }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct W(pub Inner);
 
     pub fn w(inner: Inner) -> W { W(inner) }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct H(pub Inner);
 
     pub fn h(inner: Inner) -> H { H(inner) }
@@ -130,13 +135,13 @@ pub mod xy {
         Y, H
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct XY {
         pub x: X,
         pub y: Y,
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct WH {
         pub w: W,
         pub h: H,
@@ -311,12 +316,13 @@ mod exit_animation {
         TileSprite,
         EXIT_BASE,
     };
+    use serde::{Deserialize, Serialize};
 
     type StateInner = u8;
 
     const STATE_MAX: StateInner = 6;
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct State(StateInner);
 
     impl State {
@@ -350,12 +356,13 @@ mod gem_animation {
         Xs,
         GEM_BASE,
     };
+    use serde::{Deserialize, Serialize};
 
     type StateInner = u16;
 
     const STATE_MAX: StateInner = 180;
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct State(StateInner);
 
     pub fn gen_state(rng: &mut Xs) -> State {
@@ -398,6 +405,7 @@ mod player_animation {
         LIFT_CLOSED,
         THUMP,
     };
+    use serde::{Deserialize, Serialize};
 
     type IdleState = u8;
 
@@ -412,7 +420,7 @@ mod player_animation {
         16, 17, 18, 19, 20, 21, 22,
     ];
 
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
     pub enum State {
         Idle(IdleState),
         Left(DirState),
@@ -675,7 +683,7 @@ fn xy_in_dir(xy: XY, dir: Dir) -> (XY, EdgeHitKind) {
     )
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Entity {
     pub tile_sprite: TileSprite,
     pub gem_animation_state: gem_animation::State,
@@ -725,7 +733,7 @@ pub type Key = XY;
 mod mobs {
     use super::*;
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Mobs {
         entities: BTreeMap<Key, Entity>,
     }
@@ -767,15 +775,16 @@ mod mobs {
 }
 use mobs::Mobs;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Collection {
     pub current: GemCount,
     pub target: GemCount,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct State {
     pub seed: Seed, // For restarting
+    pub params: BOLDParams, // Also for restarting
     pub rng: Xs,
     pub tiles: Tiles,
     pub mobs: Mobs,
@@ -787,13 +796,13 @@ pub struct State {
 }
 
 impl State {
-    pub fn new(rng: &mut Xs, specs: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, specs: &sprite::Specs, params: BOLDParams) -> Self {
         let seed = xs::new_seed(rng);
 
-        Self::init(seed, specs)
+        Self::init(seed, specs, params)
     }
 
-    fn init(seed: Seed, specs: &sprite::Specs) -> Self {
+    fn init(seed: Seed, specs: &sprite::Specs, params: BOLDParams) -> Self {
         let bold_spec = &specs.bold;
 
         let mut rng_ = xs::from_seed(seed);
@@ -801,20 +810,15 @@ impl State {
 
         let (mut max_tile_w, mut max_tile_h) = bold_spec.max_tile_counts();
 
-        if max_tile_w == 0 {
+        if max_tile_w <= 0 {
             max_tile_w = 1;
         }
-        if max_tile_h == 0 {
+        if max_tile_h <= 0 {
             max_tile_h = 1;
         }
 
-        if max_tile_w > xy::Inner::MAX as _ {
-            max_tile_w = xy::Inner::MAX as _;
-        }
-        if max_tile_h > xy::Inner::MAX as _ {
-            max_tile_h = xy::Inner::MAX as _;
-        }
-
+        // Any positive i16 fits in an xy::Inner. Note that `xy::Inner::MAX as i16` is -1,
+        // so comparing against that would clamp every size.
         let max_tile_w = max_tile_w as xy::Inner;
         let max_tile_h = max_tile_h as xy::Inner;
 
@@ -849,9 +853,13 @@ impl State {
 
         let mut gems_placed: GemCount = 0;
 
+        let boulder_percent = u32::from(params.boulder_percent);
+        let mob_percent = boulder_percent + u32::from(params.gem_percent);
+
         for y in 0..max_tile_h {
             for x in 0..max_tile_w {
-                if xs::range(rng, 0..4) > 0 {
+                let roll = xs::range(rng, 0..100);
+                if roll >= mob_percent {
                     continue
                 }
 
@@ -869,7 +877,7 @@ impl State {
                     continue
                 }
 
-                if xs::range(rng, 0..2) > 0 {
+                if roll >= boulder_percent {
                     mobs.insert(
                         xy,
                         Entity {
@@ -878,7 +886,7 @@ impl State {
                             exit_animation_state: <_>::default(),
                         }
                     );
-                    gems_placed += 1;
+                    gems_placed = gems_placed.saturating_add(1);
                 } else {
                     mobs.insert(
                         xy,
@@ -1020,6 +1028,7 @@ impl State {
 
         Self {
             seed,
+            params,
             rng: rng_,
             tiles,
             mobs,
@@ -1035,7 +1044,7 @@ impl State {
     }
 
     fn restart(&mut self, bold_spec: &sprite::Specs) {
-        *self = Self::init(self.seed, bold_spec);
+        *self = Self::init(self.seed, bold_spec, self.params);
     }
 
     pub fn is_complete(&self) -> bool {
@@ -1302,4 +1311,34 @@ impl State {
             6
         );
     }
+}
+
+impl Hallway for State {
+    type Params = BOLDParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn reset(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+
+    fn give_up(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
 }
\ No newline at end of file
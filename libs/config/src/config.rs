@@ -8,55 +8,132 @@ pub use used_mod::{parse, parse_manifest, Error};
 mod rune_based {
     use models::{
         config::{
+            BOLDParams,
             Config,
+            CubeMazeParams,
+            HallwaySpec,
+            HexHopParams,
+            HexTwiddleParams,
+            IcePuzzleParams,
+            KeycardShuffleParams,
             Manifest,
+            Metadata,
+            MusicSpec,
+            SoundBankSpec,
+            SpheresSpec,
+            SpritesheetSpec,
+            SWORDParams,
+            TrackSpec,
+            Version,
+            VersionRange,
             WorldSegment,
         },
         consts::{TileFlags},
-        sprite::{self, H, W, WH},
+        script,
+        sprite::{self, unscaled, SpecPieces, H, W, WH},
         DefId,
         DefIdDelta,
+        Scripts,
         Specs,
         SpeechesList,
     };
     use vec1::Vec1;
 
     use std::path::PathBuf;
-    use rune::{alloc::{Error as AllocError}, BuildError, Context, ContextError, Diagnostics, Source, Sources, Value, Vm};
+    use rune::{alloc::{Error as AllocError}, BuildError, Context, ContextError, Diagnostics, Module, Source, Sources, Value, Vm};
     use rune::diagnostics::{Diagnostic};
-    use rune::runtime::{Object, RuntimeError, VmError};
+    use rune::runtime::{Object, RuntimeContext, RuntimeError, Unit, VmError};
     use rune::sync::Arc;
+    use xs::Seed;
 
-    pub fn parse(code: &str) -> Result<Config, Error> {
-        let map: Object = eval(code)?;
+    /// `seed` is used to seed the RNG in the `generation` module, so the config
+    /// can differ from run to run, while staying the same for a given seed.
+    pub fn parse(code: &str, seed: Seed) -> Result<Config, Error> {
+        let (map, scripts) = eval_with_scripts(code, Some(seed))?;
 
-        to_config(map)
+        let mut config = to_config(map).map_err(|e| e.at_value_in(code))?;
+
+        scripts.check_names(&config).map_err(|e| e.at_value_in(code))?;
+
+        config.scripts = Scripts::new(std::sync::Arc::new(scripts));
+
+        Ok(config)
     }
 
     pub fn parse_manifest(code: &str) -> Result<Manifest, Error> {
-        let map: Object = eval(code)?;
+        // The manifest decides which files get packed, so it should not depend
+        // on the seed. So there is no `generation` module for it to use.
+        let map: Object = eval(code, None)?;
 
-        to_manifest(map)
+        to_manifest(map).map_err(|e| e.at_value_in(code))
     }
 
+    /// The path to a value in a config or manifest, like `entities[4].wants[0]`.
     #[derive(Clone, Copy, Debug)]
     pub struct IndexableKey {
-        pub key: &'static str,
-        pub index: Option<usize>,
+        segments: [KeySegment; IndexableKey::MAX_DEPTH],
+        len: u8,
+    }
+
+    #[derive(Clone, Copy, Debug, Default)]
+    struct KeySegment {
+        key: &'static str,
+        index: Option<usize>,
+    }
+
+    impl IndexableKey {
+        /// Deeper than any path we currently read from a config or manifest.
+        const MAX_DEPTH: usize = 8;
+
+        /// The path of the object `main` returns.
+        pub const ROOT: Self = Self {
+            segments: [KeySegment { key: "", index: None }; Self::MAX_DEPTH],
+            len: 0,
+        };
+
+        pub fn new(key: &'static str, index: Option<usize>) -> Self {
+            Self::ROOT.then_segment(KeySegment { key, index })
+        }
+
+        /// The path to `child`, taken as relative to this path.
+        pub fn then(self, child: impl Into<IndexableKey>) -> Self {
+            let child = child.into();
+
+            let mut output = self;
+            for &segment in child.segments() {
+                output = output.then_segment(segment);
+            }
+
+            output
+        }
+
+        /// The path to the element of the array at this path at `index`.
+        pub fn nth(self, index: usize) -> Self {
+            self.then_segment(KeySegment { key: "", index: Some(index) })
+        }
+
+        fn then_segment(mut self, segment: KeySegment) -> Self {
+            let len = usize::from(self.len);
+            debug_assert!(len < Self::MAX_DEPTH, "IndexableKey::MAX_DEPTH should be raised");
+            if len < Self::MAX_DEPTH {
+                self.segments[len] = segment;
+                self.len += 1;
+            }
+
+            self
+        }
+
+        fn segments(&self) -> &[KeySegment] {
+            &self.segments[..usize::from(self.len)]
+        }
     }
 
     macro_rules! ik {
         ($key: expr) => {
-            IndexableKey {
-                key: $key,
-                index: None,
-            }
+            IndexableKey::new($key, None)
         };
         ($key: expr ,$index: expr) => {
-            IndexableKey {
-                key: $key,
-                index: Some($index),
-            }
+            IndexableKey::new($key, Some($index))
         };
     }
 
@@ -68,24 +145,35 @@ mod rune_based {
 
     impl core::fmt::Display for IndexableKey {
         fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-            write!(f, "{}", self.key)?;
+            if self.len == 0 {
+                return write!(f, "#root");
+            }
 
-            if let Some(index) = self.index {
-                write!(f, "[{index}]")
-            } else {
-                Ok(())
+            for (i, KeySegment { key, index }) in self.segments().iter().enumerate() {
+                if i > 0 && !key.is_empty() {
+                    write!(f, ".")?;
+                }
+
+                write!(f, "{key}")?;
+
+                if let Some(index) = index {
+                    write!(f, "[{index}]")?;
+                }
             }
+
+            Ok(())
         }
     }
 
     #[derive(Debug)]
-    pub enum Error {
+    pub enum ErrorKind {
         Alloc(AllocError),
         Build(BuildError),
         Context(ContextError),
         Diagnostics(Vec<Diagnostic>, Sources),
         Runtime(RuntimeError),
-        Vm(VmError),
+        /// The sources are kept so the error can point at the line that caused it.
+        Vm(VmError, Sources),
         /// An error the configuration evaluation itself returned.
         FromConfig(String),
         TypeMismatch {
@@ -98,11 +186,7 @@ mod rune_based {
             parent_key: IndexableKey,
         },
         UnexpectedTileKind {
-            index: usize,
-            got: u64,
-        },
-        UnexpectedEntityKind {
-            index: usize,
+            key: IndexableKey,
             got: u64,
         },
         SizeError {
@@ -140,38 +224,126 @@ mod rune_based {
             kind: models::consts::HallwayKind,
         },
         EmptyList{
+            key: IndexableKey,
+            parent_key: IndexableKey,
+        },
+        ParamOutOfRange{
+            key: &'static str,
+            parent_key: IndexableKey,
+            got: i64,
+            min: i64,
+            max: i64,
+        },
+        HallwayParamNotOdd{
+            key: &'static str,
+            parent_key: IndexableKey,
+            got: i64,
+        },
+        HallwayParamsSumTooLarge{
+            keys: [&'static str; 2],
+            parent_key: IndexableKey,
+            sum: i64,
+            max: i64,
+        },
+        ParamMinGreaterThanMax{
+            keys: [&'static str; 2],
+            parent_key: IndexableKey,
+            min: i64,
+            max: i64,
+        },
+        InvalidVersion{
+            key: &'static str,
+            parent_key: IndexableKey,
+            got: String,
+        },
+        UnknownSpritesheet{
+            key: &'static str,
+            parent_key: IndexableKey,
+            name: String,
+        },
+        DuplicateSpritesheet{
+            key: &'static str,
+            parent_key: IndexableKey,
+            name: String,
+        },
+        TooManySpritesheets{ got: usize },
+        UnknownScript{
             key: &'static str,
             parent_key: IndexableKey,
+            name: String,
+        },
+        UnknownScriptActionKind {
+            key: &'static str,
+            parent_key: IndexableKey,
+            kind: models::consts::ScriptActionKind,
         },
     }
 
-    impl core::fmt::Display for Error {
+    /// Lets rune emit its diagnostics, with their source spans, into a `Formatter`.
+    struct FormatterWriter<'mut_ref, 'formatter>(&'mut_ref mut core::fmt::Formatter<'formatter>);
+
+    impl std::io::Write for FormatterWriter<'_, '_> {
+        fn write(&mut self, bytes: &[u8]) -> Result<usize, std::io::Error> {
+            let len = bytes.len();
+            let s: &str = std::str::from_utf8(bytes).map_err(std::io::Error::other)?;
+            self.0.write_str(s).map(|()| len).map_err(std::io::Error::other)
+        }
+        fn flush(&mut self) -> Result<(), std::io::Error> {
+            Ok(())
+        }
+    }
+
+    impl rune::termcolor::WriteColor for FormatterWriter<'_, '_> {
+        fn supports_color(&self) -> bool { false }
+        fn set_color(&mut self, _spec: &rune::termcolor::ColorSpec) -> Result<(), std::io::Error> { Ok(()) }
+        fn reset(&mut self) -> Result<(), std::io::Error> { Ok(()) }
+    }
+
+    /// Shows a `VmError` with the line it happened on, when rune can manage that.
+    struct VmErrorDisplay<'error>(&'error VmError, &'error Sources);
+
+    impl core::fmt::Display for VmErrorDisplay<'_> {
         fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-            use Error::*;
-            match self {
-                Diagnostics(diagnostics, sources) => {
-                    use std::io::Write;
-                    use rune::termcolor::{ColorSpec, WriteColor};
-                    struct Wrapper<'mut_ref, 'formatter>(&'mut_ref mut core::fmt::Formatter<'formatter>);
-
-                    impl Write for Wrapper<'_, '_> {
-                        fn write(&mut self, bytes: &[u8]) -> Result<usize, std::io::Error> {
-                            let len = bytes.len();
-                            let s: &str = std::str::from_utf8(bytes).map_err(std::io::Error::other)?;
-                            self.0.write_str(s).map(|()| len).map_err(std::io::Error::other)
-                        }
-                        fn flush(&mut self) -> Result<(), std::io::Error> {
-                            Ok(())
-                        }
-                    }
+            let Self(e, sources) = self;
 
-                    impl WriteColor for Wrapper<'_, '_> {
-                        fn supports_color(&self) -> bool { false }
-                        fn set_color(&mut self, _spec: &ColorSpec) -> Result<(), std::io::Error> { Ok(()) }
-                        fn reset(&mut self) -> Result<(), std::io::Error> { Ok(()) }
-                    }
+            match e.emit(&mut FormatterWriter(f), sources) {
+                Ok(()) => Ok(()),
+                Err(_) => write!(f, " The Rune code failed while running:\n  {e}"),
+            }
+        }
+    }
+
+    /// Writes the names of the helper module constants that are allowed, like `hallways::NONE`.
+    fn write_allowed<T>(
+        f: &mut core::fmt::Formatter<'_>,
+        module: &str,
+        allowed: &[(&str, T)],
+    ) -> core::fmt::Result {
+        for (i, (name, _)) in allowed.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{module}::{name}")?;
+        }
+
+        Ok(())
+    }
 
-                    let write_wrapper = &mut Wrapper(f);
+    impl core::fmt::Display for ErrorKind {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use ErrorKind::*;
+            match self {
+                Alloc(e) => {
+                    write!(f, " Rune ran out of memory:\n  {e}")
+                }
+                Build(e) => {
+                    write!(f, " The Rune code could not be compiled:\n  {e}")
+                }
+                Context(e) => {
+                    write!(f, " The Rune context could not be set up:\n  {e}")
+                }
+                Diagnostics(diagnostics, sources) => {
+                    let write_wrapper = &mut FormatterWriter(f);
 
                     for diagnostic in diagnostics {
                         match {
@@ -193,108 +365,689 @@ mod rune_based {
                     Ok(())
                 },
                 Runtime(e) => {
-                    write!(f, " Rune RuntimeError:\n  {e}")
+                    write!(f, " main should return Ok with an object, or Err with a string, but did not:\n  {e}")
                 }
-                Vm(e) => {
-                    write!(f, " Rune VmError:\n  {e}")
+                Vm(e, sources) => {
+                    write!(f, "{}", VmErrorDisplay(e, sources))
                 }
                 FromConfig(e) => {
-                    write!(f, " FromConfig Error:\n  {e}")
+                    write!(f, " main returned an error:\n  {e}")
                 }
                 TypeMismatch {
                     key,
                     expected,
                     got,
                 } => {
-                    write!(f, " TypeMismatch @ {key}, expected {expected}:\n  {got}")
+                    write!(f, " {key} should be of type {expected}, but was not:\n  {got}")
+                }
+                FieldMissing {
+                    key,
+                    parent_key,
+                } => {
+                    write!(f, " {} is required, but is missing", parent_key.then(*key))
+                }
+                UnexpectedTileKind {
+                    key,
+                    got,
+                } => {
+                    write!(f, " {key} was {got}, which is not a valid set of tile flags")
+                }
+                SizeError {
+                    key,
+                    parent_key,
+                    error,
+                } => {
+                    write!(f, " {} was out of range:\n  {error}", parent_key.then(*key))
+                }
+                TooManyEntityDefinitions { got } => {
+                    write!(f, " entities had {got} entries, but at most {} are allowed", DefId::MAX)
+                }
+                NoSegmentsFound => {
+                    write!(f, " segments must have at least one entry")
+                }
+                NoEntitiesFound => {
+                    write!(f, " entities must have at least one entry")
+                }
+                OutOfBoundsDefId {
+                    key,
+                    parent_key,
+                    def_id,
+                } => {
+                    write!(f, " {} refers to entity {def_id}, but there is no entity with that index in entities", parent_key.then(*key))
+                }
+                UnknownEntityDefIdRefKind {
+                    key,
+                    parent_key,
+                    kind,
+                } => {
+                    write!(f, " {} was {kind}, but must be one of ", parent_key.then(*key))?;
+                    write_allowed(f, "entity_ids", &models::consts::ALL_ENTITY_ID_REFERENCE_KINDS)
+                }
+                UnknownCollectActionKind {
+                    key,
+                    parent_key,
+                    kind,
+                } => {
+                    write!(f, " {} was {kind}, but must be one of ", parent_key.then(*key))?;
+                    write_allowed(f, "collect_actions", &models::consts::ALL_COLLECT_ACTION_KINDS)
+                }
+                DefIdOverflow {
+                    key,
+                    parent_key,
+                    base,
+                    delta,
+                } => {
+                    write!(f, " {} was {delta}, which is outside the possible entity indexes when added to {base}", parent_key.then(*key))
+                }
+                UnknownHallwayKind {
+                    key,
+                    parent_key,
+                    kind,
+                } => {
+                    write!(f, " {} was {kind}, but must be one of ", parent_key.then(*key))?;
+                    write_allowed(f, "hallways", &models::consts::ALL_HALLWAY_KINDS)
+                }
+                EmptyList {
+                    key,
+                    parent_key,
+                } => {
+                    write!(f, " {} must have at least one entry", parent_key.then(*key))
+                }
+                ParamOutOfRange {
+                    key,
+                    parent_key,
+                    got,
+                    min,
+                    max,
+                } => {
+                    write!(f, " {} was {got}, but must be between {min} and {max}, inclusive", parent_key.then(*key))
+                }
+                HallwayParamNotOdd {
+                    key,
+                    parent_key,
+                    got,
+                } => {
+                    write!(f, " {} was {got}, but must be odd", parent_key.then(*key))
+                }
+                HallwayParamsSumTooLarge {
+                    keys: [key_a, key_b],
+                    parent_key,
+                    sum,
+                    max,
+                } => {
+                    write!(f, " {} + {} was {sum}, but must be at most {max}", parent_key.then(*key_a), parent_key.then(*key_b))
+                }
+                ParamMinGreaterThanMax {
+                    keys: [min_key, max_key],
+                    parent_key,
+                    min,
+                    max,
+                } => {
+                    write!(f, " {} was {min}, but must not be greater than {}, which was {max}", parent_key.then(*min_key), parent_key.then(*max_key))
+                }
+                InvalidVersion {
+                    key,
+                    parent_key,
+                    got,
+                } => {
+                    write!(f, " {} was {got:?}, but must be a version like \"1.2.3\"", parent_key.then(*key))
+                }
+                UnknownSpritesheet {
+                    key,
+                    parent_key,
+                    name,
+                } => {
+                    write!(f, " {} was {name:?}, but no spritesheet by that name is listed in spritesheets", parent_key.then(*key))
+                }
+                DuplicateSpritesheet {
+                    key,
+                    parent_key,
+                    name,
+                } => {
+                    write!(f, " {} was {name:?}, but an earlier spritesheet already has that name", parent_key.then(*key))
+                }
+                TooManySpritesheets { got } => {
+                    write!(f, " spritesheets had {got} entries, but at most {MAX_SPRITESHEETS} are allowed")
+                }
+                UnknownScript {
+                    key,
+                    parent_key,
+                    name,
+                } => {
+                    write!(f, " {} was {name:?}, but there is no function by that name in the config", parent_key.then(*key))
+                }
+                UnknownScriptActionKind {
+                    key,
+                    parent_key,
+                    kind,
+                } => {
+                    write!(f, " {} was {kind}, but must be one of ", parent_key.then(*key))?;
+                    write_allowed(f, "script_actions", &models::consts::ALL_SCRIPT_ACTION_KINDS)
                 }
-                // TODO implement proper human readable display here
-                _ => write!(f, " fmt::Debug Fallback:\n  {self:#?}"),
             }
         }
     }
 
-    impl std::error::Error for Error {
-        // TODO impl source when we have a need for that?
+    impl std::error::Error for ErrorKind {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            use ErrorKind::*;
+            match self {
+                Alloc(e) => {
+                    Some(e)
+                },
+                Build(e) => {
+                    Some(e)
+                },
+                Context(e) => {
+                    Some(e)
+                },
+                Runtime(e) => {
+                    Some(e)
+                },
+                Vm(e, _) => {
+                    Some(e)
+                },
+                TypeMismatch { got, .. } => {
+                    Some(got)
+                },
+                SizeError { error, .. } => {
+                    Some(error)
+                },
+                Diagnostics(..)
+                | FromConfig(_)
+                | FieldMissing { .. }
+                | UnexpectedTileKind { .. }
+                | TooManyEntityDefinitions { .. }
+                | NoSegmentsFound
+                | NoEntitiesFound
+                | OutOfBoundsDefId { .. }
+                | UnknownEntityDefIdRefKind { .. }
+                | UnknownCollectActionKind { .. }
+                | DefIdOverflow { .. }
+                | UnknownHallwayKind { .. }
+                | EmptyList { .. }
+                | ParamOutOfRange { .. }
+                | HallwayParamNotOdd { .. }
+                | HallwayParamsSumTooLarge { .. }
+                | ParamMinGreaterThanMax { .. }
+                | InvalidVersion { .. }
+                | UnknownSpritesheet { .. }
+                | DuplicateSpritesheet { .. }
+                | TooManySpritesheets { .. }
+                | UnknownScript { .. }
+                | UnknownScriptActionKind { .. } => {
+                    None
+                },
+            }
+        }
     }
 
-    impl From<AllocError> for Error {
-        fn from(e: AllocError) -> Self {
-            Self::Alloc(e)
+    impl ErrorKind {
+        /// The path to the value this error is about, if it is about one.
+        fn key_path(&self) -> Option<IndexableKey> {
+            use ErrorKind::*;
+            match self {
+                TypeMismatch { key, .. }
+                | UnexpectedTileKind { key, .. } => {
+                    Some(*key)
+                },
+                SizeError { key, parent_key, .. }
+                | EmptyList { key, parent_key } => {
+                    Some(parent_key.then(*key))
+                },
+                OutOfBoundsDefId { key, parent_key, .. }
+                | UnknownEntityDefIdRefKind { key, parent_key, .. }
+                | UnknownCollectActionKind { key, parent_key, .. }
+                | DefIdOverflow { key, parent_key, .. }
+                | UnknownHallwayKind { key, parent_key, .. }
+                | ParamOutOfRange { key, parent_key, .. }
+                | HallwayParamNotOdd { key, parent_key, .. }
+                | InvalidVersion { key, parent_key, .. }
+                | UnknownSpritesheet { key, parent_key, .. }
+                | DuplicateSpritesheet { key, parent_key, .. }
+                | UnknownScript { key, parent_key, .. }
+                | UnknownScriptActionKind { key, parent_key, .. } => {
+                    Some(parent_key.then(*key))
+                },
+                // There is no missing field to point at, and the two params
+                // are only wrong together, so the object they belong in is used.
+                FieldMissing { parent_key, .. }
+                | HallwayParamsSumTooLarge { parent_key, .. }
+                | ParamMinGreaterThanMax { parent_key, .. } => {
+                    Some(*parent_key)
+                },
+                TooManyEntityDefinitions { .. }
+                | NoEntitiesFound => {
+                    Some(ik!("entities"))
+                },
+                NoSegmentsFound => {
+                    Some(ik!("segments"))
+                },
+                TooManySpritesheets { .. } => {
+                    Some(ik!("spritesheets"))
+                },
+                Alloc(_)
+                | Build(_)
+                | Context(_)
+                | Diagnostics(..)
+                | Runtime(_)
+                | Vm(..)
+                | FromConfig(_) => {
+                    None
+                },
+            }
         }
     }
 
-    impl From<BuildError> for Error {
-        fn from(e: BuildError) -> Self {
-            Self::Build(e)
-        }
+    /// Boxed, because the key paths and Rune sources that `ErrorKind` holds would
+    /// otherwise make every `Result` that might hold one large.
+    #[derive(Debug)]
+    pub struct Error {
+        kind: Box<ErrorKind>,
+        /// The line the value this error is about was written on, if it was found.
+        line: Option<Box<SourceLine>>,
     }
 
-    impl From<ContextError> for Error {
-        fn from(e: ContextError) -> Self {
-            Self::Context(e)
+    impl Error {
+        pub fn kind(&self) -> &ErrorKind {
+            &self.kind
+        }
+
+        /// Points this error at the line of `code` that the value it is about
+        /// was written on. Rune values do not keep track of where they were
+        /// written, so the object field or array element at the end of the
+        /// error's key path is looked for in `code` instead. If no such place, or
+        /// more than one equally likely place, is found, the error is left as is.
+        fn at_value_in(mut self, code: &str) -> Self {
+            if self.line.is_none()
+            && let Some(key) = self.kind.key_path()
+            && let Some(offset) = value_offset(code, key) {
+                self.line = Some(Box::new(SourceLine::at(code, offset)));
+            }
+
+            self
         }
     }
 
-    impl From<VmError> for Error {
-        fn from(e: VmError) -> Self {
-            Self::Vm(e)
+    impl From<ErrorKind> for Error {
+        fn from(kind: ErrorKind) -> Self {
+            Self {
+                kind: Box::new(kind),
+                line: None,
+            }
         }
     }
 
-    macro_rules! to_int {
-        ($val: expr, $key: expr, $parent_key: expr $(,)?) => ({
-            let key = $key;
-            let parent_key = $parent_key.into();
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            self.kind.fmt(f)?;
 
-            let int: i64 =
-                $val.as_integer().map_err(|got| Error::TypeMismatch{ key, expected: "int", got })?;
+            if let Some(line) = &self.line {
+                write!(f, "{line}")?;
+            }
 
-            int.try_into().map_err(|error| Error::SizeError {
-                key,
-                parent_key,
-                error,
-            })?
-        })
+            Ok(())
+        }
     }
 
-    macro_rules! get_int {
-        ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
-            {
-                let key = $key;
-                let parent_key = $parent_key.into();
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            self.kind.source()
+        }
+    }
 
-                let value: &Value =
-                    $map.get(key)
-                        .ok_or(Error::FieldMissing{ key, parent_key, })?;
+    /// A line of Rune source, and the column a value starts at on it.
+    #[derive(Debug)]
+    struct SourceLine {
+        number: usize,
+        column: usize,
+        text: String,
+    }
 
-                to_int!(
-                    value,
-                    ik!(key),
-                    parent_key,
-                )
+    impl SourceLine {
+        /// The line `offset`, a byte offset into `code`, is on.
+        fn at(code: &str, offset: usize) -> Self {
+            let start = code[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let end = code[offset..].find('\n').map_or(code.len(), |i| offset + i);
+
+            Self {
+                number: code[..offset].matches('\n').count() + 1,
+                column: code[start..offset].chars().count() + 1,
+                text: code[start..end].trim_end().to_owned(),
             }
         }
     }
 
-    fn to_config(map: Object) -> Result<Config, Error> {
-        use rune::runtime::{BorrowRef, Object};
-        use rune::{Value};
-        use models::{
-            consts::{EntityDefIdRefKind, CollectActionKind},
-            CollectAction,
-            DefId,
-            EntityDef,
-            SegmentWidth,
-            Speech
-        };
+    impl core::fmt::Display for SourceLine {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            let Self { number, column, text } = self;
 
-        macro_rules! convert_to {
-            ($from: expr => $to: ty, $key: expr, $parent_key: expr $(,)?) => {
-                <$to>::try_from($from).map_err(|error| Error::SizeError {
-                    key: $key.into(),
-                    parent_key: $parent_key,
-                    error,
+            // Tabs are kept so the marker lines up however wide they are shown.
+            let padding: String = text.chars()
+                .take(column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            write!(f, "\n  at line {number}, column {column}:\n    {text}\n    {padding}^")
+        }
+    }
+
+    /// A step along the path to a value, as found in Rune source.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum PathStep<'key> {
+        Key(&'key str),
+        Index(usize),
+    }
+
+    impl IndexableKey {
+        fn steps(&self) -> Vec<PathStep<'static>> {
+            let mut steps = Vec::with_capacity(usize::from(self.len) * 2);
+
+            for &KeySegment { key, index } in self.segments() {
+                if !key.is_empty() {
+                    steps.push(PathStep::Key(key));
+                }
+                if let Some(index) = index {
+                    steps.push(PathStep::Index(index));
+                }
+            }
+
+            steps
+        }
+    }
+
+    /// A place in Rune source where an object field or array element is written.
+    #[derive(Debug)]
+    struct ValueSite<'code> {
+        /// The fields and elements of the literals this one is nested in, ending
+        /// with this one. Starts over inside blocks and calls, since what is in
+        /// them could end up anywhere.
+        path: Vec<PathStep<'code>>,
+        offset: usize,
+    }
+
+    enum Bracket<'code> {
+        /// `#{`, with the key of the field being read, once it is known.
+        Object(Option<&'code str>),
+        /// An array literal, with the index of the element being read.
+        Array(usize),
+        /// A block, call, index, or anything else a path cannot be followed through.
+        Other,
+    }
+
+    /// Finds the object fields and array elements in `code`. This only knows as
+    /// much Rune syntax as it needs to follow nested literals, and to not be
+    /// thrown off by comments and strings.
+    fn value_sites(code: &str) -> Vec<ValueSite<'_>> {
+        fn path<'code>(stack: &[Bracket<'code>]) -> Option<Vec<PathStep<'code>>> {
+            let literals_start = stack.iter()
+                .rposition(|bracket| matches!(bracket, Bracket::Other))
+                .map_or(0, |i| i + 1);
+
+            stack[literals_start..].iter()
+                .map(|bracket| match bracket {
+                    Bracket::Object(key) => key.map(PathStep::Key),
+                    Bracket::Array(index) => Some(PathStep::Index(*index)),
+                    Bracket::Other => None,
+                })
+                .collect()
+        }
+
+        fn is_ident_byte(byte: u8) -> bool {
+            byte.is_ascii_alphanumeric() || byte == b'_'
+        }
+
+        let bytes = code.as_bytes();
+
+        let mut sites = Vec::new();
+        let mut stack: Vec<Bracket<'_>> = Vec::new();
+        // Whether the next token starts a field or element of the innermost bracket.
+        let mut expecting_item = false;
+        // The last byte of the last token, to tell array literals from indexing.
+        let mut previous = b';';
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let start = i;
+            let rest = &bytes[i..];
+
+            if rest[0].is_ascii_whitespace() {
+                i += 1;
+                continue
+            }
+
+            if rest.starts_with(b"//") {
+                i += rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+                continue
+            }
+
+            if rest.starts_with(b"/*") {
+                i += rest.windows(2).position(|w| w == b"*/").map_or(rest.len(), |end| end + 2);
+                continue
+            }
+
+            let item_start = core::mem::take(&mut expecting_item);
+            let depth = stack.len();
+
+            match rest[0] {
+                quote @ (b'"' | b'`') => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != quote {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    i += 1;
+
+                    if item_start
+                    && quote == b'"'
+                    && let Some(Bracket::Object(key)) = stack.last_mut() {
+                        *key = code.get(start + 1..i - 1);
+                    }
+                }
+                b'\'' => {
+                    // Either a char literal, or a label like `'outer`.
+                    i += match rest {
+                        [_, b'\\', ..] => {
+                            rest.iter().skip(3).position(|&b| b == b'\'').map_or(rest.len(), |end| end + 4)
+                        },
+                        [_, _, b'\'', ..] => 3,
+                        _ => 1,
+                    };
+                }
+                b'#' if rest.starts_with(b"#{") => {
+                    i += 2;
+                    stack.push(Bracket::Object(None));
+                    expecting_item = true;
+                }
+                b'[' if !is_ident_byte(previous) && !matches!(previous, b')' | b']' | b'?') => {
+                    i += 1;
+                    stack.push(Bracket::Array(0));
+                    expecting_item = true;
+                }
+                b'{' | b'(' | b'[' => {
+                    i += 1;
+                    stack.push(Bracket::Other);
+                }
+                b'}' | b')' | b']' => {
+                    i += 1;
+                    stack.pop();
+                }
+                b',' => {
+                    i += 1;
+                    match stack.last_mut() {
+                        Some(Bracket::Object(key)) => {
+                            *key = None;
+                            expecting_item = true;
+                        },
+                        Some(Bracket::Array(index)) => {
+                            *index += 1;
+                            expecting_item = true;
+                        },
+                        Some(Bracket::Other) | None => {},
+                    }
+                }
+                byte if is_ident_byte(byte) => {
+                    i += rest.iter().position(|&b| !is_ident_byte(b)).unwrap_or(rest.len());
+
+                    if item_start
+                    && let Some(Bracket::Object(key)) = stack.last_mut() {
+                        *key = Some(&code[start..i]);
+                    }
+                }
+                _ => {
+                    i += 1;
+                }
+            }
+
+            previous = bytes[i.min(bytes.len()) - 1];
+
+            // A bracket the token opens is not part of the path to the value the
+            // token starts, and a bracket it closes means there was no value.
+            if item_start
+            && let Some(enclosing) = stack.get(..depth)
+            && let Some(Bracket::Object(Some(_)) | Bracket::Array(_)) = enclosing.last()
+            && let Some(path) = path(enclosing) {
+                sites.push(ValueSite { path, offset: start });
+            }
+        }
+
+        sites
+    }
+
+    /// The byte offset in `code` that the value at `key` was most likely written
+    /// at. That is the field or element whose path matches the most of the end of
+    /// `key`, if only one does.
+    fn value_offset(code: &str, key: IndexableKey) -> Option<usize> {
+        let steps = key.steps();
+
+        let mut best: Option<&ValueSite<'_>> = None;
+        let mut tied = false;
+
+        let sites = value_sites(code);
+        for site in &sites {
+            if site.path.is_empty() || !steps.ends_with(&site.path) {
+                continue
+            }
+
+            match best {
+                Some(current) if current.path.len() > site.path.len() => {},
+                Some(current) if current.path.len() == site.path.len() => {
+                    tied = true;
+                },
+                _ => {
+                    best = Some(site);
+                    tied = false;
+                },
+            }
+        }
+
+        if tied {
+            return None
+        }
+
+        best.map(|site| site.offset)
+    }
+
+    impl From<AllocError> for Error {
+        fn from(e: AllocError) -> Self {
+            ErrorKind::Alloc(e).into()
+        }
+    }
+
+    impl From<BuildError> for Error {
+        fn from(e: BuildError) -> Self {
+            ErrorKind::Build(e).into()
+        }
+    }
+
+    impl From<ContextError> for Error {
+        fn from(e: ContextError) -> Self {
+            ErrorKind::Context(e).into()
+        }
+    }
+
+    macro_rules! to_int {
+        ($val: expr, $key: expr, $parent_key: expr $(,)?) => ({
+            let key: IndexableKey = $key;
+            let parent_key: IndexableKey = $parent_key.into();
+
+            let int: i64 =
+                $val.as_integer().map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "int", got })?;
+
+            int.try_into().map_err(|error| ErrorKind::SizeError {
+                key,
+                parent_key,
+                error,
+            })?
+        })
+    }
+
+    macro_rules! get_int {
+        ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
+            {
+                let key = $key;
+                let parent_key = $parent_key.into();
+
+                let value: &Value =
+                    $map.get(key)
+                        .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?;
+
+                to_int!(
+                    value,
+                    ik!(key),
+                    parent_key,
+                )
+            }
+        }
+    }
+
+    // Returns `None` if the key is not there, so the default can be kept.
+    macro_rules! get_param {
+        ($map: expr, $key: expr, $parent_key: expr, $range: expr $(,)?) => ({
+            let key = $key;
+            let parent_key = $parent_key;
+            let range = $range;
+
+            match $map.get(key) {
+                None => None,
+                Some(value) => {
+                    let got: i64 = value.as_integer()
+                        .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "int", got })?;
+
+                    let min = i64::from(*range.start());
+                    let max = i64::from(*range.end());
+
+                    if got < min || got > max {
+                        return Err(ErrorKind::ParamOutOfRange{ key, parent_key, got, min, max }.into());
+                    }
+
+                    Some(got.try_into().map_err(|error| ErrorKind::SizeError {
+                        key: ik!(key),
+                        parent_key,
+                        error,
+                    })?)
+                }
+            }
+        })
+    }
+
+    fn to_config(map: Object) -> Result<Config, Error> {
+        use rune::runtime::{BorrowRef, Object};
+        use rune::{Value};
+        use models::{
+            consts::{EntityDefIdRefKind, CollectActionKind},
+            CollectAction,
+            DefId,
+            EntityDef,
+            SegmentWidth,
+            Speech
+        };
+
+        macro_rules! convert_to {
+            ($from: expr => $to: ty, $key: expr, $parent_key: expr $(,)?) => {
+                <$to>::try_from($from).map_err(|error| ErrorKind::SizeError {
+                    key: $key.into(),
+                    parent_key: $parent_key,
+                    error,
                 })?
             }
         }
@@ -303,7 +1056,7 @@ mod rune_based {
             ($val: expr, $key: expr $(,)?) => ({
                 let vec: Vec<Value> = rune::from_value(
                     $val
-                ).map_err(|got| Error::TypeMismatch{ key: $key, expected: "array", got })?;
+                ).map_err(|got| ErrorKind::TypeMismatch{ key: $key, expected: "array", got })?;
 
                 vec
             })
@@ -318,8 +1071,8 @@ mod rune_based {
                     let obj: Object =
                         rune::from_value(
                             $map.get(key)
-                                .ok_or(Error::FieldMissing{ key, parent_key, })?
-                        ).map_err(|got| Error::TypeMismatch{ key: key.into(), expected: "map", got })?;
+                                .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?
+                        ).map_err(|got| ErrorKind::TypeMismatch{ key: key.into(), expected: "map", got })?;
 
                     obj
                 }
@@ -335,14 +1088,52 @@ mod rune_based {
 
                     to_array!(
                         $map.get(key)
-                            .ok_or(Error::FieldMissing{ key, parent_key, })?,
-                        ik!(key)
+                            .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?,
+                        parent_key.then(key)
                     )
                 }
             }
         }
 
-        let segments = get_array!(map, "segments", ik!("#root"));
+        let mut music = MusicSpec::default();
+
+        // Returns `None` if the key is not there, which means no music plays.
+        macro_rules! get_track {
+            ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
+                match $map.get($key) {
+                    None => None,
+                    Some(value) => {
+                        let key = $key;
+                        let parent_key: IndexableKey = $parent_key;
+                        let track: String = rune::from_value(value.clone())
+                            .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "String", got })?;
+
+                        Some(track)
+                    }
+                }
+            }
+        }
+
+        // Returns `None` if the key is not there, which means nothing extra happens.
+        macro_rules! get_script_name {
+            ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
+                match $map.get($key) {
+                    None => None,
+                    Some(value) => {
+                        let key = $key;
+                        let parent_key: IndexableKey = $parent_key;
+                        let name: String = rune::from_value(value.clone())
+                            .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "String", got })?;
+
+                        Some(name)
+                    }
+                }
+            }
+        }
+
+        music.victory = get_track!(map, "victory_track", IndexableKey::ROOT);
+
+        let segments = get_array!(map, "segments", IndexableKey::ROOT);
 
         let mut segments_vec = Vec::with_capacity(segments.len());
 
@@ -350,12 +1141,12 @@ mod rune_based {
             let parent_key = ik!("segments", i);
 
             let segment: Object = rune::from_value(segments[i].clone())
-                .map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "map", got })?;
+                .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
 
             let key = "width";
 
             let width_usize: usize = get_int!(segment, key, parent_key);
-            let width: SegmentWidth = width_usize.try_into().map_err(|error| Error::SizeError {
+            let width: SegmentWidth = width_usize.try_into().map_err(|error| ErrorKind::SizeError {
                 key: key.into(),
                 parent_key,
                 error,
@@ -372,30 +1163,31 @@ mod rune_based {
 
                     let tile_flags = match TileFlags::try_from(got) {
                         Ok(tf) => tf,
-                        Err(_) => { return Err(Error::UnexpectedTileKind { index: i, got }); },
+                        Err(_) => { return Err(ErrorKind::UnexpectedTileKind { key: parent_key.then(ik!(key, i)), got }.into()); },
                     };
 
                     tiles.push(tile_flags);
                 }
 
                 tiles.try_into()
-                    .map_err(|_| Error::EmptyList{ key, parent_key })?
+                    .map_err(|_| ErrorKind::EmptyList{ key: key.into(), parent_key })?
             };
 
             segments_vec.push(WorldSegment {
                 width,
                 cells: tiles,
             });
+            music.segments.push(get_track!(segment, "track", parent_key));
         }
 
-        let segments = segments_vec.try_into().map_err(|_| Error::NoSegmentsFound)?;
+        let segments = segments_vec.try_into().map_err(|_| ErrorKind::NoSegmentsFound)?;
 
-        let entities = get_array!(map, "entities", ik!("#root"));
+        let entities = get_array!(map, "entities", IndexableKey::ROOT);
 
         let mut entities_vec = Vec::with_capacity(entities.len());
 
         let entity_def_count = DefId::try_from(entities.len())
-            .map_err(|_| Error::TooManyEntityDefinitions{ got: entities.len() })?;
+            .map_err(|_| ErrorKind::TooManyEntityDefinitions{ got: entities.len() })?;
 
         for id in 0..entity_def_count {
             fn deref_def_id(
@@ -414,15 +1206,15 @@ mod rune_based {
                     models::consts::RELATIVE => {
                         let delta = convert_to!(value => DefIdDelta, key, parent_key);
 
-                        base.checked_add_signed(delta).ok_or(Error::DefIdOverflow{ key, parent_key, base, delta })?
+                        base.checked_add_signed(delta).ok_or(ErrorKind::DefIdOverflow{ key, parent_key, base, delta })?
                     },
                     models::consts::ABSOLUTE => convert_to!(value => DefId, key, parent_key),
-                    _ => return Err(Error::UnknownEntityDefIdRefKind { key, parent_key, kind }),
+                    _ => return Err(ErrorKind::UnknownEntityDefIdRefKind { key: "kind", parent_key, kind }.into()),
                 };
 
                 // TODO? Validate that the target is a valid kind of entity here?
                 if def_id >= entity_def_count {
-                    return Err(Error::OutOfBoundsDefId{ key, parent_key, def_id });
+                    return Err(ErrorKind::OutOfBoundsDefId{ key, parent_key, def_id }.into());
                 }
 
                 Ok(def_id)
@@ -431,7 +1223,7 @@ mod rune_based {
             let parent_key = ik!("entities", id.into());
 
             let entity: Object = rune::from_value(entities[usize::from(id)].clone())
-                .map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "map", got })?;
+                .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
 
             let flags = get_int!(entity, "flags", parent_key);
 
@@ -442,21 +1234,21 @@ mod rune_based {
 
                 let raw_speeches_list = match entity.get(key) {
                     None => break 'speeches vec![],
-                    Some(dynamic) => to_array!(dynamic, ik!(key)),
+                    Some(dynamic) => to_array!(dynamic, parent_key.then(key)),
                 };
 
                 let mut speeches = Vec::with_capacity(raw_speeches_list.len());
 
                 for list_i in 0..raw_speeches_list.len() {
-                    let parent_key = ik!(key, list_i);
+                    let list_key = ik!(key, list_i);
 
-                    let raw_speeches = to_array!(raw_speeches_list[list_i].clone(), parent_key);
+                    let raw_speeches = to_array!(raw_speeches_list[list_i].clone(), parent_key.then(list_key));
 
                     let mut individual_speeches = Vec::with_capacity(raw_speeches.len());
 
                     for i in 0..raw_speeches.len() {
                         let raw_text = raw_speeches[i].clone()
-                            .into_string().map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "string", got })?;
+                            .into_string().map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(list_key).nth(i), expected: "string", got })?;
 
                         // TODO? Allow avoiding this reflow per speech?
                         individual_speeches.push(Speech::from(raw_text.as_str()));
@@ -465,7 +1257,7 @@ mod rune_based {
                     speeches.push(
                         individual_speeches
                             .try_into()
-                            .map_err(|_| Error::EmptyList{ key, parent_key })?
+                            .map_err(|_| ErrorKind::EmptyList{ key: list_key, parent_key })?
                     );
                 }
 
@@ -477,21 +1269,21 @@ mod rune_based {
 
                 let raw_inventory_description_list = match entity.get(key) {
                     None => break 'inventory_description vec![],
-                    Some(dynamic) => to_array!(dynamic, ik!(key)),
+                    Some(dynamic) => to_array!(dynamic, parent_key.then(key)),
                 };
 
                 let mut inventory_description = Vec::with_capacity(raw_inventory_description_list.len());
 
                 for list_i in 0..raw_inventory_description_list.len() {
-                    let parent_key = ik!(key, list_i);
+                    let list_key = ik!(key, list_i);
 
-                    let raw_inventory_description = to_array!(&raw_inventory_description_list[list_i], parent_key);
+                    let raw_inventory_description = to_array!(&raw_inventory_description_list[list_i], parent_key.then(list_key));
 
                     let mut individual_inventory_description = Vec::with_capacity(raw_inventory_description.len());
 
                     for i in 0..raw_inventory_description.len() {
                         let raw_text = raw_inventory_description[i].clone()
-                            .into_string().map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "string", got })?;
+                            .into_string().map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(list_key).nth(i), expected: "string", got })?;
 
                         // TODO? Allow avoiding this reflow per speech?
                         individual_inventory_description.push(Speech::from(raw_text.as_str()));
@@ -500,7 +1292,7 @@ mod rune_based {
                     inventory_description.push(
                         individual_inventory_description
                             .try_into()
-                            .map_err(|_| Error::EmptyList{ key, parent_key })?
+                            .map_err(|_| ErrorKind::EmptyList{ key: list_key, parent_key })?
                     );
                 }
 
@@ -512,7 +1304,7 @@ mod rune_based {
 
                 let raw_wants = match entity.get(key) {
                     None => break 'wants vec![],
-                    Some(dynamic) => to_array!(dynamic, ik!(key)),
+                    Some(dynamic) => to_array!(dynamic, parent_key.then(key)),
                 };
 
                 let want_count: DefId = convert_to!(raw_wants.len() => DefId, key, parent_key);
@@ -520,10 +1312,10 @@ mod rune_based {
                 let mut wants = Vec::with_capacity(raw_wants.len());
 
                 for i in 0..want_count {
-                    let parent_key = ik!("wants", i.into());
+                    let parent_key = parent_key.then(ik!(key, i.into()));
 
                     let map: Object = rune::from_value(raw_wants[i as usize].clone())
-                        .map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "map", got })?;
+                        .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
 
                     let def_id = deref_def_id(
                         id,
@@ -538,12 +1330,26 @@ mod rune_based {
                 wants
             };
 
+            let mut scripts = models::script::Names {
+                on_interact: get_script_name!(entity, "on_interact", parent_key),
+                on_step: get_script_name!(entity, "on_step", parent_key),
+                on_collect: None,
+            };
+
             let on_collect = 'on_collect: {
                 let key = "on_collect";
 
                 let raw_on_collect = match entity.get(key) {
                     None => break 'on_collect vec![],
-                    Some(dynamic) => to_array!(dynamic, ik!(key)),
+                    Some(dynamic) => {
+                        // A string names a script to run, instead of listing actions.
+                        if let Ok(name) = dynamic.clone().into_string() {
+                            scripts.on_collect = Some(name);
+                            break 'on_collect vec![]
+                        }
+
+                        to_array!(dynamic, parent_key.then(key))
+                    },
                 };
 
                 let on_collect_count: DefId = convert_to!(raw_on_collect.len() => DefId, key, parent_key);
@@ -551,10 +1357,10 @@ mod rune_based {
                 let mut on_collect = Vec::with_capacity(raw_on_collect.len());
 
                 for i in 0..on_collect_count {
-                    let action_map: Object = rune::from_value(raw_on_collect[i as usize].clone())
-                        .map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "map", got })?;
+                    let parent_key = parent_key.then(ik!(key, i.into()));
 
-                    let parent_key = ik!("on_collect", i.into());
+                    let action_map: Object = rune::from_value(raw_on_collect[i as usize].clone())
+                        .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
 
                     let kind: CollectActionKind = get_int!(action_map, "kind", parent_key);
 
@@ -564,10 +1370,11 @@ mod rune_based {
                                 let key = "from";
 
                                 let value: &Value = action_map.get(key)
-                                    .ok_or(Error::FieldMissing{ key, parent_key, })?;
+                                    .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?;
                                 // We previously observed a "Cannot take" error when using `get_map!`,
                                 // which is why we borrow instead.
-                                let from_ref: BorrowRef<Object> = value.borrow_ref().map_err(Error::Runtime)?;
+                                let from_ref: BorrowRef<Object> = value.borrow_ref()
+                                    .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "map", got })?;
 
                                 let from_map: &Object = from_ref.as_ref();
 
@@ -575,7 +1382,7 @@ mod rune_based {
                                     id,
                                     from_map,
                                     entity_def_count,
-                                    parent_key,
+                                    parent_key.then(key),
                                 )?
                             };
 
@@ -583,10 +1390,11 @@ mod rune_based {
                                 let key = "to";
 
                                 let value: &Value = action_map.get(key)
-                                    .ok_or(Error::FieldMissing{ key, parent_key, })?;
+                                    .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?;
                                 // We previously observed a "Cannot take" error when using `get_map!`,
                                 // which is why we borrow instead.
-                                let to_ref: BorrowRef<Object> = value.borrow_ref().map_err(Error::Runtime)?;
+                                let to_ref: BorrowRef<Object> = value.borrow_ref()
+                                    .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "map", got })?;
 
                                 let to_map: &Object = to_ref.as_ref();
 
@@ -594,13 +1402,13 @@ mod rune_based {
                                     id,
                                     to_map,
                                     entity_def_count,
-                                    parent_key,
+                                    parent_key.then(key),
                                 )?
                             };
 
                             on_collect.push(CollectAction::Transform(models::Transform{ from, to }));
                         },
-                        _ => return Err(Error::UnknownCollectActionKind { key, parent_key, kind }),
+                        _ => return Err(ErrorKind::UnknownCollectActionKind { key: "kind", parent_key, kind }.into()),
                     }
 
                 }
@@ -616,12 +1424,13 @@ mod rune_based {
                 tile_sprite,
                 wants,
                 on_collect,
+                scripts,
             });
         }
 
-        let entities = entities_vec.try_into().map_err(|_| Error::NoEntitiesFound)?;
+        let entities = entities_vec.try_into().map_err(|_| ErrorKind::NoEntitiesFound)?;
 
-        let hallways = get_array!(map, "hallways", ik!("#root"));
+        let hallways = get_array!(map, "hallways", IndexableKey::ROOT);
 
         let mut hallways_vec = Vec::with_capacity(hallways.len());
 
@@ -629,7 +1438,7 @@ mod rune_based {
             let parent_key = ik!("hallways", i.into());
 
             let hallway: Object = rune::from_value(hallways[i].clone())
-                .map_err(|got| Error::TypeMismatch{ key: parent_key, expected: "map", got })?;
+                .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
 
             let key = "kind";
 
@@ -637,22 +1446,160 @@ mod rune_based {
 
             let spec = match pak_types::const_to_hallway_spec(kind) {
                 Some(spec) => spec,
-                None => return Err(Error::UnknownHallwayKind{ key, parent_key, kind }),
+                None => return Err(ErrorKind::UnknownHallwayKind{ key, parent_key, kind }.into()),
             };
 
-            hallways_vec.push(spec);
+            hallways_vec.push(with_hallway_params(spec, &hallway, parent_key)?);
+            music.hallways.push(get_track!(hallway, "track", parent_key));
         }
 
         // Interpret an empty hallways array as an array with a None kind in it.
         let hallways = hallways_vec.try_into().unwrap_or_default();
 
+        let spheres = match map.get("spheres") {
+            None => SpheresSpec::default(),
+            Some(value) => {
+                let parent_key = ik!("spheres");
+
+                let spheres: Object = rune::from_value(value.clone())
+                    .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
+
+                to_spheres_spec(&spheres, parent_key)?
+            }
+        };
+
         Ok(Config {
             segments,
             entities,
             hallways,
+            spheres,
+            music,
+            // `parse` fills this in, once the names have been checked.
+            scripts: <_>::default(),
+        })
+    }
+
+    /// Overrides the default sphere settings with any that are in `spheres`.
+    fn to_spheres_spec(
+        spheres: &Object,
+        parent_key: IndexableKey,
+    ) -> Result<SpheresSpec, Error> {
+        let mut spec = SpheresSpec::default();
+
+        macro_rules! set_param {
+            ($field: expr, $key: expr, $range: expr $(,)?) => {
+                if let Some(value) = get_param!(spheres, $key, parent_key, $range) {
+                    $field = value;
+                }
+            }
+        }
+
+        set_param!(spec.min_count, "min_count", SpheresSpec::COUNT);
+        set_param!(spec.max_count, "max_count", SpheresSpec::COUNT);
+        set_param!(spec.min_segments, "min_segments", SpheresSpec::SEGMENTS);
+        set_param!(spec.max_segments, "max_segments", SpheresSpec::SEGMENTS);
+        set_param!(spec.push_back_percent, "push_back_percent", SpheresSpec::PERCENT);
+
+        for (keys, min, max) in [
+            (["min_count", "max_count"], spec.min_count, spec.max_count),
+            (["min_segments", "max_segments"], spec.min_segments, spec.max_segments),
+        ] {
+            if min > max {
+                return Err(ErrorKind::ParamMinGreaterThanMax{
+                    keys,
+                    parent_key,
+                    min: min.into(),
+                    max: max.into(),
+                }.into());
+            }
+        }
+
+        Ok(spec)
+    }
+
+    /// Overrides the default parameters in `spec` with any that are in `hallway`.
+    fn with_hallway_params(
+        spec: HallwaySpec,
+        hallway: &Object,
+        parent_key: IndexableKey,
+    ) -> Result<HallwaySpec, Error> {
+        macro_rules! set_param {
+            ($field: expr, $key: expr, $range: expr $(,)?) => {
+                if let Some(value) = get_param!(hallway, $key, parent_key, $range) {
+                    $field = value;
+                }
+            }
+        }
+
+        Ok(match spec {
+            HallwaySpec::None => HallwaySpec::None,
+            HallwaySpec::IcePuzzle(mut params) => {
+                if let Some(width) = get_param!(hallway, "width", parent_key, IcePuzzleParams::SIDE_LENGTH) {
+                    params.width = Some(width);
+                }
+                if let Some(height) = get_param!(hallway, "height", parent_key, IcePuzzleParams::SIDE_LENGTH) {
+                    params.height = Some(height);
+                }
+                set_param!(params.max_steps, "max_steps", IcePuzzleParams::MAX_STEPS);
+                set_param!(params.levels, "levels", IcePuzzleParams::LEVELS);
+
+                HallwaySpec::IcePuzzle(params)
+            },
+            HallwaySpec::SWORD(mut params) => {
+                set_param!(params.switch_count, "switch_count", SWORDParams::SWITCH_COUNT);
+
+                HallwaySpec::SWORD(params)
+            },
+            HallwaySpec::BOLD(mut params) => {
+                set_param!(params.boulder_percent, "boulder_percent", BOLDParams::PERCENT);
+                set_param!(params.gem_percent, "gem_percent", BOLDParams::PERCENT);
+
+                let sum = i64::from(params.boulder_percent) + i64::from(params.gem_percent);
+                let max = i64::from(*BOLDParams::PERCENT.end());
+                if sum > max {
+                    return Err(ErrorKind::HallwayParamsSumTooLarge{
+                        keys: ["boulder_percent", "gem_percent"],
+                        parent_key,
+                        sum,
+                        max,
+                    }.into());
+                }
+
+                HallwaySpec::BOLD(params)
+            },
+            HallwaySpec::HexHop(mut params) => {
+                set_param!(params.radius, "radius", HexHopParams::RADIUS);
+
+                HallwaySpec::HexHop(params)
+            },
+            HallwaySpec::HexTwiddle(mut params) => {
+                set_param!(params.radius, "radius", HexTwiddleParams::RADIUS);
+
+                HallwaySpec::HexTwiddle(params)
+            },
+            HallwaySpec::CubeMaze(mut params) => {
+                let key = "size";
+                set_param!(params.size, key, CubeMazeParams::SIZE);
+
+                if params.size % 2 == 0 {
+                    return Err(ErrorKind::HallwayParamNotOdd{ key, parent_key, got: params.size.into() }.into());
+                }
+
+                HallwaySpec::CubeMaze(params)
+            },
+            HallwaySpec::KeycardShuffle(mut params) => {
+                set_param!(params.lock_count, "lock_count", KeycardShuffleParams::LOCK_COUNT);
+                set_param!(params.max_cards_per_lock, "max_cards_per_lock", KeycardShuffleParams::MAX_CARDS_PER_LOCK);
+
+                HallwaySpec::KeycardShuffle(params)
+            },
         })
     }
 
+    /// Index 0 is the spritesheet at `spritesheet_path`, so the ones listed in
+    /// `spritesheets` get the rest of the indexes.
+    const MAX_SPRITESHEETS: usize = sprite::SheetIndex::MAX as usize;
+
     fn to_manifest(map: Object) -> Result<Manifest, Error> {
         macro_rules! get_str {
             ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
@@ -662,118 +1609,460 @@ mod rune_based {
                     let string: String =
                         rune::from_value(
                             $map.get(key)
-                                .ok_or(Error::FieldMissing{ key, parent_key, })?
-                        ).map_err(|got| Error::TypeMismatch{ key: key.into(), expected: "String", got })?;
+                                .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?
+                        ).map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "String", got })?;
 
                     string
                 }
             }
         }
 
-        let root_key = ik!("#root");
+        // Returns `None` if the key is not there.
+        macro_rules! get_optional_str {
+            ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
+                match $map.get($key) {
+                    None => None,
+                    Some(value) => {
+                        let key = $key;
+                        let parent_key: IndexableKey = $parent_key;
+                        let string: String = rune::from_value(value)
+                            .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "String", got })?;
+
+                        Some(string)
+                    }
+                }
+            }
+        }
+
+        macro_rules! get_version {
+            ($map: expr, $key: expr, $parent_key: expr $(,)?) => {
+                match get_optional_str!($map, $key, $parent_key) {
+                    None => None,
+                    Some(string) => Some(
+                        string.parse::<Version>()
+                            .map_err(|()| ErrorKind::InvalidVersion{ key: $key, parent_key: $parent_key, got: string })?
+                    ),
+                }
+            }
+        }
+
+        let root_key = IndexableKey::ROOT;
 
         let name = get_str!(map, "name", root_key);
         let config_path = PathBuf::from(get_str!(map, "config_path", root_key));
         let spritesheet_path = PathBuf::from(get_str!(map, "spritesheet_path", root_key));
 
-        let mut specs = Specs::default();
+        let format_version: u32 = match map.get("format_version") {
+            None => 1,
+            Some(value) => to_int!(value, ik!("format_version"), root_key),
+        };
 
-        // Not intended for reuse outside this function.
-        macro_rules! assign_spec {
-            (<$typ: path> $spec_key: ident) => ({
-                let key = stringify!($spec_key);
+        let mut game_versions = VersionRange::default();
 
-                if let Some(v) = map.get(key) {
-                    let spec: Object =
-                        rune::from_value(v)
-                            .map_err(|got| Error::TypeMismatch{ key: key.into(), expected: "map", got })?;
+        if let Some(raw_game_versions) = map.get("game_versions") {
+            let key = "game_versions";
+            let range: Object = rune::from_value(raw_game_versions)
+                .map_err(|got| ErrorKind::TypeMismatch{ key: ik!(key), expected: "map", got })?;
 
-                    let mut pieces = specs.$spec_key.pieces();
+            let parent_key = ik!(key);
 
-                    {
-                        let key = "offset";
+            if let Some(min) = get_version!(range, "min", parent_key) {
+                game_versions.min = min;
+            }
+            game_versions.max = get_version!(range, "max", parent_key);
+        }
 
-                        if let Some(raw_offset) = spec.get(key) {
-                            let offset: Object = rune::from_value(raw_offset)
-                                .map_err(|got| Error::TypeMismatch{ key: key.into(), expected: "map", got })?;
+        let metadata = Metadata {
+            title: get_optional_str!(map, "title", root_key).unwrap_or_default(),
+            author: get_optional_str!(map, "author", root_key).unwrap_or_default(),
+            description: get_optional_str!(map, "description", root_key).unwrap_or_default(),
+        };
 
-                            let parent_key = ik!(key);
+        let mut spritesheets = Vec::new();
 
-                            pieces.offset = WH {
-                                w: W::new(get_int!(offset, "w", parent_key)),
-                                h: H::new(get_int!(offset, "h", parent_key)),
-                            };
-                        }
-                    }
+        if let Some(raw_spritesheets) = map.get("spritesheets") {
+            let raw_spritesheets: Vec<Value> = rune::from_value(raw_spritesheets)
+                .map_err(|got| ErrorKind::TypeMismatch{ key: ik!("spritesheets"), expected: "array", got })?;
 
-                    {
-                        let key = "tile";
+            if raw_spritesheets.len() > MAX_SPRITESHEETS {
+                return Err(ErrorKind::TooManySpritesheets{ got: raw_spritesheets.len() }.into());
+            }
 
-                        if let Some(raw_tile) = spec.get(key) {
-                            let tile: Object = rune::from_value(raw_tile)
-                                .map_err(|got| Error::TypeMismatch{ key: key.into(), expected: "map", got })?;
+            for (i, raw_sheet) in raw_spritesheets.into_iter().enumerate() {
+                let parent_key = ik!("spritesheets", i);
 
-                            let parent_key = ik!(key);
+                let sheet: Object = rune::from_value(raw_sheet)
+                    .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
 
-                            pieces.tile = WH {
-                                w: W::new(get_int!(tile, "w", parent_key)),
-                                h: H::new(get_int!(tile, "h", parent_key)),
-                            };
-                        }
-                    }
+                let name = get_str!(sheet, "name", parent_key);
+                let path = PathBuf::from(get_str!(sheet, "path", parent_key));
 
-                    specs.$spec_key = sprite::spec(pieces);
+                // Specs refer to spritesheets by name, so a second one with the same
+                // name could never be used.
+                if spritesheets.iter().any(|earlier: &SpritesheetSpec| earlier.name == name) {
+                    return Err(ErrorKind::DuplicateSpritesheet{ key: "name", parent_key, name }.into());
                 }
-            })
+
+                spritesheets.push(SpritesheetSpec { name, path });
+            }
         }
 
-        assign_spec!(<sprite::BaseFont> base_font);
-        assign_spec!(<sprite::BaseTiles> base_tiles);
-        assign_spec!(<sprite::BaseUI> base_ui);
-        assign_spec!(<sprite::IcePuzzles> ice_puzzles);
-        assign_spec!(<sprite::SWORD> sword);
+        let mut specs = Specs::default();
 
-        Ok(Manifest {
-            name,
-            config_path,
-            spritesheet_path,
-            specs,
-        })
-    }
+        specs.try_map_named_pieces(|spec_key, mut pieces| -> Result<SpecPieces, Error> {
+            let Some(v) = map.get(spec_key) else {
+                return Ok(pieces)
+            };
 
-    fn eval(code: &str) -> Result<Object, Error> {
-        let context = init_context()?;
-        let runtime = Arc::try_new(context.runtime()?)?;
+            let spec: Object =
+                rune::from_value(v)
+                    .map_err(|got| ErrorKind::TypeMismatch{ key: spec_key.into(), expected: "map", got })?;
 
-        let mut sources = sources_with_helpers()?;
-        sources.insert(Source::memory(code)?)?;
+            let spec_parent_key = ik!(spec_key);
 
-        let mut diagnostics = Diagnostics::new();
+            {
+                let key = "sheet";
 
-        let result = rune::prepare(&mut sources)
-            .with_context(&context)
-            .with_diagnostics(&mut diagnostics)
-            .build();
+                if let Some(raw_sheet) = spec.get(key) {
+                    let name: String = rune::from_value(raw_sheet)
+                        .map_err(|got| ErrorKind::TypeMismatch{ key: spec_parent_key.then(key), expected: "String", got })?;
+
+                    let index = spritesheets.iter()
+                        .position(|sheet: &SpritesheetSpec| sheet.name == name)
+                        .ok_or(ErrorKind::UnknownSpritesheet{ key, parent_key: spec_parent_key, name })?;
+
+                    // We checked there are few enough spritesheets above.
+                    pieces.sheet = (index + 1) as sprite::SheetIndex;
+                }
+            }
+
+            // Not intended for reuse outside this closure.
+            macro_rules! get_wh {
+                ($key: expr, $current: expr, $min: expr $(,)?) => ({
+                    let key = $key;
+                    let mut output: WH = $current;
+
+                    if let Some(raw_wh) = spec.get(key) {
+                        let parent_key = spec_parent_key.then(key);
+
+                        let wh: Object = rune::from_value(raw_wh)
+                            .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
+
+                        if let Some(w) = get_param!(wh, "w", parent_key, $min..=unscaled::Inner::MAX) {
+                            output.w = W::new(w);
+                        }
+                        if let Some(h) = get_param!(wh, "h", parent_key, $min..=unscaled::Inner::MAX) {
+                            output.h = H::new(h);
+                        }
+                    }
+
+                    output
+                })
+            }
+
+            pieces.offset = get_wh!("offset", pieces.offset, 0);
+            // Zero sized tiles would not show up, and would cause divisions by zero.
+            pieces.tile = get_wh!("tile", pieces.tile, 1);
+
+            if let Some(tiles_per_row) = get_param!(
+                spec,
+                "tiles_per_row",
+                spec_parent_key,
+                1..=sprite::TileCount::MAX
+            ) {
+                pieces.tiles_per_row = tiles_per_row;
+            }
+
+            Ok(pieces)
+        })?;
+
+        let mut sounds = Vec::new();
+
+        if let Some(raw_sounds) = map.get("sounds") {
+            let raw_sounds: Vec<Value> = rune::from_value(raw_sounds)
+                .map_err(|got| ErrorKind::TypeMismatch{ key: ik!("sounds"), expected: "array", got })?;
+
+            for (i, raw_bank) in raw_sounds.into_iter().enumerate() {
+                let parent_key = ik!("sounds", i);
+
+                let bank: Object = rune::from_value(raw_bank)
+                    .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
+
+                let name = get_str!(bank, "name", parent_key);
+
+                let key = "paths";
+
+                let raw_paths: Vec<Value> = rune::from_value(
+                    bank.get(key)
+                        .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?
+                ).map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "array", got })?;
+
+                let mut paths = Vec::with_capacity(raw_paths.len());
+
+                for (path_i, raw_path) in raw_paths.into_iter().enumerate() {
+                    let path: String = rune::from_value(raw_path)
+                        .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(ik!(key, path_i)), expected: "String", got })?;
+
+                    paths.push(PathBuf::from(path));
+                }
+
+                let paths = paths.try_into().map_err(|_| ErrorKind::EmptyList{ key: key.into(), parent_key })?;
+
+                sounds.push(SoundBankSpec { name, paths });
+            }
+        }
+
+        let mut music = Vec::new();
+
+        if let Some(raw_music) = map.get("music") {
+            let raw_music: Vec<Value> = rune::from_value(raw_music)
+                .map_err(|got| ErrorKind::TypeMismatch{ key: ik!("music"), expected: "array", got })?;
+
+            for (i, raw_track) in raw_music.into_iter().enumerate() {
+                let parent_key = ik!("music", i);
+
+                let track: Object = rune::from_value(raw_track)
+                    .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
+
+                let name = get_str!(track, "name", parent_key);
+                let path = PathBuf::from(get_str!(track, "path", parent_key));
+
+                music.push(TrackSpec { name, path });
+            }
+        }
+
+        let mut extra_assets = Vec::new();
+
+        if let Some(raw_extra_assets) = map.get("extra_assets") {
+            let key = "extra_assets";
+            let raw_paths: Vec<Value> = rune::from_value(raw_extra_assets)
+                .map_err(|got| ErrorKind::TypeMismatch{ key: ik!(key), expected: "array", got })?;
+
+            for (i, raw_path) in raw_paths.into_iter().enumerate() {
+                let path: String = rune::from_value(raw_path)
+                    .map_err(|got| ErrorKind::TypeMismatch{ key: ik!(key, i), expected: "String", got })?;
+
+                extra_assets.push(PathBuf::from(path));
+            }
+        }
+
+        Ok(Manifest {
+            name,
+            format_version,
+            game_versions,
+            metadata,
+            config_path,
+            spritesheet_path,
+            spritesheets,
+            specs,
+            sounds,
+            music,
+            extra_assets,
+        })
+    }
+
+    fn eval(code: &str, seed: Option<Seed>) -> Result<Object, Error> {
+        eval_with_scripts(code, seed).map(|(map, _)| map)
+    }
+
+    /// Also returns what is needed to call the functions in `code` later.
+    fn eval_with_scripts(code: &str, seed: Option<Seed>) -> Result<(Object, RuneScripts), Error> {
+        let mut context = init_context()?;
+
+        let mut generation_rng = None;
+        if let Some(seed) = seed {
+            let (module, rng) = generation_module(seed)?;
+            context.install(module)?;
+            generation_rng = Some(rng);
+        }
+
+        let runtime = Arc::try_new(context.runtime()?)?;
+
+        let mut sources = sources_with_helpers()?;
+        sources.insert(Source::memory(code)?)?;
+
+        let mut diagnostics = Diagnostics::new();
+
+        let result = rune::prepare(&mut sources)
+            .with_context(&context)
+            .with_diagnostics(&mut diagnostics)
+            .build();
 
         if has_meaningful_diagnostics(&diagnostics) {
-            return Err(Error::Diagnostics(to_meaningful_diagnostics(diagnostics), sources))
+            return Err(ErrorKind::Diagnostics(to_meaningful_diagnostics(diagnostics), sources).into())
         }
 
         let unit = result?;
 
-        let mut vm = Vm::new(runtime, Arc::try_new(unit)?);
+        let scripts = RuneScripts {
+            generation_rng,
+            runtime,
+            unit: Arc::try_new(unit)?,
+            sources,
+        };
 
-        let vm_output: rune::Value = vm.call(["main"], ())?;
+        let vm_output: rune::Value = match scripts.vm().call(["main"], ()) {
+            Ok(output) => output,
+            Err(e) => return Err(ErrorKind::Vm(e, scripts.sources).into()),
+        };
 
         let nested_result: Result<Result<Object, String>, RuntimeError> = rune::from_value(vm_output);
 
         match nested_result {
-            Ok(Ok(obj)) => Ok(obj),
-            Ok(Err(e)) => Err(Error::FromConfig(e)),
-            Err(e) => Err(Error::Runtime(e)),
+            Ok(Ok(obj)) => Ok((obj, scripts)),
+            Ok(Err(e)) => Err(ErrorKind::FromConfig(e).into()),
+            Err(e) => Err(ErrorKind::Runtime(e).into()),
+        }
+
+
+    }
+
+    /// The compiled config, kept so that the functions entity defs name can be
+    /// called during play. The `generation` module is reseeded from the seed each
+    /// call is given, so what a script gets from it does not depend on what ran
+    /// before, like `main` or scripts from before a save was loaded.
+    struct RuneScripts {
+        generation_rng: Option<GenerationRng>,
+        runtime: Arc<RuntimeContext>,
+        unit: Arc<Unit>,
+        sources: Sources,
+    }
+
+    impl RuneScripts {
+        fn vm(&self) -> Vm {
+            Vm::new(self.runtime.clone(), self.unit.clone())
         }
 
+        /// Checks that every function the entity defs name is there to be called.
+        fn check_names(&self, config: &Config) -> Result<(), Error> {
+            for (i, entity) in config.entities.iter().enumerate() {
+                for hook in [script::Hook::Interact, script::Hook::Step, script::Hook::Collect] {
+                    let Some(name) = entity.scripts.get(hook) else { continue };
+
+                    if self.vm().lookup_function([name]).is_err() {
+                        return Err(ErrorKind::UnknownScript {
+                            key: hook.field_name(),
+                            parent_key: ik!("entities", i),
+                            name: name.to_owned(),
+                        }.into());
+                    }
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    /// How many instructions a script may run each time it is called, so that
+    /// one which loops forever stops with an error, instead of freezing the game.
+    const SCRIPT_BUDGET: usize = 1_000_000;
+
+    impl script::Run for RuneScripts {
+        fn run(&self, hook: script::Hook, name: &str, seed: Seed, view: &script::View) -> Result<Vec<script::Action>, script::Failure> {
+            use rune::runtime::budget;
+            use script::Failure;
+
+            if let Some(rng) = &self.generation_rng {
+                *lock(rng) = xs::from_seed(seed);
+            }
+
+            let view = view_to_value(view).map_err(|e| Failure::Message(e.to_string()))?;
 
+            let (result, out_of_budget) = budget::with(SCRIPT_BUDGET, || {
+                let result = self.vm().call([name], (view,));
+
+                (result, !budget::acquire().take())
+            }).call();
+
+            let output = match result {
+                Ok(output) => output,
+                Err(_) if out_of_budget => return Err(Failure::OutOfBudget),
+                Err(e) => return Err(Failure::Message(VmErrorDisplay(&e, &self.sources).to_string())),
+            };
+
+            to_actions(output, ik!(hook.field_name())).map_err(|e| Failure::Message(e.to_string()))
+        }
+    }
+
+    fn view_to_value(view: &script::View) -> Result<Value, Error> {
+        let script::View {
+            def_id,
+            inventory,
+            player_inventory,
+            segment_id,
+        } = view;
+
+        fn to_ints(def_ids: &[DefId]) -> Vec<i64> {
+            def_ids.iter().map(|&def_id| i64::from(def_id)).collect()
+        }
+
+        let mut object = Object::new();
+
+        macro_rules! insert {
+            ($key: literal, $value: expr) => {
+                object.insert(
+                    rune::alloc::String::try_from($key)?,
+                    rune::to_value($value).map_err(ErrorKind::Runtime)?,
+                )?;
+            }
+        }
+
+        insert!("def_id", i64::from(*def_id));
+        insert!("inventory", to_ints(inventory));
+        insert!("player_inventory", to_ints(player_inventory));
+        insert!("segment_id", i64::from(*segment_id));
+
+        rune::to_value(object).map_err(|e| ErrorKind::Runtime(e).into())
+    }
+
+    fn to_actions(output: Value, parent_key: IndexableKey) -> Result<Vec<script::Action>, Error> {
+        use models::{consts::ScriptActionKind, script::Action, Transform};
+
+        let raw_actions: Vec<Value> = rune::from_value(output)
+            .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "array", got })?;
+
+        let mut actions = Vec::with_capacity(raw_actions.len());
+
+        for (i, raw_action) in raw_actions.into_iter().enumerate() {
+            let parent_key = parent_key.nth(i);
+
+            let action_map: Object = rune::from_value(raw_action)
+                .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key, expected: "map", got })?;
+
+            let kind: ScriptActionKind = get_int!(action_map, "kind", parent_key);
+
+            actions.push(match kind {
+                models::consts::GIVE_ITEM => Action::GiveItem(get_int!(action_map, "def_id", parent_key)),
+                models::consts::TAKE_ITEM => Action::TakeItem(get_int!(action_map, "def_id", parent_key)),
+                models::consts::TRANSFORM_ALL => Action::Transform(Transform {
+                    from: get_int!(action_map, "from", parent_key),
+                    to: get_int!(action_map, "to", parent_key),
+                }),
+                models::consts::TALK => Action::Talk {
+                    def_id: get_int!(action_map, "def_id", parent_key),
+                    state: get_int!(action_map, "state", parent_key),
+                },
+                models::consts::MESSAGE => {
+                    let key = "message";
+
+                    let value: &Value = action_map.get(key)
+                        .ok_or(ErrorKind::FieldMissing{ key, parent_key, })?;
+
+                    Action::Message(
+                        value.clone().into_string()
+                            .map_err(|got| ErrorKind::TypeMismatch{ key: parent_key.then(key), expected: "string", got })?
+                    )
+                },
+                models::consts::DOOR_TO => Action::DoorTo {
+                    segment_id: get_int!(action_map, "segment_id", parent_key),
+                    x: get_int!(action_map, "x", parent_key),
+                    y: get_int!(action_map, "y", parent_key),
+                },
+                _ => return Err(ErrorKind::UnknownScriptActionKind { key: "kind", parent_key, kind }.into()),
+            });
+        }
+
+        Ok(actions)
     }
 
     fn sources_with_helpers() -> Result<Sources, AllocError> {
@@ -906,6 +2195,62 @@ mod rune_based {
 
         add_module!(collect_actions = collect_actions_string);
 
+        let mut script_actions_string = String::with_capacity(1024);
+
+        for (name, value) in models::consts::ALL_SCRIPT_ACTION_KINDS {
+            script_actions_string += &format!("pub const {name} = {value};\n");
+        }
+
+        script_actions_string += r#"
+            pub fn give_item(def_id) {
+                #{
+                    kind: GIVE_ITEM,
+                    def_id: def_id,
+                }
+            }
+
+            pub fn take_item(def_id) {
+                #{
+                    kind: TAKE_ITEM,
+                    def_id: def_id,
+                }
+            }
+
+            pub fn transform_all(from, to) {
+                #{
+                    kind: TRANSFORM_ALL,
+                    from: from,
+                    to: to,
+                }
+            }
+
+            pub fn talk(def_id, state) {
+                #{
+                    kind: TALK,
+                    def_id: def_id,
+                    state: state,
+                }
+            }
+
+            pub fn message(message) {
+                #{
+                    kind: MESSAGE,
+                    message: message,
+                }
+            }
+
+            pub fn door_to(segment_id, x, y) {
+                #{
+                    kind: DOOR_TO,
+                    segment_id: segment_id,
+                    x: x,
+                    y: y,
+                }
+            }
+        "#;
+
+        add_module!(script_actions = script_actions_string);
+
         Ok(sources)
     }
 
@@ -993,9 +2338,9 @@ mod rune_based {
         }
 
         fn test_eval(code: &str) {
-            match eval(code) {
+            match eval(code, None) {
                 Err(e) => {
-                    if let Error::Diagnostics(diagnostics, sources) = &e {
+                    if let ErrorKind::Diagnostics(diagnostics, sources) = e.kind() {
                         if !diagnostics.is_empty() {
                             assert!(diagnostics.is_empty(), "{diagnostics:#?}");
                         }
@@ -1106,19 +2451,710 @@ mod rune_based {
                 }
             "#;
 
-            let obj = eval(&code).expect("should eval properly");
+            let obj = eval(&code, None).expect("should eval properly");
 
             to_config(obj).expect("should extract config properly");
         }
+
+        fn small_config_with_hallway(hallway: &str) -> String {
+            format!(r#"
+                use hallways as HW;
+                use tile_flags as TF;
+                const A = TF::FLOOR | TF::ITEM_START | TF::NPC_START;
+
+                pub fn main() {{
+                    Ok(#{{
+                        hallways: [
+                            {hallway},
+                        ],
+                        entities: [
+                            #{{
+                                flags: 0,
+                                tile_sprite: 0,
+                            }},
+                        ],
+                        segments: [
+                            #{{
+                                width: 1,
+                                tiles: [A, A, A],
+                            }},
+                        ],
+                    }})
+                }}
+            "#)
+        }
+
+        #[test]
+        fn to_config_reads_hallway_params() {
+            let code = small_config_with_hallway("#{ kind: HW::ICE_PUZZLE, width: 8, levels: 2 }");
+
+            let obj = eval(&code, None).expect("should eval properly");
+
+            let config = to_config(obj).expect("should extract config properly");
+
+            match config.hallways.first() {
+                HallwaySpec::IcePuzzle(params) => {
+                    assert_eq!(params.width, Some(8));
+                    // Unspecified params keep their defaults.
+                    assert_eq!(params.height, IcePuzzleParams::default().height);
+                    assert_eq!(params.max_steps, IcePuzzleParams::default().max_steps);
+                    assert_eq!(params.levels, 2);
+                },
+                other => panic!("{other:?}"),
+            }
+        }
+
+        #[test]
+        fn to_config_rejects_invalid_hallway_params() {
+            for hallway in [
+                "#{ kind: HW::SWORD, switch_count: 200 }",
+                "#{ kind: HW::HEX_HOP, radius: -1 }",
+                "#{ kind: HW::CUBE_MAZE, size: 8 }",
+                "#{ kind: HW::BOLD, boulder_percent: 60, gem_percent: 60 }",
+                "#{ kind: HW::KEYCARD_SHUFFLE, lock_count: \"many\" }",
+            ] {
+                let obj = eval(&small_config_with_hallway(hallway), None).expect("should eval properly");
+
+                let result = to_config(obj);
+
+                assert!(
+                    matches!(
+                        result.as_ref().map_err(Error::kind),
+                        Err(ErrorKind::ParamOutOfRange { .. })
+                        | Err(ErrorKind::HallwayParamNotOdd { .. })
+                        | Err(ErrorKind::HallwayParamsSumTooLarge { .. })
+                        | Err(ErrorKind::TypeMismatch { .. })
+                    ),
+                    "{hallway}: {result:?}"
+                );
+            }
+        }
+
+        #[test]
+        fn to_manifest_reads_sound_banks() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "sounds",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        sounds: [
+                            #{ name: "click", paths: ["./click1.ogg", "./click2.ogg"] },
+                        ],
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            let manifest = to_manifest(obj).expect("should extract manifest properly");
+
+            assert_eq!(manifest.sounds.len(), 1);
+            assert_eq!(manifest.sounds[0].name, "click");
+            assert_eq!(manifest.sounds[0].paths.len(), 2);
+        }
+
+        #[test]
+        fn to_config_reads_music() {
+            let code = small_config_with_hallway(r#"#{ kind: HW::ICE_PUZZLE, track: "slippery" }"#)
+                .replacen("hallways:", "victory_track: \"fanfare\",\n hallways:", 1);
+
+            let obj = eval(&code, None).expect("should eval properly");
+
+            let config = to_config(obj).expect("should extract config properly");
+
+            assert_eq!(
+                config.music,
+                MusicSpec {
+                    // The segment has no track.
+                    segments: vec![None],
+                    hallways: vec![Some("slippery".to_string())],
+                    victory: Some("fanfare".to_string()),
+                }
+            );
+        }
+
+        #[test]
+        fn to_manifest_reads_music() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "music",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        music: [
+                            #{ name: "theme", path: "./theme.ogg" },
+                        ],
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            let manifest = to_manifest(obj).expect("should extract manifest properly");
+
+            assert_eq!(manifest.music.len(), 1);
+            assert_eq!(manifest.music[0].name, "theme");
+            assert_eq!(manifest.music[0].path, PathBuf::from("./theme.ogg"));
+        }
+
+        #[test]
+        fn to_manifest_reads_versions_and_metadata() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "described",
+                        format_version: 1,
+                        game_versions: #{ min: "0.1", max: "0.2.3" },
+                        title: "A Pak",
+                        author: "Someone",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        extra_assets: ["./README.txt"],
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            let manifest = to_manifest(obj).expect("should extract manifest properly");
+
+            assert_eq!(manifest.format_version, 1);
+            assert_eq!(
+                manifest.game_versions,
+                VersionRange {
+                    min: Version { major: 0, minor: 1, patch: 0 },
+                    max: Some(Version { major: 0, minor: 2, patch: 3 }),
+                }
+            );
+            assert_eq!(manifest.metadata.title, "A Pak");
+            assert_eq!(manifest.metadata.author, "Someone");
+            assert_eq!(manifest.metadata.description, "");
+            assert_eq!(manifest.extra_assets, vec![PathBuf::from("./README.txt")]);
+        }
+
+        #[test]
+        fn to_manifest_rejects_bad_versions() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "bad",
+                        game_versions: #{ min: "one" },
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            assert!(matches!(to_manifest(obj).as_ref().map_err(Error::kind), Err(ErrorKind::InvalidVersion { key: "min", .. })));
+        }
+
+        #[test]
+        fn to_manifest_resolves_spritesheet_names() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "sheets",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        spritesheets: [
+                            #{ name: "ice", path: "./ice.png" },
+                            #{ name: "swords", path: "./swords.png" },
+                        ],
+                        ice_puzzles: #{ sheet: "ice", offset: #{ w: 0, h: 0 } },
+                        sword: #{ sheet: "swords" },
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            let manifest = to_manifest(obj).expect("should extract manifest properly");
+
+            assert_eq!(manifest.spritesheets.len(), 2);
+            assert_eq!(manifest.specs.base_font.sheet(), 0);
+            assert_eq!(manifest.specs.ice_puzzles.sheet(), 1);
+            assert_eq!(manifest.specs.sword.sheet(), 2);
+        }
+
+        #[test]
+        fn to_manifest_reads_every_part_of_a_spec() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "specs",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        keycard_shuffle_lights: #{
+                            offset: #{ w: 1, h: 2 },
+                            tile: #{ w: 3 },
+                            tiles_per_row: 4,
+                        },
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            let manifest = to_manifest(obj).expect("should extract manifest properly");
+
+            let default_pieces = Specs::default().keycard_shuffle_lights.pieces();
+
+            assert_eq!(
+                manifest.specs.keycard_shuffle_lights.pieces(),
+                SpecPieces {
+                    sheet: 0,
+                    offset: WH { w: W::new(1), h: H::new(2) },
+                    tile: WH { w: W::new(3), h: default_pieces.tile.h },
+                    tiles_per_row: 4,
+                }
+            );
+        }
+
+        #[test]
+        fn to_manifest_rejects_empty_tiles() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "specs",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        bold: #{ tiles_per_row: 0 },
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            assert!(matches!(to_manifest(obj).as_ref().map_err(Error::kind), Err(ErrorKind::ParamOutOfRange { key: "tiles_per_row", .. })));
+
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "specs",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        bold: #{ tile: #{ w: 0, h: 16 } },
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            assert!(matches!(to_manifest(obj).as_ref().map_err(Error::kind), Err(ErrorKind::ParamOutOfRange { key: "w", .. })));
+        }
+
+        #[test]
+        fn to_manifest_rejects_duplicate_spritesheet_names() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "sheets",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        spritesheets: [
+                            #{ name: "ice", path: "./ice.png" },
+                            #{ name: "ice", path: "./more_ice.png" },
+                        ],
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            assert!(matches!(to_manifest(obj).as_ref().map_err(Error::kind), Err(ErrorKind::DuplicateSpritesheet { key: "name", .. })));
+        }
+
+        #[test]
+        fn to_manifest_allows_exactly_max_spritesheets() {
+            let with_sheets = |count: usize| {
+                let sheets: String = (0..count)
+                    .map(|i| format!("#{{ name: \"{i}\", path: \"./{i}.png\" }},"))
+                    .collect();
+
+                eval(&format!(r#"
+                    pub fn main() {{
+                        Ok(#{{
+                            name: "sheets",
+                            config_path: "./config.rn",
+                            spritesheet_path: "./gfx.png",
+                            spritesheets: [{sheets}],
+                        }})
+                    }}
+                "#), None).expect("should eval properly")
+            };
+
+            let manifest = to_manifest(with_sheets(MAX_SPRITESHEETS)).expect("should extract manifest properly");
+            assert_eq!(manifest.spritesheets.len(), MAX_SPRITESHEETS);
+
+            let result = to_manifest(with_sheets(MAX_SPRITESHEETS + 1));
+            assert!(matches!(result.as_ref().map_err(Error::kind), Err(ErrorKind::TooManySpritesheets { .. })));
+        }
+
+        #[test]
+        fn to_manifest_rejects_unknown_spritesheets() {
+            let obj = eval(r#"
+                pub fn main() {
+                    Ok(#{
+                        name: "sheets",
+                        config_path: "./config.rn",
+                        spritesheet_path: "./gfx.png",
+                        ice_puzzles: #{ sheet: "ice" },
+                    })
+                }
+            "#, None).expect("should eval properly");
+
+            assert!(matches!(to_manifest(obj).as_ref().map_err(Error::kind), Err(ErrorKind::UnknownSpritesheet { key: "sheet", .. })));
+        }
+
+        fn small_config_with_spheres(spheres: &str) -> String {
+            small_config_with_hallway("#{ kind: HW::NONE }")
+                .replacen("hallways:", &format!("spheres: {spheres},\n hallways:"), 1)
+        }
+
+        #[test]
+        fn to_config_reads_spheres() {
+            let obj = eval(&small_config_with_spheres("#{ min_count: 3, max_count: 3, push_back_percent: 0 }"), None)
+                .expect("should eval properly");
+
+            let config = to_config(obj).expect("should extract config properly");
+
+            assert_eq!(
+                config.spheres,
+                SpheresSpec {
+                    min_count: 3,
+                    max_count: 3,
+                    push_back_percent: 0,
+                    ..SpheresSpec::default()
+                }
+            );
+        }
+
+        #[test]
+        fn to_config_rejects_invalid_spheres() {
+            for spheres in [
+                "#{ min_count: 0 }",
+                "#{ max_segments: 100 }",
+                "#{ min_count: 4, max_count: 3 }",
+                "#{ push_back_percent: 101 }",
+                "5",
+            ] {
+                let obj = eval(&small_config_with_spheres(spheres), None).expect("should eval properly");
+
+                let result = to_config(obj);
+
+                assert!(
+                    matches!(
+                        result.as_ref().map_err(Error::kind),
+                        Err(ErrorKind::ParamOutOfRange { .. })
+                        | Err(ErrorKind::ParamMinGreaterThanMax { .. })
+                        | Err(ErrorKind::TypeMismatch { .. })
+                    ),
+                    "{spheres}: {result:?}"
+                );
+            }
+        }
+
+        fn generated_numbers(seed: Option<Seed>) -> Result<Vec<i64>, Error> {
+            let obj = eval(r#"
+                use generation as G;
+
+                pub fn main() {
+                    let numbers = [];
+                    for _ in 0..16 {
+                        numbers.push(G::range(10, 20));
+                    }
+                    numbers.push(G::range(5, 5));
+                    Ok(#{ numbers })
+                }
+            "#, seed)?;
+
+            let numbers = obj.get("numbers").expect("numbers should be there").clone();
+
+            Ok(rune::from_value(numbers).expect("numbers should be an array of ints"))
+        }
+
+        #[test]
+        fn generation_depends_only_on_the_seed() {
+            let a = generated_numbers(Some([1; 16])).expect("should eval properly");
+
+            assert_eq!(a, generated_numbers(Some([1; 16])).expect("should eval properly"));
+            assert_ne!(a, generated_numbers(Some([2; 16])).expect("should eval properly"));
+
+            let (last, in_range) = a.split_last().expect("there should be numbers");
+            assert_eq!(*last, 5, "an empty range should give its start");
+            assert!(in_range.iter().all(|n| (10..20).contains(n)), "{a:?}");
+
+            assert!(
+                matches!(generated_numbers(None).as_ref().map_err(Error::kind), Err(ErrorKind::Diagnostics(..))),
+                "generation should not be available without a seed"
+            );
+        }
+
+        #[test]
+        fn indexable_keys_display_full_paths() {
+            assert_eq!(IndexableKey::ROOT.to_string(), "#root");
+            assert_eq!(IndexableKey::ROOT.then("name").to_string(), "name");
+
+            let key = ik!("entities", 4).then(ik!("speeches", 0)).nth(2);
+            assert_eq!(key.to_string(), "entities[4].speeches[0][2]");
+
+            let key = ik!("entities", 4).then(ik!("on_collect", 0)).then("to").then("kind");
+            assert_eq!(key.to_string(), "entities[4].on_collect[0].to.kind");
+        }
+
+        #[test]
+        fn value_offsets_are_found_through_nested_literals() {
+            let code = r#"
+                // entities: [#{ flags: 0 }],
+                pub fn main() {
+                    let entities = [
+                        #{ flags: 0, speeches: [["hi", `a ${1}`]] },
+                        /* #{ flags: 0 } */ #{ "flags": HW::DOORS[1], tile_sprite: 'x' },
+                    ];
+
+                    Ok(#{ entities, segments: [#{ width: 1, tiles: [0, 1, 2] }] })
+                }
+            "#;
+
+            let line_of = |key: IndexableKey| {
+                value_offset(code, key).map(|offset| SourceLine::at(code, offset).number)
+            };
+
+            assert_eq!(line_of(ik!("entities", 0).then("flags")), Some(5));
+            assert_eq!(line_of(ik!("entities", 1).then("flags")), Some(6));
+            assert_eq!(line_of(ik!("entities", 0).then(ik!("speeches", 0)).nth(1)), Some(5));
+            assert_eq!(line_of(ik!("segments", 0).then(ik!("tiles", 2))), Some(9));
+            assert_eq!(line_of(ik!("entities", 2).then("flags")), None, "the two flags are equally likely");
+            assert_eq!(line_of(ik!("entities", 1).then("wants")), None);
+
+            let offset = value_offset(code, ik!("segments", 0).then(ik!("tiles", 2))).expect("should find the tile");
+            assert_eq!(&code[offset..offset + 1], "2");
+        }
+
+        #[test]
+        fn value_offsets_are_not_found_through_calls_or_blocks() {
+            let code = r#"
+                pub fn main() {
+                    let entities = [];
+                    entities.push(#{ flags: 0 });
+                    Ok(#{ entities, segments: { [] } })
+                }
+            "#;
+
+            assert_eq!(value_offset(code, ik!("entities", 0).then("flags")), Some(code.find("flags").unwrap()));
+            assert_eq!(value_offset(code, ik!("segments", 0)), None);
+        }
+
+        #[test]
+        fn source_lines_point_at_the_column() {
+            let code = "#{\n\tflags: 99,\n}";
+
+            let line = SourceLine::at(code, code.find("flags").unwrap());
+
+            assert_eq!(line.to_string(), "\n  at line 2, column 2:\n    \tflags: 99,\n    \t^");
+        }
+
+        #[test]
+        fn to_config_errors_give_full_paths() {
+            for (entity_fields, expected) in [
+                ("wants: [#{ kind: 99, value: 0 }]", "entities[0].wants[0].kind was 99"),
+                (
+                    "on_collect: [#{ kind: 1, from: #{ kind: 1, value: 0 }, to: #{ kind: 99, value: 0 } }]",
+                    "entities[0].on_collect[0].to.kind was 99",
+                ),
+                ("speeches: [[\"hi\", 5]]", "entities[0].speeches[0][1] should be of type string"),
+            ] {
+                let code = small_config_with_hallway("#{ kind: HW::NONE }")
+                    .replacen("tile_sprite: 0,", &format!("tile_sprite: 0, {entity_fields},"), 1);
+
+                let obj = eval(&code, None).expect("should eval properly");
+
+                let message = to_config(obj).expect_err("should fail to extract config").to_string();
+
+                assert!(message.contains(expected), "{message}");
+            }
+        }
+
+        #[test]
+        fn scripts_named_by_entities_can_be_run() -> Result<(), Box<dyn std::error::Error>> {
+            use script::{Action, Hook};
+
+            let code = small_config_with_hallway("#{ kind: HW::NONE }")
+                .replacen("tile_sprite: 0,", r#"tile_sprite: 0, on_interact: "greet", on_step: "broken","#, 1)
+                + r#"
+                    pub fn greet(view) {
+                        [script_actions::message("hi!"), script_actions::give_item(view.def_id)]
+                    }
+
+                    pub fn broken(view) {
+                        [#{ kind: 99 }]
+                    }
+                "#;
+
+            let config = parse(&code, [0; 16])?;
+
+            assert_eq!(config.entities.first().scripts.on_interact.as_deref(), Some("greet"));
+
+            let view = script::View { def_id: 0, ..<_>::default() };
+
+            assert_eq!(
+                config.scripts.run(Hook::Interact, "greet", [0; 16], &view)?,
+                vec![Action::Message("hi!".to_owned()), Action::GiveItem(0)],
+            );
+
+            let message = config.scripts.run(Hook::Step, "broken", [0; 16], &view)
+                .expect_err("should fail to read the actions")
+                .to_string();
+
+            assert!(message.contains("on_step[0].kind was 99"), "{message}");
+
+            Ok(())
+        }
+
+        #[test]
+        fn scripts_get_the_same_numbers_from_the_same_seed() -> Result<(), Box<dyn std::error::Error>> {
+            use script::Hook;
+
+            let code = small_config_with_hallway("#{ kind: HW::NONE }")
+                .replacen("tile_sprite: 0,", r#"tile_sprite: 0, on_interact: "roll","#, 1)
+                + r#"
+                    pub fn roll(view) {
+                        [script_actions::message(`${generation::range(0, 1000000)}`)]
+                    }
+                "#;
+
+            let config = parse(&code, [0; 16])?;
+
+            let view = script::View { def_id: 0, ..<_>::default() };
+
+            let roll = |seed| config.scripts.run(Hook::Interact, "roll", seed, &view);
+
+            let first = roll([1; 16])?;
+            assert_ne!(roll([2; 16])?, first);
+            assert_eq!(roll([1; 16])?, first, "earlier calls should not change what a seed gives");
+
+            Ok(())
+        }
+
+        #[test]
+        fn scripts_that_never_finish_are_stopped() -> Result<(), Box<dyn std::error::Error>> {
+            use script::Hook;
+
+            let code = small_config_with_hallway("#{ kind: HW::NONE }")
+                .replacen("tile_sprite: 0,", r#"tile_sprite: 0, on_interact: "spin","#, 1)
+                + r#"
+                    pub fn spin(view) {
+                        loop {}
+                    }
+                "#;
+
+            let config = parse(&code, [0; 16])?;
+
+            let view = script::View { def_id: 0, ..<_>::default() };
+
+            assert!(matches!(
+                config.scripts.run(Hook::Interact, "spin", [0; 16], &view),
+                Err(script::Error::OutOfBudget { hook: Hook::Interact, .. })
+            ));
+
+            Ok(())
+        }
+
+        #[test]
+        fn scripts_must_exist() {
+            let code = small_config_with_hallway("#{ kind: HW::NONE }")
+                .replacen("tile_sprite: 0,", r#"tile_sprite: 0, on_collect: "nowhere","#, 1);
+
+            let message = parse(&code, [0; 16]).expect_err("should fail to find the script").to_string();
+
+            assert!(message.contains("entities[0].on_collect was \"nowhere\""), "{message}");
+            assert!(message.contains("on_collect: \"nowhere\",\n"), "should show the line to fix: {message}");
+        }
     }
 
     fn init_context() -> Result<Context, ContextError> {
         let /* mut */ context = Context::with_default_modules()?;
 
-        // TODO? Add native modules if we turn out to need any?
-
         Ok(context)
     }
+
+    /// The RNG behind the `generation` module.
+    type GenerationRng = std::sync::Arc<std::sync::Mutex<xs::Xs>>;
+
+    fn lock(rng: &GenerationRng) -> std::sync::MutexGuard<'_, xs::Xs> {
+        rng.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// A native module with an RNG seeded from `seed`, and read-only parameters
+    /// that are useful when generating parts of a config. Also returns the RNG,
+    /// so it can be reseeded later.
+    fn generation_module(seed: Seed) -> Result<(Module, GenerationRng), ContextError> {
+        use std::sync::Mutex;
+
+        // Mixed in, so the config does not see the same numbers that world
+        // generation starts with.
+        const SALT: &[u8; 16] = b"ripe config rng!";
+
+        let mut config_seed = seed;
+        for (byte, salt) in config_seed.iter_mut().zip(SALT) {
+            *byte ^= salt;
+        }
+
+        // The VM runs on one thread, and calls these in the order the script
+        // does, so the results only depend on the seed and the script.
+        let rng: GenerationRng = std::sync::Arc::new(Mutex::new(xs::from_seed(config_seed)));
+
+        macro_rules! with_rng {
+            (|$rng: ident $(, $param: ident : $type: ty)*| -> $output: ty $body: block) => ({
+                let rng = rng.clone();
+
+                move |$($param: $type),*| -> $output {
+                    let $rng: &mut xs::Xs = &mut lock(&rng);
+
+                    $body
+                }
+            })
+        }
+
+        let mut module = Module::with_item(["generation"])?;
+
+        module.constant("MAX_ENTITY_DEFINITIONS", i64::from(DefId::MAX)).build()?;
+
+        module.function("seed", move || -> Vec<i64> {
+            seed.iter().map(|&byte| i64::from(byte)).collect()
+        }).build()?;
+
+        // A number from `min` up to, but not including, `one_past_max`.
+        // Returns `min` if there are no such numbers.
+        module.function("range", with_rng!(|rng, min: i64, one_past_max: i64| -> i64 {
+            let Ok(span) = u32::try_from(one_past_max.saturating_sub(min)) else {
+                return if one_past_max > min {
+                    // Spans that large are unlikely to be useful, so cap them.
+                    min + i64::from(xs::range(rng, 0..u32::MAX))
+                } else {
+                    min
+                }
+            };
+
+            if span == 0 {
+                return min
+            }
+
+            min + i64::from(xs::range(rng, 0..span))
+        })).build()?;
+
+        // Returns true `percent` percent of the time.
+        module.function("chance", with_rng!(|rng, percent: i64| -> bool {
+            i64::from(xs::range(rng, 0..100)) < percent
+        })).build()?;
+
+        // One of the elements of `values`, or `None` if it is empty.
+        module.function("pick", with_rng!(|rng, values: Vec<Value>| -> Option<Value> {
+            if values.is_empty() {
+                return None
+            }
+
+            let i = xs::index(rng, 0..values.len());
+
+            values.into_iter().nth(i)
+        })).build()?;
+
+        // A shuffled copy of `values`.
+        module.function("shuffle", with_rng!(|rng, values: Vec<Value>| -> Vec<Value> {
+            let mut values = values;
+
+            xs::shuffle(rng, &mut values);
+
+            values
+        })).build()?;
+
+        Ok((module, rng))
+    }
 }
 
 #[cfg(false)]
@@ -1868,9 +3904,11 @@ mod hardcoded {
             hallways: Vec1::try_from(
                 vec![
                     models::config::HallwaySpec::None,
-                    models::config::HallwaySpec::IcePuzzle,
+                    models::config::HallwaySpec::IcePuzzle(<_>::default()),
                 ],
             ).unwrap(),
+            spheres: <_>::default(),
+            music: <_>::default(),
         };
 
         Ok(config)
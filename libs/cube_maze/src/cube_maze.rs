@@ -1,13 +1,16 @@
 use gfx::{Commands, AddDrawCommands};
+use hallway::Hallway;
 use gfx_sizes::{ARGB};
-use platform_types::{command, sprite, unscaled, Button, Dir, DirFlag, Input, Speaker};
+use platform_types::{command, config::CubeMazeParams, sprite, unscaled, Button, Dir, DirFlag, Input, Speaker, SFX};
 use qrs::{QRS, QRSD, Q, R, qr};
 use vec1::{Grid1, Grid1Spec};
 use xs::{Seed, Xs};
 
+use serde::{Deserialize, Serialize};
+
 type TileSprite = u8;
 
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Copy, Default, Debug, Deserialize, Serialize)]
 enum GoalFrame {
     #[default]
     Zero, // Blank
@@ -31,13 +34,13 @@ impl GoalFrame {
     const DEFAULT: Self = Self::Zero;
 }
 
-#[derive(Clone, Copy, Default, Debug)]
+#[derive(Clone, Copy, Default, Debug, Deserialize, Serialize)]
 struct Goal {
     xy: face::XY,
     frame: GoalFrame,
 }
 
-#[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Deserialize, Serialize)]
 enum TileKind {
     #[default]
     Wall,
@@ -51,7 +54,7 @@ impl TileKind {
     ];
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
 struct Tile {
     kind: TileKind,
 }
@@ -66,7 +69,7 @@ mod face {
     pub type X = unscaled::Inner;
     pub type Y = unscaled::Inner;
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
     pub struct XY {
         pub x: X,
         pub y: Y,
@@ -179,7 +182,7 @@ mod face {
         pub target: XY
     }
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Face {
         pub player: XY,
         pub player_offset: Offset,
@@ -215,7 +218,7 @@ mod face {
         ];
     }
 
-    #[derive(Clone, Default, Debug)]
+    #[derive(Clone, Default, Debug, Deserialize, Serialize)]
     pub struct Faces {
         pub width: Width,
         pub top: Face,
@@ -254,8 +257,9 @@ mod face {
     }
 
     impl Faces {
-        pub fn new(rng: &mut Xs) -> Self {
-            let width: Width = 11; // looks bad if this isn't an odd number, and it should be >= 7
+        pub fn new(rng: &mut Xs, width: Width) -> Self {
+            // Looks bad if this isn't an odd number, and it should be >= 7
+            let width: Width = width.max(7) | 1;
             let width_usize = width as usize;
             let length = width_usize * width_usize;
 
@@ -452,7 +456,7 @@ mod face {
     
         use super::*;
     
-        #[derive(Clone, Copy, Default, PartialEq, Eq)]
+        #[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
         pub struct Offset {
             xyd: unscaled::XYD,
         }
@@ -529,29 +533,31 @@ mod face {
 }
 use face::{Faces, offset::Offset};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct State {
     pub seed: Seed, // For restarting
+    pub params: CubeMazeParams, // Also for restarting
     pub rng: Xs,
     pub faces: Faces,
     pub tick_count: u64,
 }
 
 impl State {
-    pub fn new(rng: &mut Xs, specs: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, specs: &sprite::Specs, params: CubeMazeParams) -> Self {
         let seed = xs::new_seed(rng);
 
-        Self::init(seed, specs)
+        Self::init(seed, specs, params)
     }
 
-    fn init(seed: Seed, _specs: &sprite::Specs) -> Self {
+    fn init(seed: Seed, _specs: &sprite::Specs, params: CubeMazeParams) -> Self {
         let mut rng_ = xs::from_seed(seed);
         let rng = &mut rng_;
 
-        let faces = Faces::new(rng);
+        let faces = Faces::new(rng, params.size);
 
         Self {
             seed,
+            params,
             rng: rng_,
             faces,
             .. <_>::default()
@@ -560,7 +566,7 @@ impl State {
 
     #[allow(unused)]
     fn restart(&mut self, specs: &sprite::Specs) {
-        *self = Self::init(self.seed, specs);
+        *self = Self::init(self.seed, specs, self.params);
     }
 
     pub fn all_offsets_settled(&self) -> bool {
@@ -637,7 +643,10 @@ impl State {
         self.tick_count = self.tick_count.wrapping_add(1);
     }
 
-    fn move_players(&mut self, tile_wh: unscaled::WH, dir: Dir) {
+    fn move_players(&mut self, tile_wh: unscaled::WH, dir: Dir, speaker: &mut Speaker) {
+        // All the faces move at once, so they should only make one sound.
+        let mut bumped = false;
+
         for face_kind in face::Kind::ALL {
             let face = match face_kind {
                 face::Kind::Top => &mut self.faces.top,
@@ -655,10 +664,14 @@ impl State {
                     );
                     face.player = new_xy;
                 } else {
-                    // TODO? Good place for bump SFX
+                    bumped = true;
                 }
             }
         }
+
+        if bumped {
+            speaker.request_sfx(SFX::BUMP);
+        }
     }
 
     pub fn update_and_render(
@@ -666,7 +679,7 @@ impl State {
         commands: &mut Commands,
         specs: &sprite::Specs,
         input: Input,
-        _speaker: &mut Speaker,
+        speaker: &mut Speaker,
     ) {
         let tile_wh = specs.cube_maze_sides.tile();
 
@@ -675,7 +688,7 @@ impl State {
         //
 
         if let Some(dir) = input.dir_pressed_this_frame() {
-            self.move_players(tile_wh, dir);
+            self.move_players(tile_wh, dir, speaker);
         }
 
         if input.pressed_this_frame(Button::START) {
@@ -771,3 +784,29 @@ impl State {
         }
     }
 }
+
+impl Hallway for State {
+    type Params = CubeMazeParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn reset(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+}
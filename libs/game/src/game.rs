@@ -1,10 +1,10 @@
-use features::invariant_assert;
 use models::{
     config::{Config},
     offset,
+    script::{self, Hook, Scripts},
     sprite,
     speeches,
-    CollectAction,
+    xy,
     DefId,
     Entity,
     Location,
@@ -20,11 +20,13 @@ type DoorTarget = Location;
 
 use xs::{Xs, Seed};
 
+use serde::{Deserialize, Serialize};
+
 use platform_types::{arrow_timer::{ArrowTimer}, Dir};
 use vec1::Vec1;
-use world::{World, HallwayStates};
+use world::{World, HallwayStates, Music};
 pub use world::{EntityKey, is_passable};
-pub use world::hallway::State as HallwayState;
+pub use world::hallway::{State as HallwayState, GIVE_UP_HOLD_FRAMES};
 
 // Proposed Steps
 // * Make the simplest task: Go find a thing and bring it to the person who wants it ✔
@@ -467,14 +469,14 @@ fn can_walk_onto(world: &World, key @ EntityKey { segment_id, xy: XY{ x, y } }:
 /// 64k speech boxes ought to be enough for anybody!
 pub type SpeechIndex = u16;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub enum PostTalkingAction {
     #[default]
     NoOp,
     TakeItem(EntityKey, DefId),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TalkingState {
     pub key: speeches::Key,
     pub speech_index: SpeechIndex,
@@ -500,7 +502,7 @@ impl TalkingState {
     }
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
 pub enum Mode {
     #[default]
     Walking,
@@ -516,10 +518,12 @@ pub enum Mode {
     Hallway{
         source: Location,
         target: Location,
+        /// How many frames SELECT has been held for. See `GIVE_UP_HOLD_FRAMES`.
+        give_up_frames: FrameCount,
     },
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct FadeMessageSpec {
     pub message: String,
     pub xy: XY,
@@ -539,7 +543,7 @@ pub type FadeMessageSpecs = Vec<FadeMessageSpec>;
 
 type FrameCount = u16;
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct DoorAnimation {
     frame: FrameCount,
     is_dramatic: bool,
@@ -571,7 +575,7 @@ impl DoorAnimation {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct State {
     pub rng: Xs,
     pub world: World,
@@ -587,12 +591,25 @@ pub struct State {
     // }
     pub goal_door_tile_sprite: TileSprite,
     pub hallway_states: HallwayStates,
+    pub music: Music,
 }
 
 impl State {
     pub fn all_entities_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
         self.world.all_entities_mut()
     }
+
+    /// The name of the music track that should be playing right now, if any.
+    pub fn current_track(&self) -> Option<&str> {
+        match &self.mode {
+            Mode::Hallway { source, target, .. } => self.music.hallway(*source, *target),
+            Mode::Victory(_) => self.music.victory(),
+            Mode::Walking
+            | Mode::Inventory { .. }
+            | Mode::Talking(_)
+            | Mode::DoorTo(..) => self.music.segment(self.world.segment_id),
+        }
+    }
 }
 
 pub type Error = world::Error;
@@ -608,6 +625,7 @@ impl State {
             entity_defs,
             goal_door_tile_sprite,
             hallway_states,
+            music,
         } = world::generate(&mut rng, &config, specs)?;
 
         Ok(State {
@@ -621,10 +639,202 @@ impl State {
             entity_defs,
             goal_door_tile_sprite,
             hallway_states,
+            music,
         })
     }
 }
 
+pub mod save {
+    //! A versioned format for saving a run in progress, so it can be resumed later.
+    //! The layout is a magic number, then a little-endian format version, then the
+    //! bincode encoded payload.
+
+    use super::State;
+    use models::ContentHash;
+    use xs::Seed;
+
+    use serde::{Deserialize, Serialize};
+
+    /// Bump this whenever a change to `State`, or anything inside it, means that
+    /// previously written saves would no longer decode correctly.
+    pub const FORMAT_VERSION: u32 = 7;
+
+    const MAGIC: [u8; 4] = *b"RIPE";
+
+    const HEADER_LEN: usize = MAGIC.len() + core::mem::size_of::<u32>();
+
+    #[derive(Debug)]
+    pub enum Error {
+        NotASave,
+        UnsupportedVersion(u32),
+        /// The save was made with a different pak than the current one.
+        PakMismatch { expected: ContentHash, actual: ContentHash },
+        Encode(bincode::Error),
+        Decode(bincode::Error),
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use Error::*;
+            match self {
+                NotASave => write!(f, "That does not look like a save file."),
+                UnsupportedVersion(version) => write!(
+                    f,
+                    "Save format version {version} is not supported. Only version {FORMAT_VERSION} is."
+                ),
+                PakMismatch { expected, actual } => write!(
+                    f,
+                    "The save was made with a pak with hash {expected:016x}, but the loaded pak has hash {actual:016x}."
+                ),
+                Encode(e) => write!(f, "Could not encode save: {e}"),
+                Decode(e) => write!(f, "Could not decode save: {e}"),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            use Error::*;
+            match self {
+                NotASave
+                | UnsupportedVersion(_)
+                | PakMismatch { .. } => None,
+                Encode(e)
+                | Decode(e) => Some(e),
+            }
+        }
+    }
+
+    #[derive(Serialize)]
+    struct SaveRef<'state> {
+        seed: Seed,
+        pak_hash: ContentHash,
+        state: &'state State,
+    }
+
+    #[derive(Deserialize)]
+    struct Save {
+        seed: Seed,
+        pak_hash: ContentHash,
+        state: State,
+    }
+
+    /// The seed is stored alongside the state, so that things like restarting from an
+    /// error can use the same seed the run started with. The hash of the pak is
+    /// stored too, since a save is meaningless with a different pak.
+    pub fn to_bytes(seed: Seed, pak_hash: ContentHash, state: &State) -> Result<Vec<u8>, Error> {
+        let mut output = Vec::with_capacity(HEADER_LEN + 4096);
+
+        output.extend_from_slice(&MAGIC);
+        output.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+
+        bincode::serialize_into(&mut output, &SaveRef { seed, pak_hash, state })
+            .map_err(Error::Encode)?;
+
+        Ok(output)
+    }
+
+    /// `pak_hash` is the hash of the current pak, which must match the saved one.
+    pub fn from_bytes(bytes: &[u8], pak_hash: ContentHash) -> Result<(Seed, State), Error> {
+        if bytes.len() < HEADER_LEN
+        || bytes[..MAGIC.len()] != MAGIC {
+            return Err(Error::NotASave);
+        }
+
+        let mut version_bytes = [0; 4];
+        version_bytes.copy_from_slice(&bytes[MAGIC.len()..HEADER_LEN]);
+        let version = u32::from_le_bytes(version_bytes);
+
+        // When we bump the version, this is where migrations from older versions would go.
+        if version != FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let save: Save = bincode::deserialize(&bytes[HEADER_LEN..])
+            .map_err(Error::Decode)?;
+
+        if save.pak_hash != pak_hash {
+            return Err(Error::PakMismatch { expected: save.pak_hash, actual: pak_hash });
+        }
+
+        Ok((save.seed, save.state))
+    }
+
+    #[cfg(test)]
+    mod round_trip_works {
+        use super::*;
+
+        const PAK_HASH: ContentHash = 0x1234;
+
+        fn generated_state(seed: Seed) -> State {
+            State::new(
+                &<_>::default(),
+                seed,
+                world::testing::example_config(),
+            ).expect("example config should generate")
+        }
+
+        #[test]
+        fn on_a_freshly_generated_state() {
+            let seed = [1; 16];
+            let state = generated_state(seed);
+
+            let bytes = to_bytes(seed, PAK_HASH, &state).unwrap();
+
+            let (loaded_seed, loaded) = from_bytes(&bytes, PAK_HASH).unwrap();
+
+            assert_eq!(loaded_seed, seed);
+            assert_eq!(loaded.rng, state.rng);
+            assert_eq!(loaded.world.player_key(), state.world.player_key());
+            // Some minigame state uses `HashMap`s, so the bytes themselves can come out
+            // in a different order, but the amount of data should always match.
+            assert_eq!(to_bytes(loaded_seed, PAK_HASH, &loaded).unwrap().len(), bytes.len());
+        }
+
+        #[test]
+        fn on_a_state_in_a_hallway() {
+            let seed = [2; 16];
+            let mut state = generated_state(seed);
+
+            let source = state.world.player_key();
+            let mut target = source;
+            target.xy.x = models::xy::x(1);
+            state.mode = super::super::Mode::Hallway { source, target, give_up_frames: 7 };
+
+            let bytes = to_bytes(seed, PAK_HASH, &state).unwrap();
+
+            let (_, loaded) = from_bytes(&bytes, PAK_HASH).unwrap();
+
+            assert_eq!(format!("{:?}", loaded.mode), format!("{:?}", state.mode));
+        }
+
+        #[test]
+        fn rejecting_other_versions() {
+            let seed = [3; 16];
+            let state = generated_state(seed);
+
+            let mut bytes = to_bytes(seed, PAK_HASH, &state).unwrap();
+            bytes[MAGIC.len()..HEADER_LEN].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+
+            assert!(matches!(from_bytes(&bytes, PAK_HASH), Err(Error::UnsupportedVersion(v)) if v == FORMAT_VERSION + 1));
+            assert!(matches!(from_bytes(b"not a save", PAK_HASH), Err(Error::NotASave)));
+        }
+
+        #[test]
+        fn rejecting_other_paks() {
+            let seed = [4; 16];
+            let state = generated_state(seed);
+
+            let bytes = to_bytes(seed, PAK_HASH, &state).unwrap();
+
+            assert!(matches!(
+                from_bytes(&bytes, PAK_HASH + 1),
+                Err(Error::PakMismatch { expected: PAK_HASH, .. })
+            ));
+        }
+    }
+}
+
 fn xy_in_dir(XY { x, y }: XY, dir: Dir) -> Option<XY> {
     use Dir::*;
 
@@ -650,9 +860,12 @@ pub fn get_speech<'speeches>(speeches: &'speeches Speeches, key: speeches::Key,
 }
 
 impl State {
-    pub fn walk(&mut self, dir: Dir) {
+    /// Runs the `on_step` script of whatever the player walks onto, if it has one,
+    /// before anything else happens. So the script can change what that is. If
+    /// the script starts talking or goes through a door, that is all that happens.
+    pub fn walk(&mut self, dir: Dir, scripts: &Scripts) -> Result<(), script::Error> {
         let Some(new_xy) = xy_in_dir(self.world.player.xy, dir) else {
-            return
+            return Ok(())
         };
 
         let new_key = self.world.local_key(new_xy);
@@ -661,7 +874,7 @@ impl State {
             if let Some(mob) = self.world.mobs.get(new_key) {
                 if !mob.is_steppable() {
                     // Doors or other things which may become steppable, but aren't now.
-                    return
+                    return Ok(())
                 }
             }
 
@@ -675,6 +888,22 @@ impl State {
 
             let key = self.world.player_key();
 
+            if let Some(mob) = self.world.mobs.get(key) {
+                if let Some(name) = mob.script(Hook::Step) {
+                    let name = name.to_owned();
+                    let view = self.script_view(mob);
+                    let mode_before = core::mem::discriminant(&self.mode);
+
+                    self.run_script(scripts, Hook::Step, &name, &view)?;
+
+                    // Otherwise stepping onto a door would overwrite where the
+                    // script sent the player, or what it had them say.
+                    if core::mem::discriminant(&self.mode) != mode_before {
+                        return Ok(())
+                    }
+                }
+            }
+
             if let Some(mob) = self.world.mobs.get(key) {
                 if mob.is_collectable() {
                     self.shake_amount = 5;
@@ -684,7 +913,14 @@ impl State {
                         // but we'd presumbably need to change a bunch of other things
                         // too, to make multiple threads work.
                         .expect("We just checked for it a moment ago!");
+                    let on_collect = steppable.script(Hook::Collect).map(str::to_owned);
                     self.push_inventory(key, steppable);
+
+                    // `push_inventory` puts the item at the end.
+                    if let (Some(name), Some(item)) = (on_collect, self.world.player.inventory.last()) {
+                        let view = self.script_view(item);
+                        self.run_script(scripts, Hook::Collect, &name, &view)?;
+                    }
                 } else if mob.is_victory() {
                     let mut animation = DoorAnimation::default();
                     animation.is_dramatic = true;
@@ -696,26 +932,55 @@ impl State {
                 }
             }
         }
+
+        Ok(())
+    }
+
+    /// The direction from the player to `xy`, if `xy` is right next to them.
+    pub fn dir_to_adjacent(&self, xy: XY) -> Option<Dir> {
+        Dir::ALL.into_iter().find(|&dir| xy_in_dir(self.world.player.xy, dir) == Some(xy))
+    }
+
+    /// Walks in `dir`, unless there is something there that can't be stepped on,
+    /// in which case the player interacts with it instead.
+    pub fn walk_or_interact(&mut self, dir: Dir, scripts: &Scripts) -> Result<(), script::Error> {
+        let Some(target_xy) = xy_in_dir(self.world.player.xy, dir) else {
+            return Ok(())
+        };
+
+        match self.world.mobs.get(self.world.local_key(target_xy)) {
+            Some(mob) if !mob.is_steppable() => self.interact(dir, scripts),
+            _ => self.walk(dir, scripts),
+        }
     }
 
-    #[must_use]
-    pub fn interact(&mut self, dir: Dir) {
+    /// If what the player interacts with has an `on_interact` script, then that
+    /// runs instead of the usual handling of wants and speeches.
+    pub fn interact(&mut self, dir: Dir, scripts: &Scripts) -> Result<(), script::Error> {
         let entity = &self.world.player;
 
         let Some(target_xy) = xy_in_dir(entity.xy, dir) else {
             self.fade_message_specs.push(FadeMessageSpec::new(format!("there's nothing there."), entity.xy));
-            return
+            return Ok(())
         };
 
         let key = self.world.local_key(target_xy);
 
+        if let Some(mob) = self.world.mobs.get(key) {
+            if let Some(name) = mob.script(Hook::Interact) {
+                let name = name.to_owned();
+                let view = self.script_view(mob);
+                return self.run_script(scripts, Hook::Interact, &name, &view);
+            }
+        }
+
         let entity = &mut self.world.player;
 
         let Some(interactable) = self.world.mobs.get_mut(key) else {
             self.fade_message_specs.push(
                 FadeMessageSpec::new(format!("there's nobody there."), entity.xy)
             );
-            return
+            return Ok(())
         };
 
         let mut post_action = PostTalkingAction::NoOp;
@@ -734,7 +999,7 @@ impl State {
         if let Some(speeches) = self.speeches.get(speeches_key) {
             if !speeches.is_empty() {
                 self.mode = Mode::Talking(TalkingState::new_with_action(speeches_key, post_action));
-                return
+                return Ok(())
             }
         }
 
@@ -747,6 +1012,120 @@ impl State {
                 entity.xy
             )
         );
+
+        Ok(())
+    }
+
+    fn script_view(&self, entity: &Entity) -> script::View {
+        script::View {
+            def_id: entity.def_id(),
+            inventory: entity.inventory.iter().map(Entity::def_id).collect(),
+            player_inventory: self.world.player.inventory.iter().map(Entity::def_id).collect(),
+            segment_id: self.world.segment_id,
+        }
+    }
+
+    fn run_script(
+        &mut self,
+        scripts: &Scripts,
+        hook: Hook,
+        name: &str,
+        view: &script::View,
+    ) -> Result<(), script::Error> {
+        // Drawn from the saved RNG, so a loaded save gives scripts the same
+        // numbers the run it was saved from would have.
+        let seed = xs::new_seed(&mut self.rng);
+
+        let actions = scripts.run(hook, name, seed, view)?;
+
+        // All checked before any are carried out, so a bad action later in the
+        // list does not leave the ones before it half done.
+        for action in &actions {
+            self.check_action(hook, name, action)?;
+        }
+
+        for action in actions {
+            self.act(action);
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error if an action that the script called `name` returned
+    /// refers to something that does not exist.
+    fn check_action(&self, hook: Hook, name: &str, action: &script::Action) -> Result<(), script::Error> {
+        use script::Action::*;
+
+        let check_def = |def_id: DefId| {
+            if usize::from(def_id) < self.entity_defs.len() {
+                Ok(())
+            } else {
+                Err(script::Error::UnknownDefId { hook, name: name.to_owned(), def_id })
+            }
+        };
+
+        match *action {
+            GiveItem(def_id) => check_def(def_id)?,
+            TakeItem(_) | Message(_) => {},
+            Transform(models::Transform { from, to }) => {
+                check_def(from)?;
+                check_def(to)?;
+            },
+            Talk { def_id, state } => {
+                let has_speeches = self.speeches.get(speeches::Key { def_id, state })
+                    .is_some_and(|speeches| !speeches.is_empty());
+
+                if !has_speeches {
+                    return Err(script::Error::NoSpeech { hook, name: name.to_owned(), def_id, state });
+                }
+            },
+            DoorTo { segment_id, x, y } => {
+                let is_in_world = self.world.segments.get(usize::from(segment_id))
+                    .is_some_and(|segment| xy_to_i(segment, xy::x(x), xy::y(y)).is_ok());
+
+                if !is_in_world {
+                    return Err(script::Error::NoSuchTile { hook, name: name.to_owned(), segment_id, x, y });
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Carries out an action that a script returned, once `check_action` has
+    /// found it to be fine.
+    fn act(&mut self, action: script::Action) {
+        use script::Action::*;
+
+        match action {
+            GiveItem(def_id) => {
+                let item = world::to_entity(&self.entity_defs[usize::from(def_id)], self.world.player.xy);
+                self.push_inventory(self.world.player_key(), item);
+            },
+            TakeItem(def_id) => {
+                let inventory = &mut self.world.player.inventory;
+
+                if let Some(i) = inventory.iter().position(|item| item.def_id() == def_id) {
+                    inventory.remove(i);
+                }
+            },
+            Transform(models::Transform { from, to }) => {
+                let to_def = &self.entity_defs[usize::from(to)];
+
+                world::transform_all_matching(&mut self.world, from, to_def);
+            },
+            Talk { def_id, state } => {
+                self.mode = Mode::Talking(TalkingState::new(speeches::Key { def_id, state }));
+            },
+            Message(message) => {
+                self.fade_message_specs.push(FadeMessageSpec::new(message, self.world.player.xy));
+            },
+            DoorTo { segment_id, x, y } => {
+                let xy = XY { x: xy::x(x), y: xy::y(y) };
+
+                self.mode = Mode::DoorTo(Location { segment_id, xy }, <_>::default());
+            },
+        }
     }
 
     pub fn tick(&mut self) {
@@ -778,14 +1157,14 @@ impl State {
                         warp_player_to(&mut self.world, &target);
                         self.mode = Mode::Walking;
                     } else {
-                        self.mode = Mode::Hallway{ source, target };
+                        self.mode = Mode::Hallway{ source, target, give_up_frames: 0 };
                     }
                 } else {
                     // Do this last, so that the last frame is shown
                     advance_door_animation!(animation);
                 }
             }
-            Mode::Hallway{ source, target } => {
+            Mode::Hallway{ source, target, .. } => {
                 let do_warp = if let Some(hallway) = self.hallway_states.get_mut(*source, *target) {
                     hallway.is_complete()
                 } else {
@@ -816,23 +1195,178 @@ impl State {
         }
     }
 
+    /// Backs out of the current hallway, if there is one, leaving the player at the
+    /// door they went in through. What happens to the hallway's progress is up to
+    /// its `Hallway::give_up`.
+    pub fn give_up_on_hallway(&mut self, specs: &sprite::Specs) {
+        let Mode::Hallway{ source, target, .. } = self.mode else { return };
+
+        if let Some(hallway) = self.hallway_states.get_mut(source, target) {
+            hallway.give_up(specs);
+        }
+
+        self.mode = Mode::Walking;
+    }
+
     pub fn push_inventory(&mut self, target_key: EntityKey, item: Entity) {
-        if target_key == self.world.player_key() {
-            for action in &item.transformable.on_collect {
-                match action {
-                    CollectAction::Transform(models::Transform{ from, to }) => {
-                        if let Some(to_def) = self.entity_defs.get((*to) as usize) {
-                            world::transform_all_matching(&mut self.world, *from, to_def);
-                        } else {
-                            invariant_assert!(false, "Why are we trying to transform something into something that doesn't exist? to {to}");
-                        }
-                    }
-                }
-            }
+        world::push_inventory(&mut self.world, &self.entity_defs, target_key, item);
+    }
+
+    /// Has the entity at `receiving_entity_key` take the item with `def_id` from the
+    /// player, in exchange for what they were holding, if anything.
+    pub fn take_item(&mut self, receiving_entity_key: EntityKey, def_id: DefId) {
+        let giving_entity_key = self.world.player_key();
+
+        world::give_item(
+            &mut self.world,
+            &self.entity_defs,
+            giving_entity_key,
+            receiving_entity_key,
+            def_id,
+        );
+    }
+}
+
+#[cfg(test)]
+mod scripts_work {
+    use super::*;
+    use script::Action;
+    use std::sync::Arc;
+
+    /// Always returns the same actions, instead of running anything.
+    struct Returns(Vec<Action>);
+
+    impl script::Run for Returns {
+        fn run(&self, _: Hook, _: &str, _: Seed, _: &script::View) -> Result<Vec<Action>, script::Failure> {
+            Ok(self.0.clone())
         }
+    }
+
+    /// Returns no actions, but remembers the seeds it was run with.
+    #[derive(Default)]
+    struct Seeds(std::sync::Mutex<Vec<Seed>>);
 
-        if let Some(target) = self.world.get_entity_mut(target_key) {
-            target.inventory.push(item);
+    impl script::Run for Seeds {
+        fn run(&self, _: Hook, _: &str, seed: Seed, _: &script::View) -> Result<Vec<Action>, script::Failure> {
+            self.0.lock().unwrap().push(seed);
+            Ok(Vec::new())
         }
     }
-}
\ No newline at end of file
+
+    /// Puts an entity with `on_interact` set next to the player, and returns the
+    /// direction it is in.
+    fn state_with_scripted_mob() -> (State, Dir) {
+        let mut state = State::new(&<_>::default(), [5; 16], world::testing::example_config())
+            .expect("example config should generate");
+
+        let player_xy = state.world.player.xy;
+        let (dir, xy) = Dir::ALL.into_iter()
+            .find_map(|dir| xy_in_dir(player_xy, dir).map(|xy| (dir, xy)))
+            .expect("the player should have a neighbouring tile");
+
+        let mut mob = world::to_entity(&state.entity_defs[1], xy);
+        mob.transformable.scripts.on_interact = Some("main".to_owned());
+        state.world.mobs.insert(state.world.segment_id, mob);
+
+        (state, dir)
+    }
+
+    #[test]
+    fn in_place_of_the_usual_interaction() {
+        let (mut state, dir) = state_with_scripted_mob();
+        let item_count = state.world.player.inventory.len();
+
+        let scripts = Scripts::new(Arc::new(Returns(vec![
+            Action::GiveItem(0),
+            Action::Message("here you go.".to_owned()),
+        ])));
+
+        state.interact(dir, &scripts).unwrap();
+
+        assert_eq!(state.world.player.inventory.len(), item_count + 1);
+        assert_eq!(state.world.player.inventory.last().map(Entity::def_id), Some(0));
+        assert_eq!(state.fade_message_specs.len(), 1);
+        assert!(matches!(state.mode, Mode::Walking));
+    }
+
+    #[test]
+    fn in_place_of_the_usual_stepping_if_they_change_the_mode() {
+        use models::consts::{DOOR, STEPPABLE};
+
+        let mut state = State::new(&<_>::default(), [5; 16], world::testing::example_config())
+            .expect("example config should generate");
+
+        let player_xy = state.world.player.xy;
+        let (dir, xy) = Dir::ALL.into_iter()
+            .filter_map(|dir| xy_in_dir(player_xy, dir).map(|xy| (dir, xy)))
+            .find(|&(_, xy)| can_walk_onto(&state.world, state.world.local_key(xy)))
+            .expect("the player should have a tile they can walk onto");
+
+        let mut door = world::to_entity(&state.entity_defs[1], xy);
+        door.transformable.flags |= STEPPABLE | DOOR;
+        door.transformable.scripts.on_step = Some("main".to_owned());
+        state.world.mobs.insert(state.world.segment_id, door);
+
+        let destination = Location { segment_id: state.world.segment_id, xy: player_xy };
+
+        let scripts = Scripts::new(Arc::new(Returns(vec![Action::DoorTo {
+            segment_id: destination.segment_id,
+            x: player_xy.x.get(),
+            y: player_xy.y.get(),
+        }])));
+
+        state.walk(dir, &scripts).unwrap();
+
+        assert!(matches!(state.mode, Mode::DoorTo(target, _) if target == destination));
+    }
+
+    #[test]
+    fn with_the_same_randomness_after_a_save_is_loaded() {
+        let (mut state, dir) = state_with_scripted_mob();
+
+        let bytes = save::to_bytes([5; 16], 0, &state).unwrap();
+        let (_, mut loaded) = save::from_bytes(&bytes, 0).unwrap();
+
+        let seeds = Arc::new(Seeds::default());
+        let scripts = Scripts::new(seeds.clone());
+
+        state.interact(dir, &scripts).unwrap();
+        state.interact(dir, &scripts).unwrap();
+        loaded.interact(dir, &scripts).unwrap();
+
+        let seeds = seeds.0.lock().unwrap();
+        assert_ne!(seeds[0], seeds[1], "each run should get a new seed");
+        assert_eq!(seeds[0], seeds[2]);
+    }
+
+    #[test]
+    fn with_actions_that_refer_to_nothing_being_errors() {
+        let (mut state, dir) = state_with_scripted_mob();
+
+        let scripts = Scripts::new(Arc::new(Returns(vec![Action::GiveItem(DefId::MAX)])));
+
+        assert!(matches!(
+            state.interact(dir, &scripts),
+            Err(script::Error::UnknownDefId { def_id: DefId::MAX, .. })
+        ));
+
+        let scripts = Scripts::new(Arc::new(Returns(vec![Action::DoorTo { segment_id: 0, x: u16::MAX, y: 0 }])));
+
+        assert!(matches!(state.interact(dir, &scripts), Err(script::Error::NoSuchTile { .. })));
+        assert!(matches!(state.interact(dir, &Scripts::default()), Err(script::Error::NothingToRun { .. })));
+    }
+
+    #[test]
+    fn without_carrying_out_any_actions_if_one_refers_to_nothing() {
+        let (mut state, dir) = state_with_scripted_mob();
+        let item_count = state.world.player.inventory.len();
+
+        let scripts = Scripts::new(Arc::new(Returns(vec![
+            Action::GiveItem(0),
+            Action::Talk { def_id: DefId::MAX, state: 0 },
+        ])));
+
+        assert!(matches!(state.interact(dir, &scripts), Err(script::Error::NoSpeech { .. })));
+        assert_eq!(state.world.player.inventory.len(), item_count);
+    }
+}
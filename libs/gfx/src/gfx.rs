@@ -4,7 +4,7 @@ use models::{ShakeAmount, Speech};
 pub mod to_tile;
 
 use gfx_sizes::ARGB;
-use pak_types::{sprite::{self, Renderable, BaseFont, BaseUI},};
+use pak_types::{sprite::{self, BaseFont, BaseUI},};
 use platform_types::{Command, PALETTE, unscaled, command, arrow_timer::{self, ArrowTimer}, PaletteIndex};
 use text::byte_slice as text;
 
@@ -139,14 +139,14 @@ impl Commands {
 pub trait AddDrawCommands {
     fn sspr_override(
         &mut self,
-        sprite_xy: sprite::XY<Renderable>,
+        sprite_xy: sprite::SheetXY,
         unscaled_rect: unscaled::Rect,
         colour_override: ARGB,
     );
 
     fn sspr(
         &mut self,
-        sprite_xy: sprite::XY<Renderable>,
+        sprite_xy: sprite::SheetXY,
         unscaled_rect: unscaled::Rect,
     ) {
         self.sspr_override(
@@ -216,7 +216,7 @@ pub trait AddDrawCommands {
 impl AddDrawCommands for Commands {
     fn sspr_override(
         &mut self,
-        sprite_xy: sprite::XY<Renderable>,
+        sprite_xy: sprite::SheetXY,
         unscaled_rect: unscaled::Rect,
         colour_override: ARGB,
     ) {
@@ -358,7 +358,7 @@ macro_rules! clip_new_commands {
 impl <'commands, C: AddDrawCommands + Sized> AddDrawCommands for ClippedCommands<'commands, C> {
     fn sspr_override(
         &mut self,
-        sprite_xy: sprite::XY<Renderable>,
+        sprite_xy: sprite::SheetXY,
         unscaled_rect: unscaled::Rect,
         colour_override: ARGB,
     ) {
@@ -588,7 +588,7 @@ fn push_with_screenshake(
     command_vec: &mut Vec<Command>, 
     shake_xd: unscaled::XD,
     shake_yd: unscaled::YD,
-    sprite_xy: sprite::XY<sprite::Renderable>,
+    sprite_xy: sprite::SheetXY,
     mut rect: unscaled::Rect,
     colour_override: ARGB,
 ) {
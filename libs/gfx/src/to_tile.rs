@@ -1,7 +1,7 @@
 #![deny(unused)]
 
 use platform_types::{sprite, sprite::{BaseTiles}, unscaled};
-use models::{Entity, xy::{XY}};
+use models::{Entity, xy::{self, XY}};
 /// Take a models::XY to the unscaled::XY representing the corner of the tile, with the mininum x/y values.
 /// Suitable for drawing the tile at that point
 pub fn min_corner(spec: &sprite::Spec<BaseTiles>, xy: XY) -> unscaled::XY {
@@ -15,6 +15,28 @@ pub fn min_corner(spec: &sprite::Spec<BaseTiles>, xy: XY) -> unscaled::XY {
     + tile
 }
 
+/// The inverse of `min_corner`. Take an unscaled::XY to the models::XY of the tile
+/// that it is within, if any.
+pub fn containing(spec: &sprite::Spec<BaseTiles>, xy: unscaled::XY) -> Option<XY> {
+    let tile = spec.tile();
+    let (w, h) = (tile.w.get(), tile.h.get());
+    if w <= 0 || h <= 0 {
+        return None
+    }
+
+    // Undo the BaseTiles specific adjustment from `min_corner`.
+    let x = xy.x.0.saturating_sub(w);
+    let y = xy.y.0.saturating_sub(h);
+    if x < 0 || y < 0 {
+        return None
+    }
+
+    Some(XY {
+        x: xy::x(u16::try_from(x / w).ok()?),
+        y: xy::y(u16::try_from(y / h).ok()?),
+    })
+}
+
 /// Take a models::XY to the unscaled::XY representing the center of the tile.
 pub fn center(spec: &sprite::Spec<BaseTiles>, xy: XY) -> unscaled::XY {
     min_corner(spec, xy) + spec.tile_center_offset()
@@ -22,4 +44,30 @@ pub fn center(spec: &sprite::Spec<BaseTiles>, xy: XY) -> unscaled::XY {
 
 pub fn entity_rect(spec: &sprite::Spec<BaseTiles>, entity: &Entity) -> unscaled::Rect {
     spec.offset_rect(entity.offset, min_corner(spec, entity.xy))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod containing_works {
+    use super::*;
+
+    #[test]
+    fn on_every_pixel_of_a_tile() {
+        let spec = sprite::Specs::default().base_tiles;
+        let tile_xy = XY { x: xy::x(3), y: xy::y(2) };
+        let corner = min_corner(&spec, tile_xy);
+
+        for h in 0..spec.tile().h.get() {
+            for w in 0..spec.tile().w.get() {
+                let at = corner + unscaled::WH { w: unscaled::W::new(w), h: unscaled::H::new(h) };
+                assert_eq!(containing(&spec, at), Some(tile_xy));
+            }
+        }
+    }
+
+    #[test]
+    fn on_the_border_before_the_first_tile() {
+        let spec = sprite::Specs::default().base_tiles;
+
+        assert_eq!(containing(&spec, unscaled::XY::default()), None);
+    }
+}
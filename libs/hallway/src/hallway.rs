@@ -0,0 +1,55 @@
+//! What every hallway mini-game needs to provide, so that the rest of the game can
+//! treat them all the same way.
+//!
+//! To add a new hallway, implement `Hallway` for its state, and add a line for it to
+//! `hallway_kinds!` in `pak_types`, so it can be picked in a config. If configs can
+//! set any of its parameters, `config` needs to be taught how to read them too.
+
+use gfx::Commands;
+use platform_types::{sprite, Input, Speaker};
+use xs::Xs;
+
+/// How far through a hallway the player is, in whatever units make sense for that
+/// hallway. For example, levels cleared, or locks unlocked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub done: u32,
+    pub total: u32,
+}
+
+/// How many frames SELECT needs to be held for, to give up on a hallway. Hallways
+/// that use SELECT for something themselves should only act on presses that are
+/// released before this many frames, so giving up does not also trigger them.
+pub const GIVE_UP_HOLD_FRAMES: u16 = 60;
+
+pub trait Hallway: Sized {
+    /// The per-hallway parameters that can be set in the config.
+    type Params: Copy;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self;
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    );
+
+    fn is_complete(&self) -> bool;
+
+    /// Puts the hallway back the way it was when it was first made. Hallways that
+    /// cannot do that are left as they are.
+    fn reset(&mut self, _specs: &sprite::Specs) {}
+
+    /// Called when the player backs out of the hallway, before they are returned to
+    /// where they came from. By default, progress is kept. Hallways that can end up
+    /// in a state that is no longer finishable should reset here, so that giving up
+    /// is a way out.
+    fn give_up(&mut self, _specs: &sprite::Specs) {}
+
+    /// `None` if the hallway has no meaningful way to measure progress.
+    fn progress(&self) -> Option<Progress> {
+        None
+    }
+}
@@ -0,0 +1,432 @@
+//! Running the game without a window, and turning what it renders into PNGs. This
+//! is useful for reviewing paks on machines without a display, and for comparing
+//! frames against known good images.
+
+use app::replay::{Event, EventKind};
+use gfx_sizes::ARGB;
+use platform_types::command;
+use render::FrameBuffer;
+
+/// Runs `frame_count` frames, with the buttons pressed and released as `events`
+/// says, then renders the last frame. `events` should be sorted by frame. At least
+/// one frame is always run, so that there is something to render.
+pub fn run(state: &mut app::State, frame_count: u32, events: &[Event]) -> FrameBuffer {
+    let mut frame_buffer = FrameBuffer::from_size((command::WIDTH, command::HEIGHT));
+
+    let frame_count = frame_count.max(1);
+
+    let mut events = events.iter().peekable();
+
+    for frame_index in 0..frame_count {
+        while let Some(event) = events.next_if(|e| e.frame <= frame_index) {
+            match event.kind {
+                EventKind::Press => app::press(state, event.button),
+                EventKind::Release => app::release(state, event.button),
+            }
+        }
+
+        let (commands, spritesheets, _) = app::frame(state);
+
+        // Only the last frame ends up in the image, so there's no point in
+        // rendering the others.
+        if frame_index + 1 == frame_count {
+            render::render(&mut frame_buffer, commands, spritesheets);
+        }
+    }
+
+    frame_buffer
+}
+
+pub type PngError = png::EncodingError;
+
+/// Encodes the unscaled buffer, which is the frame as the game drew it, before it
+/// was scaled up to fit the window.
+pub fn to_png(frame_buffer: &FrameBuffer) -> Result<Vec<u8>, PngError> {
+    pixels_to_png(&frame_buffer.unscaled_buffer[..])
+}
+
+/// `pixels` should be the same size as the unscaled buffer.
+fn pixels_to_png(pixels: &[ARGB]) -> Result<Vec<u8>, PngError> {
+    let mut rgb = Vec::with_capacity(pixels.len() * 3);
+
+    for &argb in pixels {
+        rgb.push((argb >> 16) as u8);
+        rgb.push((argb >> 8) as u8);
+        rgb.push(argb as u8);
+    }
+
+    let mut output = Vec::new();
+
+    {
+        let mut encoder = png::Encoder::new(
+            &mut output,
+            command::WIDTH.into(),
+            command::HEIGHT.into(),
+        );
+        // The alpha channel is not used when showing frames, so leave it out.
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&rgb)?;
+    }
+
+    Ok(output)
+}
+
+/// Decodes a PNG, like one written by `to_png`, into the same format as the
+/// unscaled buffer.
+pub fn from_png(bytes: &[u8]) -> Result<Vec<ARGB>, pak::PngError> {
+    let frame = pak::read_png_frame(std::io::Cursor::new(bytes))?;
+
+    Ok(pak::spritesheet_from_png_frame(&frame).cells.into())
+}
+
+pub mod script {
+    //! A plain text way to write down button presses and releases, so that scripts
+    //! can be written by hand. Each line looks like `<frame> <press|release> <button>`,
+    //! for example `10 press START`. Blank lines, and everything after a `#`, are
+    //! ignored.
+    use app::replay::{Event, EventKind};
+    use platform_types::Button;
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum Error {
+        MissingField { line: usize, field: &'static str },
+        BadFrame { line: usize, got: String },
+        UnknownAction { line: usize, got: String },
+        UnknownButton { line: usize, got: String },
+        ExtraField { line: usize, got: String },
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use Error::*;
+            match self {
+                MissingField { line, field } => write!(f, "Line {line}: Expected a {field}"),
+                BadFrame { line, got } => write!(f, "Line {line}: Expected a frame number, got \"{got}\""),
+                UnknownAction { line, got } => write!(f, "Line {line}: Expected \"press\" or \"release\", got \"{got}\""),
+                UnknownButton { line, got } => write!(
+                    f,
+                    "Line {line}: Expected one of {}, got \"{got}\"",
+                    Button::ALL.map(Button::name).join(", ")
+                ),
+                ExtraField { line, got } => write!(f, "Line {line}: Expected the end of the line, got \"{got}\""),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    /// The returned events are sorted by frame, with events on the same frame kept
+    /// in the order they were written.
+    pub fn parse(text: &str) -> Result<Vec<Event>, Error> {
+        let mut events = Vec::new();
+
+        for (i, raw_line) in text.lines().enumerate() {
+            let line = i + 1;
+
+            let content = raw_line.split('#').next().unwrap_or_default();
+
+            let mut fields = content.split_whitespace();
+
+            let Some(frame) = fields.next() else {
+                // Blank line
+                continue
+            };
+            let frame = frame.parse()
+                .map_err(|_| Error::BadFrame { line, got: frame.to_string() })?;
+
+            let action = fields.next()
+                .ok_or(Error::MissingField { line, field: "action" })?;
+            let kind = match action {
+                "press" => EventKind::Press,
+                "release" => EventKind::Release,
+                _ => return Err(Error::UnknownAction { line, got: action.to_string() }),
+            };
+
+            let name = fields.next()
+                .ok_or(Error::MissingField { line, field: "button" })?;
+            let button = Button::from_name(name)
+                .ok_or_else(|| Error::UnknownButton { line, got: name.to_string() })?;
+
+            if let Some(extra) = fields.next() {
+                return Err(Error::ExtraField { line, got: extra.to_string() });
+            }
+
+            events.push(Event { frame, button, kind });
+        }
+
+        events.sort_by_key(|e| e.frame);
+
+        Ok(events)
+    }
+
+    #[cfg(test)]
+    mod parse_works {
+        use super::*;
+
+        #[test]
+        fn on_this_example() {
+            let events = parse("
+                # Open the inventory, after the title screen.
+                12 release START
+                10 press start
+
+                20 press A # And do something with it.
+            ").unwrap();
+
+            assert_eq!(
+                events,
+                vec![
+                    Event { frame: 10, button: Button::START, kind: EventKind::Press },
+                    Event { frame: 12, button: Button::START, kind: EventKind::Release },
+                    Event { frame: 20, button: Button::A, kind: EventKind::Press },
+                ]
+            );
+        }
+
+        #[test]
+        fn on_these_bad_lines() {
+            assert_eq!(parse("ten press A"), Err(Error::BadFrame { line: 1, got: "ten".to_string() }));
+            assert_eq!(parse("\n1 hold A"), Err(Error::UnknownAction { line: 2, got: "hold".to_string() }));
+            assert_eq!(parse("1 press"), Err(Error::MissingField { line: 1, field: "button" }));
+            assert_eq!(parse("1 press C"), Err(Error::UnknownButton { line: 1, got: "C".to_string() }));
+            assert_eq!(parse("1 press A B"), Err(Error::ExtraField { line: 1, got: "B".to_string() }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod to_png_works {
+    use super::*;
+
+    #[test]
+    fn on_a_rendered_frame() {
+        let mut state = app::State::new(platform_types::StateParams {
+            pak_loader: None,
+            logger: None,
+            error_logger: None,
+            seed: [42; 16],
+        });
+
+        let frame_buffer = run(&mut state, 2, &[]);
+
+        let png = to_png(&frame_buffer).unwrap();
+
+        let decoded = from_png(&png).unwrap();
+
+        let expected: Vec<ARGB> = frame_buffer.unscaled_buffer.iter()
+            // The alpha channel does not survive the trip.
+            .map(|argb| argb | 0xFF00_0000)
+            .collect();
+
+        assert_eq!(decoded, expected);
+    }
+}
+
+/// Renders fixed frames of each screen, and each hallway, and compares them against
+/// the images in the `golden` folder. To accept changes to how things look, run the
+/// tests with `RIPE_UPDATE_GOLDEN` set, and check in the new images.
+#[cfg(test)]
+mod golden_images_match {
+    use super::*;
+    use app::replay::Event;
+    use game::{DoorAnimation, HallwayState, Mode, TalkingState};
+    use platform_types::{Button, StateParams};
+
+    const GOLDEN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+    const FAILURES_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden/failures");
+    const UPDATE_ENV_VAR: &str = "RIPE_UPDATE_GOLDEN";
+
+    const SEED: xs::Seed = [42; 16];
+
+    /// The built-in config and spritesheet depend on how the game was built, so we
+    /// swap in fixed ones, to keep the images the same everywhere.
+    fn fixed_state() -> app::State {
+        let mut state = app::State::new(StateParams {
+            pak_loader: None,
+            logger: None,
+            error_logger: None,
+            seed: SEED,
+        });
+
+        let png = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/../../assets/gfx.png"))
+            .expect("assets/gfx.png should be readable");
+        let frame = pak::read_png_frame(std::io::Cursor::new(png))
+            .expect("assets/gfx.png should be a valid PNG");
+        state.spritesheets = models::Spritesheets::singleton(pak::spritesheet_from_png_frame(&frame));
+
+        state.game_state = game::State::new(&state.specs, SEED, world::testing::example_config())
+            .map_err(app::Error::Game)
+            .map_err(app::ErrorState::from);
+
+        state
+    }
+
+    fn game_state(state: &mut app::State) -> &mut game::State {
+        state.game_state.as_mut().expect("should not be in an error state")
+    }
+
+    fn press_and_release(frame: u32, button: Button) -> [Event; 2] {
+        use app::replay::EventKind::*;
+
+        [
+            Event { frame, button, kind: Press },
+            Event { frame: frame + 1, button, kind: Release },
+        ]
+    }
+
+    fn assert_matches_golden(name: &str, frame_buffer: &FrameBuffer) {
+        // Alpha does not survive a trip through a PNG, so ignore it.
+        let actual: Vec<ARGB> = frame_buffer.unscaled_buffer.iter()
+            .map(|argb| argb | 0xFF00_0000)
+            .collect();
+
+        let path = format!("{GOLDEN_DIR}/{name}.png");
+
+        if std::env::var_os(UPDATE_ENV_VAR).is_some() {
+            std::fs::create_dir_all(GOLDEN_DIR).unwrap();
+            std::fs::write(&path, pixels_to_png(&actual).unwrap()).unwrap();
+            return
+        }
+
+        let expected = match std::fs::read(&path) {
+            Ok(bytes) => from_png(&bytes).unwrap_or_else(|e| panic!("{path}: {e}")),
+            Err(e) => panic!("{path}: {e}\nRun the tests with {UPDATE_ENV_VAR} set to create it."),
+        };
+
+        if expected == actual {
+            return
+        }
+
+        assert_eq!(expected.len(), actual.len(), "{path} is not the size of a frame");
+
+        let width = usize::from(command::WIDTH);
+
+        let mut differing_count = 0;
+        let mut first = None;
+        let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+
+        // Matching pixels are dimmed, so the differing ones stand out.
+        let diff: Vec<ARGB> = expected.iter().zip(actual.iter()).enumerate()
+            .map(|(i, (&e, &a))| {
+                if e == a {
+                    return 0xFF00_0000 | ((e >> 2) & 0x3F3F3F)
+                }
+
+                let (x, y) = (i % width, i / width);
+                differing_count += 1;
+                first.get_or_insert((x, y, e, a));
+                min_x = min_x.min(x);
+                min_y = min_y.min(y);
+                max_x = max_x.max(x);
+                max_y = max_y.max(y);
+
+                0xFFFF_00FF
+            })
+            .collect();
+
+        std::fs::create_dir_all(FAILURES_DIR).unwrap();
+        let actual_path = format!("{FAILURES_DIR}/{name}.actual.png");
+        let diff_path = format!("{FAILURES_DIR}/{name}.diff.png");
+        std::fs::write(&actual_path, pixels_to_png(&actual).unwrap()).unwrap();
+        std::fs::write(&diff_path, pixels_to_png(&diff).unwrap()).unwrap();
+
+        let (x, y, e, a) = first.expect("some pixel should differ");
+
+        panic!(
+            "{name}: {differing_count} of {} pixels differ from {path}, within x {min_x}..={max_x}, y {min_y}..={max_y}.\n\
+            The first is at ({x}, {y}), which should be #{:06x}, but was #{:06x}.\n\
+            Wrote what was rendered to {actual_path}, and the differences, in magenta, to {diff_path}.\n\
+            If the change is expected, run the tests with {UPDATE_ENV_VAR} set.",
+            expected.len(),
+            e & 0xFF_FFFF,
+            a & 0xFF_FFFF,
+        );
+    }
+
+    #[test]
+    fn for_walking() {
+        let mut state = fixed_state();
+
+        assert_matches_golden("walking", &run(&mut state, 2, &[]));
+    }
+
+    #[test]
+    fn for_the_inventory() {
+        let mut state = fixed_state();
+
+        let frame_buffer = run(&mut state, 4, &press_and_release(1, Button::START));
+
+        assert!(matches!(game_state(&mut state).mode, Mode::Inventory { .. }), "precondition failure");
+
+        assert_matches_golden("inventory", &frame_buffer);
+    }
+
+    #[test]
+    fn for_talking() {
+        let mut state = fixed_state();
+
+        let game_state = game_state(&mut state);
+
+        let key = (0..game_state.entity_defs.len())
+            .map(|def_id| models::speeches::Key {
+                state: <_>::default(),
+                def_id: def_id as _,
+            })
+            .find(|&key| game_state.speeches.get(key).is_some_and(|s| !s.is_empty()))
+            .expect("some entity should have something to say");
+
+        game_state.mode = Mode::Talking(TalkingState::new(key));
+
+        assert_matches_golden("talking", &run(&mut state, 2, &[]));
+    }
+
+    #[test]
+    fn for_victory() {
+        let mut state = fixed_state();
+
+        game_state(&mut state).mode = Mode::Victory(DoorAnimation::default());
+
+        assert_matches_golden("victory", &run(&mut state, 30, &[]));
+    }
+
+    #[test]
+    fn for_an_error() {
+        let mut state = fixed_state();
+
+        state.game_state = Err(app::ErrorState::from(app::Error::Game(world::Error::NoGoalItemFound)));
+
+        assert_matches_golden("error", &run(&mut state, 2, &[]));
+    }
+
+    #[test]
+    fn for_each_hallway() {
+        for (name, kind) in models::consts::ALL_HALLWAY_KINDS {
+            let spec = models::const_to_hallway_spec(kind).expect("every kind should have a spec");
+
+            let mut state = fixed_state();
+
+            let mut rng = xs::from_seed(SEED);
+
+            let Some(hallway_state) = HallwayState::from_spec(spec, &mut rng, &state.specs) else {
+                // There's nothing to draw for `NONE`.
+                continue
+            };
+
+            let source = <_>::default();
+            let mut target = game::EntityKey::default();
+            target.xy.x = models::xy::x(1);
+
+            let game_state = game_state(&mut state);
+            game_state.mode = Mode::Hallway { source, target, give_up_frames: 0 };
+            game_state.hallway_states.insert(source, target, hallway_state);
+
+            assert_matches_golden(
+                &format!("hallway_{}", name.to_lowercase()),
+                &run(&mut state, 2, &[]),
+            );
+        }
+    }
+}
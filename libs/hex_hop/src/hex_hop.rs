@@ -1,9 +1,12 @@
 use gfx::{Commands, AddDrawCommands};
+use hallway::Hallway;
 use gfx_sizes::ARGB;
-use platform_types::{sprite, unscaled, Button, Input, Speaker};
+use platform_types::{config::HexHopParams, sprite, unscaled, Button, Input, Speaker, SFX};
 use qrs::{QRS, QRSD, Q, R};
 use xs::{Seed, Xs};
 
+use serde::{Deserialize, Serialize};
+
 use std::collections::{BTreeMap, BTreeSet};
 type Index = usize;
 
@@ -474,7 +477,7 @@ pub fn xy_to_i(width: impl Into<TilesWidth>, xy: XY) -> Result<Index, XYToIError
 
 type HexHeight = u8;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub struct Tile {
     pub height: HexHeight,
     pub colour: ARGB
@@ -560,7 +563,7 @@ mod offset {
 
     use super::*;
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     enum Kind {
         #[default]
         Still,
@@ -569,7 +572,7 @@ mod offset {
         // that will require other variants here.
     }
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct Offset {
         kind: Kind,
         xyd: unscaled::XYD,
@@ -722,7 +725,7 @@ const X_MOB: MobSprite = 10;
 
 type Energy = u8;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Entity {
     pub offset: Offset,
     pub sprite: MobSprite,
@@ -732,14 +735,14 @@ pub struct Entity {
 mod mobs {
     use super::*;
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub enum Target {
         #[default]
         Player,
         NonPlayer(Key)
     }
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Mobs {
         player_qrs: QRS,
         player: Entity,
@@ -860,22 +863,23 @@ use mobs::Mobs;
 
 
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct State {
     pub seed: Seed, // For restarting
+    pub params: HexHopParams, // Also for restarting
     pub rng: Xs,
     pub tiles: Tiles,
     pub mobs: Mobs,
 }
 
 impl State {
-    pub fn new(rng: &mut Xs, specs: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, specs: &sprite::Specs, params: HexHopParams) -> Self {
         let seed = xs::new_seed(rng);
 
-        Self::init(seed, specs)
+        Self::init(seed, specs, params)
     }
 
-    fn init(seed: Seed, _specs: &sprite::Specs) -> Self {
+    fn init(seed: Seed, _specs: &sprite::Specs, params: HexHopParams) -> Self {
         let mut rng_ = xs::from_seed(seed);
         let rng = &mut rng_;
 
@@ -888,9 +892,9 @@ impl State {
             }
         }
 
-        // TODO make these configurable. Include a way to define an algorithm for them to change
-        // (at different rates) the further rooms get from the start
-        let radius = 3;
+        // TODO make the rest of these configurable. Include a way to define an algorithm for them
+        // to change (at different rates) the further rooms get from the start
+        let radius = params.radius;
         let skip_one_in = 3;
         let heights = [0, 0, 0, 5, 10, 15, 20, 20, 20];
         let palette = [
@@ -1044,6 +1048,7 @@ impl State {
 
         Self {
             seed,
+            params,
             rng: rng_,
             tiles,
             mobs
@@ -1051,7 +1056,7 @@ impl State {
     }
 
     fn restart(&mut self, specs: &sprite::Specs) {
-        *self = Self::init(self.seed, specs);
+        *self = Self::init(self.seed, specs, self.params);
     }
 
     pub fn is_complete(&self) -> bool {
@@ -1082,7 +1087,7 @@ impl State {
         commands: &mut Commands,
         specs: &sprite::Specs,
         input: Input,
-        _speaker: &mut Speaker,
+        speaker: &mut Speaker,
     ) {
         let hex_pieces_spec: &sprite::Spec::<sprite::HexPieces> = &specs.hex_pieces;
         let hex_hop_mobs_spec: &sprite::Spec::<sprite::HexHopMobs> = &specs.hex_hop_mobs;
@@ -1220,7 +1225,7 @@ impl State {
             }
         }
         if colliding {
-            // TODO good place for SFX
+            speaker.request_sfx(SFX::HIT);
 
             self.restart(specs);
         }
@@ -1404,4 +1409,34 @@ impl State {
             }
         }
     }
+}
+
+impl Hallway for State {
+    type Params = HexHopParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn reset(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+
+    fn give_up(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
 }
\ No newline at end of file
@@ -1,10 +1,13 @@
 #![deny(unreachable_patterns)]
 
 use gfx::{Commands, AddDrawCommands};
-use platform_types::{command, sprite, unscaled, Button, Dir, DirFlag, Input, Speaker};
+use hallway::Hallway;
+use platform_types::{command, config::HexTwiddleParams, sprite, unscaled, Button, Dir, DirFlag, Input, Speaker};
 use qrs::{QRS, QRSD, Q, R, qr};
 use xs::{Seed, Xs};
 
+use serde::{Deserialize, Serialize};
+
 use std::collections::{BTreeMap};
 
 const X_Q_FACTOR: i16 = 2;
@@ -87,7 +90,7 @@ mod offset {
 
     use super::*;
 
-    #[derive(Clone, Copy, Default, PartialEq, Eq)]
+    #[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
     pub struct Offset {
         xyd: unscaled::XYD,
     }
@@ -211,7 +214,7 @@ use offset::Offset;
 
 
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 enum Twiddle {
     #[default]
     OneSixth,
@@ -242,7 +245,7 @@ impl Twiddle {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Symbol {
     #[default]
     A,
@@ -265,7 +268,7 @@ impl Symbol {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TileKind {
     Symbol(Symbol),
     Warp,
@@ -320,7 +323,7 @@ fn offsets_are_settled(offsets: &Offsets) -> bool {
     offsets.iter().all(|o| o.is_settled())
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub enum ExitAnimationState {
     #[default]
     HalfOpen,
@@ -343,7 +346,7 @@ impl ExitAnimationState {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub enum DoorMode {
     #[default]
     Closed,
@@ -361,7 +364,7 @@ impl DoorMode {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Tile {
     pub kind: TileKind,
     pub offsets: Offsets,
@@ -412,7 +415,7 @@ const ARROW_BASE: MobSprite = CPU_BASE + DIR_COUNT;
 
 type Facing = qrs::Dir;
 
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Action {
     #[default]
     NoOp,
@@ -432,7 +435,7 @@ impl Action {
     }
 }
 
-#[derive(Clone, Default, PartialEq, Eq)]
+#[derive(Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Entity {
     pub offsets: Offsets,
     pub on_offset_done: Action,
@@ -462,7 +465,7 @@ mod mobs {
     use super::*;
 
     #[repr(u8)]
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
     pub enum Index {
         #[default]
         Zero,
@@ -480,7 +483,7 @@ mod mobs {
         }
     }
 
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
     pub enum Target {
         Player(Index),
         NonPlayer(Index)
@@ -512,7 +515,7 @@ mod mobs {
 
     const PIECES_PER_PLAYER: usize = 3;
 
-    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
     pub struct Mobs {
         player_mobs: [(Key, Entity); PIECES_PER_PLAYER],
         cpu_mobs: [(Key, Entity); PIECES_PER_PLAYER],
@@ -868,7 +871,7 @@ mod twiddle_works {
 
 
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub enum PanSelection {
     #[default]
     Center,
@@ -913,7 +916,7 @@ impl PanSelection {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub enum UiMode {
     #[default]
     Select,
@@ -966,9 +969,10 @@ fn viable_warp_spots<'tiles, 'mobs>(tiles: &'tiles Tiles, mobs: &'mobs Mobs, sou
 type FrameCount = u64;
 type Turn = mobs::Target;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct State {
     pub seed: Seed, // For restarting
+    pub params: HexTwiddleParams, // Also for restarting
     pub rng: Xs,
     pub tiles: Tiles,
     pub mobs: Mobs,
@@ -978,6 +982,10 @@ pub struct State {
     pub turn: Turn,
     pub camera_offset: CameraOffset,
     pub hide_sidebar: bool,
+    /// How many frames SELECT has been held for, so that holding it to give up does
+    /// not also toggle the sidebar.
+    #[serde(skip)]
+    pub select_held_frames: u16,
 }
 
 fn next_turn(turn: Turn) -> Turn {
@@ -1076,11 +1084,11 @@ struct CommandsWithCamera<'commands> {
 }
 
 impl CommandsWithCamera<'_> {
-    fn sspr(&mut self, xy: sprite::XY<sprite::Renderable>, rect: unscaled::Rect) {
+    fn sspr(&mut self, xy: sprite::SheetXY, rect: unscaled::Rect) {
         self.commands.sspr(xy, rect + self.camera_offset);
     }
 
-    fn sspr_override(&mut self, xy: sprite::XY<sprite::Renderable>, rect: unscaled::Rect, colour: gfx_sizes::ARGB) {
+    fn sspr_override(&mut self, xy: sprite::SheetXY, rect: unscaled::Rect, colour: gfx_sizes::ARGB) {
         self.commands.sspr_override(xy, rect + self.camera_offset, colour);
     }
 
@@ -1533,13 +1541,13 @@ fn apply_move(
 }
 
 impl State {
-    pub fn new(rng: &mut Xs, specs: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, specs: &sprite::Specs, params: HexTwiddleParams) -> Self {
         let seed = xs::new_seed(rng);
 
-        Self::init(seed, specs)
+        Self::init(seed, specs, params)
     }
 
-    fn init(seed: Seed, _specs: &sprite::Specs) -> Self {
+    fn init(seed: Seed, _specs: &sprite::Specs, params: HexTwiddleParams) -> Self {
         let mut rng_ = xs::from_seed(seed);
         let rng = &mut rng_;
 
@@ -1562,7 +1570,7 @@ impl State {
                 }
             }
 
-            for at in qrs::spiral(2, qr!(0, 0)) {
+            for at in qrs::spiral(params.radius, qr!(0, 0)) {
                 if has_holes && xs::range(rng, 0..4) == 0 { continue }
 
                 insert_tile!(at);
@@ -1589,7 +1597,7 @@ impl State {
 
         if is_uncompletable(&tiles) {
             // Known completable fallback
-            for (i, at) in qrs::spiral(2, qr!(0, 0)).enumerate() {
+            for (i, at) in qrs::spiral(params.radius, qr!(0, 0)).enumerate() {
                 tiles.insert(
                     at,
                     Tile {
@@ -1604,6 +1612,7 @@ impl State {
 
         let mut output = Self {
             seed,
+            params,
             rng: rng_,
             tiles,
             mobs,
@@ -1617,7 +1626,7 @@ impl State {
 
     #[allow(unused)]
     fn restart(&mut self, specs: &sprite::Specs) {
-        *self = Self::init(self.seed, specs);
+        *self = Self::init(self.seed, specs, self.params);
     }
 
     pub fn all_offsets_settled(&self) -> bool {
@@ -2258,10 +2267,17 @@ impl State {
             }
         }
 
-        if input.pressed_this_frame(Button::SELECT) {
+        if input.released_this_frame(Button::SELECT)
+            && self.select_held_frames < hallway::GIVE_UP_HOLD_FRAMES {
             self.hide_sidebar = !self.hide_sidebar;
         }
 
+        if input.gamepad.contains(Button::SELECT) {
+            self.select_held_frames = self.select_held_frames.saturating_add(1);
+        } else {
+            self.select_held_frames = 0;
+        }
+
         // Note: Selectrum should not be moved after this line, this frame.
         let selectrum_xy = qrs_to_unscaled(self.selectrum_at, self.camera_offset);
 
@@ -2738,3 +2754,82 @@ impl State {
         }
     }
 }
+
+impl Hallway for State {
+    type Params = HexTwiddleParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn reset(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+
+    fn give_up(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+}
+
+#[cfg(test)]
+mod sidebar_toggling_works {
+    use super::*;
+
+    fn hold_select_for(state: &mut State, frames: u16) {
+        let specs = sprite::Specs::default();
+        let mut commands = Commands::new(<_>::default(), specs.base_font.clone(), specs.base_ui.clone());
+        let mut speaker = Speaker::default();
+
+        let mut previous_gamepad = Button::default();
+        for i in 0..=frames {
+            let gamepad = if i < frames { Button::SELECT } else { Button::default() };
+
+            state.update_and_render(
+                &mut commands,
+                &specs,
+                Input { gamepad, previous_gamepad, .. <_>::default() },
+                &mut speaker,
+            );
+
+            previous_gamepad = gamepad;
+        }
+    }
+
+    fn new_state() -> State {
+        State::new(&mut xs::from_seed(<_>::default()), &sprite::Specs::default(), <_>::default())
+    }
+
+    #[test]
+    fn on_a_short_press() {
+        let mut state = new_state();
+        let before = state.hide_sidebar;
+
+        hold_select_for(&mut state, 2);
+
+        assert_ne!(state.hide_sidebar, before);
+    }
+
+    #[test]
+    fn not_when_select_is_held_to_give_up() {
+        let mut state = new_state();
+        let before = state.hide_sidebar;
+
+        hold_select_for(&mut state, hallway::GIVE_UP_HOLD_FRAMES);
+
+        assert_eq!(state.hide_sidebar, before);
+    }
+}
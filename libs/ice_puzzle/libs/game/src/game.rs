@@ -1,15 +1,28 @@
 use common::*;
 use gfx::{Commands, AddDrawCommands};
-use platform_types::{sprite::{self, IcePuzzles}, unscaled::{self, H, W}, Button, Input, Speaker};
+use platform_types::{config::IcePuzzleParams, sprite::{self, IcePuzzles}, unscaled::{self, H, W}, Button, Input, Speaker};
 use xs::{Seed};
 
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct State {
     pub state: common::State,
+    // Function pointers cannot be saved, but they are always the same anyway.
+    #[serde(skip, default = "default_platform")]
     platform: Platform,
+    // Cleared every frame, so there's no need to save these.
+    #[serde(skip)]
     events: Vec<Event>,
 }
 
+fn default_platform() -> Platform {
+    Platform {
+        p_xy,
+        size: platform::size,
+    }
+}
+
 fn str_to_sprite_xy(spec: &sprite::Spec::<IcePuzzles>, s: &str) -> sprite::XY<IcePuzzles> {
     let tile = spec.tile();
     let tile_w = tile.w;
@@ -91,16 +104,23 @@ impl State {
     pub fn new(
         seed: Seed,
         spec: &sprite::Spec<IcePuzzles>,
+        params: IcePuzzleParams,
     ) -> State {
+        let screen_size = platform::size(spec);
+
+        // Anything bigger than the screen would not be visible.
+        let size = Size::new(
+            params.width.map_or(screen_size.width, |w| screen_size.width.min(w.into())),
+            params.height.map_or(screen_size.height, |h| screen_size.height.min(h.into())),
+        );
+
         State {
             state: state_manipulation::new_state(
-                platform::size(spec),
+                size,
                 seed,
+                params.max_steps,
             ),
-            platform: Platform {
-                p_xy,
-                size: platform::size,
-            },
+            platform: default_platform(),
             events: Vec::with_capacity(1),
         }
     }
@@ -222,7 +242,7 @@ fn something_gets_drawn() {
 
     let specs = sprite::Specs::default();
 
-    let mut state = State::new(seed, &specs.ice_puzzles);
+    let mut state = State::new(seed, &specs.ice_puzzles, <_>::default());
 
     let mut commands = Commands::new(seed, specs.base_font, specs.base_ui);
     
@@ -5,13 +5,15 @@ use std::fmt;
 use std::collections::HashMap;
 use xs::Xs;
 
+use serde::{Deserialize, Serialize};
+
 #[derive(Clone, Debug)]
 pub struct Platform {
     pub p_xy: fn(&mut Commands, &sprite::Spec::<IcePuzzles>, i32, i32, &'static str),
     pub size: fn(&sprite::Spec::<IcePuzzles>) -> Size,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct State {
     pub player_pos: (i32, i32),
     pub initial_player_pos: (i32, i32),
@@ -21,11 +23,14 @@ pub struct State {
     pub frame_count: u32,
     pub motion: Motion,
     pub max_steps: u8,
+    // These two are kept around for generating later levels.
+    pub size: Size,
+    pub starting_max_steps: u8,
 }
 
 pub type Cells = HashMap<(i32, i32), Cell>;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Deserialize, Serialize)]
 pub enum Cell {
     Wall,
     Goal,
@@ -56,7 +61,7 @@ impl std::fmt::Debug for Cell {
     }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
 pub enum Motion {
     Stopped,
     Up,
@@ -65,7 +70,7 @@ pub enum Motion {
     Left,
 }
 
-#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 pub enum Dir {
     #[default]
     Up,
@@ -123,7 +128,7 @@ impl Point {
 
 
 /// A 2D size representation.
-#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Deserialize, Serialize)]
 pub struct Size {
     pub width: i32,
     pub height: i32,
@@ -13,12 +13,11 @@ use xs::{Seed, Xs};
 pub fn new_state(
     size: Size,
     seed: Seed,
+    max_steps: u8,
 ) -> State {
     let rng = xs::from_seed(seed);
 
-    let max_steps = 4;
-
-    next_level(size, rng, max_steps)
+    next_level(size, rng, max_steps, max_steps)
 }
 
 pub fn update_and_render(
@@ -41,14 +40,14 @@ pub fn game_update_and_render(
     events: &mut Vec<Event>
 ) {
     for event in events {
-        cross_mode_event_handling(platform, state, spec, event);
+        cross_mode_event_handling(state, event);
     }
 
-    move_player((platform.size)(spec), state);
+    move_player(state.size, state);
 
     if let Some(&Goal) = state.cells.get(&state.player_pos) {
         state.max_steps += 1;
-        *state = next_level((platform.size)(spec), state.rng, state.max_steps);
+        *state = next_level(state.size, state.rng, state.starting_max_steps, state.max_steps);
     }
 
     draw(commands, spec, platform, state);
@@ -69,9 +68,7 @@ fn move_player(size: Size, state: &mut State) {
 }
 
 fn cross_mode_event_handling(
-    platform: &Platform,
     state: &mut State,
-    spec: &sprite::Spec::<IcePuzzles>,
     event: &Event
 ) {
     match *event {
@@ -108,7 +105,7 @@ fn cross_mode_event_handling(
             state.player_facing_direction = Dir::default();
         }
         Event::KeyPressed { key: KeyCode::R, ctrl: true, shift: _ } => {
-            *state = new_state((platform.size)(spec), xs::new_seed(&mut state.rng));
+            *state = new_state(state.size, xs::new_seed(&mut state.rng), state.starting_max_steps);
         }
         _ => (),
     }
@@ -201,7 +198,7 @@ fn print_cell(commands: &mut Commands, spec: &sprite::Spec::<sprite::IcePuzzles>
     }
 }
 
-fn next_level(size: Size, mut rng: Xs, max_steps: u8) -> State {
+fn next_level(size: Size, mut rng: Xs, starting_max_steps: u8, max_steps: u8) -> State {
     let mut cells = HashMap::new();
 
     for y in 0..size.height {
@@ -293,6 +290,8 @@ fn next_level(size: Size, mut rng: Xs, max_steps: u8) -> State {
         frame_count: 0,
         motion: Stopped,
         max_steps: max_steps,
+        size,
+        starting_max_steps,
     }
 }
 
@@ -1,21 +1,27 @@
 use gfx::{Commands};
-use platform_types::{sprite, Input, Speaker};
+use hallway::{Hallway, Progress};
+use platform_types::{config::IcePuzzleParams, sprite, Input, Speaker};
 use xs::Xs;
 
-#[derive(Clone, Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct State {
     pub state: game::State,
+    pub params: IcePuzzleParams,
 }
 
 impl State {
-    pub fn new(rng: &mut Xs, spec: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, spec: &sprite::Specs, params: IcePuzzleParams) -> Self {
         Self {
-            state: game::State::new(xs::new_seed(rng), &spec.ice_puzzles),
+            state: game::State::new(xs::new_seed(rng), &spec.ice_puzzles, params),
+            params,
         }
     }
 
     pub fn is_complete(&self) -> bool {
-        self.state.state.max_steps >= 5
+        // Clearing a level bumps up `max_steps`.
+        self.state.state.max_steps >= self.params.max_steps.saturating_add(self.params.levels)
     }
 
     pub fn update_and_render(
@@ -27,8 +33,6 @@ impl State {
     ) {
         let spec: &sprite::Spec::<sprite::IcePuzzles> = &specs.ice_puzzles;
 
-        // TODO allow backing out in case the player wants to give up on the puzzle
-
         game::State::update_and_render(
             commands,
             spec,
@@ -37,4 +41,35 @@ impl State {
             speaker,
         );
     }
+}
+
+impl Hallway for State {
+    type Params = IcePuzzleParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn progress(&self) -> Option<Progress> {
+        let cleared = self.state.state.max_steps.saturating_sub(self.params.max_steps);
+
+        Some(Progress {
+            done: u32::from(core::cmp::min(cleared, self.params.levels)),
+            total: u32::from(self.params.levels),
+        })
+    }
 }
\ No newline at end of file
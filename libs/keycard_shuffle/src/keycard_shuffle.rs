@@ -1,12 +1,15 @@
 use gfx::{Commands, AddDrawCommands};
+use hallway::{Hallway, Progress};
 use gfx_sizes::{ARGB};
-use platform_types::{command, sprite, unscaled, Button, Dir, DirFlag, Input, Speaker};
+use platform_types::{command, config::KeycardShuffleParams, sprite, unscaled, Button, Dir, DirFlag, Input, Speaker, SFX};
 //use vec1::{Grid1, Grid1Spec};
 use xs::{Seed, Xs};
 
+use serde::{Deserialize, Serialize};
+
 type Index = usize;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 enum CardColour {
     Blue,
     Green,
@@ -27,7 +30,7 @@ impl CardColour {
     ];
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 enum CardSymbol {
     None,
     OnePip,
@@ -40,15 +43,24 @@ impl CardSymbol {
         Self::OnePip,
         Self::TwoPips,
     ];
+
+    /// How many lights in a row a card with this symbol lights up.
+    fn light_count(self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::OnePip => 2,
+            Self::TwoPips => 3,
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 struct CardKind {
     colour: CardColour,
     symbol: CardSymbol,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct Inventory {
     cells: Vec<CardKind>,
     index: Index,
@@ -56,40 +68,80 @@ struct Inventory {
 
 mod world {
     use platform_types::{unscaled};
+    use serde::{Deserialize, Serialize};
 
     pub type Inner = i16;
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct X(pub Inner);
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct Y(pub Inner);
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct XY {
         pub x: X,
         pub y: Y,
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct Lock {
     xy: world::XY,
-    // TODO state for lights and whether is unlocked
+    /// The colour each light wants, in the order they light up.
+    lights: Vec<CardColour>,
+    lit_count: usize,
+}
+
+impl Lock {
+    fn is_unlocked(&self) -> bool {
+        self.lit_count >= self.lights.len()
+    }
+
+    /// A card lights up the next unlit lights, as many as its symbol says. It is only
+    /// accepted if every one of those lights wants the card's colour.
+    fn accepts(&self, card: CardKind) -> bool {
+        let end = self.lit_count + card.symbol.light_count();
+
+        self.lights.get(self.lit_count..end)
+            .is_some_and(|wanted| wanted.iter().all(|&colour| colour == card.colour))
+    }
+
+    fn insert(&mut self, card: CardKind) -> bool {
+        let accepted = self.accepts(card);
+        if accepted {
+            self.lit_count += card.symbol.light_count();
+        }
+        accepted
+    }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct Locks {
     locks: Vec<Lock>,
     index: Index,
 }
 
+impl Locks {
+    /// Moves the index to the next lock that is still locked, if any.
+    fn select_next_locked(&mut self) {
+        let len = self.locks.len();
+        for offset in 1..=len {
+            let index = (self.index + offset) % len;
+            if !self.locks[index].is_unlocked() {
+                self.index = index;
+                return
+            }
+        }
+    }
+}
+
 type FrameCount = u16;
 
 const MAX_INSERT_FRAME: FrameCount = 60;
 const MAX_INSIDE_FRAME: FrameCount = 20;
 const MAX_REMOVE_FRAME: FrameCount = 60;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 enum LockAnimationState {
     Insert(FrameCount),
     Inside(FrameCount),
@@ -100,21 +152,23 @@ impl Default for LockAnimationState {
     fn default() -> Self { LockAnimationState::Insert(0) }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct LockAnimation {
     state: LockAnimationState,
     inventory_index: Index,
     lock_index: Index,
+    /// Whether the lock took the card. Decided once the card is all the way inside.
+    accepted: bool,
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 struct Animations {
     lock: Option<LockAnimation>,
 }
 
 const FLAG_ZERO_FRAMES: FrameCount = 45;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 enum FlagState {
     Zero(FrameCount),
     One(FrameCount),
@@ -128,9 +182,10 @@ impl Default for FlagState {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct State {
     pub seed: Seed, // For restarting
+    pub params: KeycardShuffleParams, // Also for restarting
     pub rng: Xs,
     pub inventory: Inventory,
     pub inventory_scroll: unscaled::XYD,
@@ -158,13 +213,13 @@ const LOCK_SCENE_OUTER_RECT: unscaled::Rect = unscaled::Rect {
 };
 
 impl State {
-    pub fn new(rng: &mut Xs, specs: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, specs: &sprite::Specs, params: KeycardShuffleParams) -> Self {
         let seed = xs::new_seed(rng);
 
-        Self::init(seed, specs)
+        Self::init(seed, specs, params)
     }
 
-    fn init(seed: Seed, _specs: &sprite::Specs) -> Self {
+    fn init(seed: Seed, _specs: &sprite::Specs, params: KeycardShuffleParams) -> Self {
         let mut rng_ = xs::from_seed(seed);
         let rng = &mut rng_;
 
@@ -189,20 +244,45 @@ impl State {
             LOCK_SCENE_OUTER_RECT
         );
 
+        // Each lock's lights are made from the cards dealt to it, so dealing from one
+        // deck means there is always a way to use the inventory to open every lock.
+        let mut deck = inventory.cells.clone();
+        xs::shuffle(rng, &mut deck);
+
+        let lock_count = usize::from(params.lock_count.max(1));
+
         let mut locks = Locks::default();
-        for i in 0..5 {
+        for lock_index in 0..lock_count {
             let xy = world::XY {
                 x: world::X(xs::range(rng, 0..lock_scene_inner_rect.w.get() as u32) as unscaled::Inner),
                 y: world::Y(xs::range(rng, 0..lock_scene_inner_rect.h.get() as u32) as unscaled::Inner),
             };
 
+            // Leave at least one card for each of the remaining locks.
+            let max_card_count = core::cmp::min(
+                usize::from(params.max_cards_per_lock.max(1)),
+                deck.len().saturating_sub(lock_count - lock_index - 1).max(1),
+            );
+
+            let mut lights = Vec::with_capacity(max_card_count * CardSymbol::TwoPips.light_count());
+            for _ in 0..xs::range(rng, 1..max_card_count as u32 + 1) {
+                if let Some(card) = deck.pop() {
+                    for _ in 0..card.symbol.light_count() {
+                        lights.push(card.colour);
+                    }
+                }
+            }
+
             locks.locks.push(Lock {
                 xy,
+                lights,
+                lit_count: 0,
             });
         }
 
         Self {
             seed,
+            params,
             rng: rng_,
             inventory,
             locks,
@@ -212,11 +292,11 @@ impl State {
 
     #[allow(unused)]
     fn restart(&mut self, specs: &sprite::Specs) {
-        *self = Self::init(self.seed, specs);
+        *self = Self::init(self.seed, specs, self.params);
     }
 
     fn all_offsets_settled(&self) -> bool {
-        true
+        self.animations.lock.is_none()
     }
 
     pub fn is_complete(&self) -> bool {
@@ -225,11 +305,21 @@ impl State {
             return false
         }
 
-        // TODO actual checking
-        false
+        self.locks.locks.iter().all(Lock::is_unlocked)
     }
 
-    fn tick(&mut self) {
+    /// Cards are used up once a lock has taken them.
+    fn remove_card(&mut self, inventory_index: Index) {
+        if inventory_index < self.inventory.cells.len() {
+            self.inventory.cells.remove(inventory_index);
+        }
+
+        if self.inventory.index >= self.inventory.cells.len() {
+            self.inventory.index = self.inventory.cells.len().saturating_sub(1);
+        }
+    }
+
+    fn tick(&mut self, speaker: &mut Speaker) {
         // Advance animations
         // We can make an iterator if we actually need at least 3 distinct animations that are handled the same.
         if let Some(animation) = &mut self.animations.lock {
@@ -237,21 +327,37 @@ impl State {
                 LockAnimationState::Insert(at_frame) => {
                     *at_frame += 1;
                     if *at_frame > MAX_INSERT_FRAME {
-                        // TODO Good place for click sound effect
+                        speaker.request_sfx(SFX::CLICK);
                         animation.state = LockAnimationState::Inside(0);
+
+                        if let (Some(&card), Some(lock)) = (
+                            self.inventory.cells.get(animation.inventory_index),
+                            self.locks.locks.get_mut(animation.lock_index),
+                        ) {
+                            animation.accepted = lock.insert(card);
+                        }
                     }
                 },
                 LockAnimationState::Inside(at_frame) => {
                     *at_frame += 1;
                     if *at_frame > MAX_INSIDE_FRAME {
-                        // TODO Good place for click sound effect
+                        speaker.request_sfx(SFX::CLICK);
                         animation.state = LockAnimationState::Remove(0);
                     }
                 },
                 LockAnimationState::Remove(at_frame) => {
                     *at_frame += 1;
                     if *at_frame > MAX_REMOVE_FRAME {
+                        let LockAnimation { inventory_index, lock_index, accepted, .. } = *animation;
                         self.animations.lock = None;
+
+                        if accepted {
+                            self.remove_card(inventory_index);
+
+                            if self.locks.locks.get(lock_index).is_some_and(Lock::is_unlocked) {
+                                self.locks.select_next_locked();
+                            }
+                        }
                     }
                 },
             };
@@ -274,7 +380,7 @@ impl State {
         mut commands: &mut Commands,
         specs: &sprite::Specs,
         input: Input,
-        _speaker: &mut Speaker,
+        speaker: &mut Speaker,
     ) {
         //
         // Update
@@ -329,7 +435,7 @@ impl State {
                     }
                 }
                 Dir::Right => {
-                    if self.inventory.index < self.inventory.cells.len() - 1 {
+                    if self.inventory.index + 1 < self.inventory.cells.len() {
                         self.inventory.index += 1;
                     }
                 }
@@ -374,26 +480,31 @@ impl State {
             }
         } else if input.pressed_this_frame(Button::A) {
             if self.animations.lock.is_none() {
-                if let (Some(_), Some(_)) = (
+                if let (Some(_), Some(false)) = (
                     self.inventory.cells.get(self.inventory.index),
-                    self.locks.locks.get(self.locks.index),
+                    self.locks.locks.get(self.locks.index).map(Lock::is_unlocked),
                 ) {
                     self.animations.lock = Some(
                         LockAnimation{
                             state: <_>::default(),
                             inventory_index: self.inventory.index,
                             lock_index: self.locks.index,
+                            accepted: false,
                         }
                     );
                 }
             }
+        } else if input.pressed_this_frame(Button::B) {
+            if self.animations.lock.is_none() {
+                self.locks.select_next_locked();
+            }
         }
 
         if input.pressed_this_frame(Button::START) {
             self.restart(specs);
         }
 
-        self.tick();
+        self.tick(speaker);
 
         //
         // Render
@@ -412,6 +523,17 @@ impl State {
             0xFF222222, // Black
         ];
 
+        let colour_index = |colour: CardColour| -> usize {
+            match colour {
+                CardColour::Blue => 0,
+                CardColour::Green => 1,
+                CardColour::Red => 2,
+                CardColour::Yellow => 3,
+                CardColour::Purple => 4,
+                CardColour::Cyan => 5,
+            }
+        };
+
         let card_wh = specs.keycard_shuffle_cards.tile();
         let letters_wh = specs.keycard_shuffle_letters.tile();
         let lights_wh = specs.keycard_shuffle_lights.tile();
@@ -462,14 +584,7 @@ impl State {
                     h: unscaled::H::new(command::HEIGHT_SIGNED),
                 };
 
-                let colour_index: u16 = match kind.colour {
-                    CardColour::Blue => 0,
-                    CardColour::Green => 1,
-                    CardColour::Red => 2,
-                    CardColour::Yellow => 3,
-                    CardColour::Purple => 4,
-                    CardColour::Cyan => 5,
-                };
+                let colour_index = colour_index(kind.colour) as u16;
 
                 let mut cmds = $commands.clipped(clip_rect);
 
@@ -533,33 +648,64 @@ impl State {
             }
         };
 
-        for lock in &self.locks.locks {
+        for (lock_index, lock) in self.locks.locks.iter().enumerate() {
             let xy = world_to_unscaled(lock.xy);
             
-            // Render flags
-            let sprite_offset = match self.flag_state {
-                FlagState::Zero(_) => 0,
-                FlagState::One(_) | FlagState::Three(_) => 1,
-                FlagState::Two(_) => 2,
+            // Render flags. Unlocked locks have a still green flag, and the selected one is yellow.
+            let (sprite_offset, colour) = if lock.is_unlocked() {
+                (0, PALETTE[1])
+            } else {
+                let sprite_offset = match self.flag_state {
+                    FlagState::Zero(_) => 0,
+                    FlagState::One(_) | FlagState::Three(_) => 1,
+                    FlagState::Two(_) => 2,
+                };
+
+                let colour = if lock_index == self.locks.index {
+                    PALETTE[3]
+                } else {
+                    PALETTE[2]
+                };
+
+                (sprite_offset, colour)
             };
 
             commands.sspr_override(
                 specs.keycard_shuffle_lights.xy_from_tile_sprite(3u16 + sprite_offset),
                 specs.keycard_shuffle_lights.rect(xy),
-                PALETTE[2]
+                colour
             );
         }
 
-        // Render card slot back
+        // Render lock lights, for the selected lock, in a column beside the slot.
+        if let Some(lock) = self.locks.locks.get(self.locks.index) {
+            let mut light_xy = unscaled::XY {
+                x: slot_rect.x + slot_rect.w + unscaled::W::new(SPACING),
+                y: slot_rect.y,
+            };
+
+            for (light_index, colour) in lock.lights.iter().enumerate() {
+                // Lit lights are filled in, unlit ones are just an outline.
+                let sprite: u16 = if light_index < lock.lit_count { 1 } else { 0 };
 
-        // TODO Render lock lights
+                commands.sspr_override(
+                    specs.keycard_shuffle_lights.xy_from_tile_sprite(sprite),
+                    specs.keycard_shuffle_lights.rect(light_xy),
+                    PALETTE[colour_index(*colour)]
+                );
+
+                light_xy.y += lights_wh.h + unscaled::H::new(SPACING);
+            }
+        }
+
+        // Render card slot back
 
         commands.sspr(slot_sprite_xy, slot_rect);
 
         let slot_overlay_x_shift = slot_rect.w.halve().inc();
 
         let mut slot_overlay_sprite_xy = slot_sprite_xy;
-        slot_overlay_sprite_xy.x += slot_overlay_x_shift;
+        slot_overlay_sprite_xy.xy.x += slot_overlay_x_shift;
 
         let mut slot_overlay_rect = slot_rect;
         slot_overlay_rect.x += slot_overlay_x_shift;
@@ -630,4 +776,110 @@ impl State {
             inventory_render_index += 1;
         }
     }
-}
\ No newline at end of file
+}
+
+impl Hallway for State {
+    type Params = KeycardShuffleParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn reset(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+
+    fn progress(&self) -> Option<Progress> {
+        let locks = &self.locks.locks;
+
+        Some(Progress {
+            done: locks.iter().filter(|lock| lock.is_unlocked()).count() as u32,
+            total: locks.len() as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod is_complete_works {
+    use super::*;
+
+    /// Tries every way of using the cards, without looking at how the locks were made.
+    fn can_be_completed(state: &State) -> bool {
+        if state.is_complete() {
+            return true
+        }
+
+        let Some(lock_index) = state.locks.locks.iter().position(|lock| !lock.is_unlocked()) else {
+            return false
+        };
+
+        for (inventory_index, &card) in state.inventory.cells.iter().enumerate() {
+            if !state.locks.locks[lock_index].accepts(card) {
+                continue
+            }
+
+            let mut next = state.clone();
+            assert!(next.locks.locks[lock_index].insert(card));
+            next.remove_card(inventory_index);
+
+            if can_be_completed(&next) {
+                return true
+            }
+        }
+
+        false
+    }
+
+    #[test]
+    fn on_every_layout_from_many_seeds() {
+        let specs = <_>::default();
+
+        let most = KeycardShuffleParams {
+            lock_count: *KeycardShuffleParams::LOCK_COUNT.end(),
+            max_cards_per_lock: *KeycardShuffleParams::MAX_CARDS_PER_LOCK.end(),
+        };
+
+        for params in [<_>::default(), most] {
+            for seed_index in 0..1000u128 {
+                let mut rng = xs::from_seed(seed_index.to_le_bytes());
+
+                let state = State::new(&mut rng, &specs, params);
+
+                assert_eq!(state.locks.locks.len(), usize::from(params.lock_count));
+                assert!(!state.is_complete(), "{seed_index}");
+                assert!(can_be_completed(&state), "{seed_index}: {:#?}", state.locks);
+            }
+        }
+    }
+
+    #[test]
+    fn on_a_lock_that_wants_a_different_colour() {
+        let mut lock = Lock {
+            lights: vec![CardColour::Red, CardColour::Red, CardColour::Blue],
+            .. <_>::default()
+        };
+
+        let card = |colour, symbol| CardKind { colour, symbol };
+
+        assert!(!lock.insert(card(CardColour::Blue, CardSymbol::None)));
+        assert!(!lock.insert(card(CardColour::Red, CardSymbol::TwoPips)));
+        assert!(lock.insert(card(CardColour::Red, CardSymbol::OnePip)));
+        assert!(!lock.is_unlocked());
+        assert!(lock.insert(card(CardColour::Blue, CardSymbol::None)));
+        assert!(lock.is_unlocked());
+    }
+}
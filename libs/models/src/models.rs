@@ -3,6 +3,8 @@ pub use offset;
 
 use vec1::{Grid1};
 
+use serde::{Deserialize, Serialize};
+
 /// An amount of screenshake to render with.
 pub type ShakeAmount = u8;
 
@@ -15,7 +17,7 @@ pub struct SpeechSelection {
     pub precedence: Precedence,
 }
 
-#[derive(Clone, Default, Debug, PartialEq, Eq)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum DesireState {
     #[default]
     Unsatisfiable,
@@ -24,7 +26,7 @@ pub enum DesireState {
     Satisfied,
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
 pub struct Desire {
     pub state: DesireState,
     pub def_id: DefId,
@@ -60,13 +62,14 @@ impl Desire {
 
 pub type Desires = Vec<Desire>;
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct MiniEntityDef {
     pub id: DefId,
     pub flags: consts::EntityDefFlags,
     pub tile_sprite: TileSprite,
     pub on_collect: OnCollect,
     pub wants: Vec<DefId>,
+    pub scripts: script::Names,
 }
 
 impl From<&EntityDef> for MiniEntityDef {
@@ -77,17 +80,19 @@ impl From<&EntityDef> for MiniEntityDef {
             tile_sprite: def.tile_sprite,
             wants: def.wants.clone(),
             on_collect: def.on_collect.clone(),
+            scripts: def.scripts.clone(),
         }
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct EntityTransformable {
     pub id: DefId,
     pub flags: consts::EntityDefFlags,
     pub tile_sprite: TileSprite,    
     pub on_collect: OnCollect,
     pub wants: Desires,
+    pub scripts: script::Names,
 }
 
 impl From<&MiniEntityDef> for EntityTransformable {
@@ -99,20 +104,21 @@ impl From<&MiniEntityDef> for EntityTransformable {
             tile_sprite: def.tile_sprite,
             on_collect: def.on_collect.clone(),
             wants: def.wants.iter().map(|&id| Desire::new(id)).collect::<Vec<_>>(),
+            scripts: def.scripts.clone(),
         }
     }
 }
 
 pub type Inventory = Vec<Entity>;
 
-#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct Location {
     pub segment_id: SegmentId,
     pub xy: XY,
 }
 
 // Fat-struct for entities! Fat-struct for entities!
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Deserialize, Serialize)]
 pub struct Entity {
     pub xy: XY,
     pub offset: offset::XY,
@@ -172,12 +178,18 @@ impl Entity {
     pub fn is_door(&self) -> bool {
         self.transformable.flags & DOOR == DOOR
     }
+
+    pub fn script(&self, hook: script::Hook) -> Option<&str> {
+        self.transformable.scripts.get(hook)
+    }
 }
 
 pub mod xy {
+    use serde::{Deserialize, Serialize};
+
     pub type Inner = u16;
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
     pub struct X(Inner);
 
     /// Use this in case we want to clamp the coords, or something.
@@ -256,7 +268,7 @@ pub mod xy {
     }
 
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
     pub struct Y(Inner);
 
     /// Use this in case we want to clamp the coords, or something.
@@ -378,7 +390,7 @@ pub mod xy {
         X Y W H
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
     pub struct XY {
         pub x: X,
         pub y: Y,
@@ -462,7 +474,7 @@ pub mod xy {
 }
 pub use xy::{X, Y, W, H, Rect, XY};
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Tile {
     pub sprite: TileSprite,
 }
@@ -496,12 +508,13 @@ pub mod speeches {
     use vec1::Vec1;
     use pak_types::{DefId, Speech};
     use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
 
     /// The state of the entity in so far as it relates to which speech
     /// should be used.
     pub type State = u8;
 
-    #[derive(Clone, Copy, Debug)]
+    #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
     pub struct Key {
         pub state: State,
         pub def_id: DefId,
@@ -509,7 +522,7 @@ pub mod speeches {
 
     type SparseState = std::num::NonZeroU8;
 
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     struct SparseKey {
         state: SparseState,
         def_id: DefId,
@@ -531,7 +544,7 @@ pub mod speeches {
 
     type SparseSpeeches = BTreeMap<SparseKey, Vec1<Speech>>;
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Speeches {
         // We expect that many entities will have a first speech of each category, so dense seems appropriate.
         // For now, it seems reasonable to assume we can force Def IDs to be dense, and start at 0.
@@ -1,5 +1,7 @@
 ///! Offsets from a tile, for visual purposes only.
 
+use serde::{Deserialize, Serialize};
+
 pub type Inner = f32;
 
 /// Distinct from f32::signum in that it returns 0.0 for 0.0, -0.0, NaNs, etc.
@@ -34,14 +36,14 @@ fn normalize(inner: Inner) -> Inner {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub struct X(Inner);
 
 pub fn x(inner: Inner) -> X {
     X(normalize(inner))
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
 pub struct Y(Inner);
 
 pub fn y(inner: Inner) -> Y {
@@ -215,7 +217,7 @@ shared_impl!{
     X Y
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct XY {
     pub x: X,
     pub y: Y,
@@ -1,9 +1,10 @@
-use pak_types::{Pak, Spritesheet};
+use pak_types::{config::{Manifest, Version, VersionRange, GAME_VERSION}, music, sound, sprite::SheetIndex, ContentHash, Pak, Spritesheet};
 use gfx_sizes::ARGB;
 use platform_types::{PakReader};
+use xs::Seed;
 
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::string::FromUtf8Error;
 use std::num::TryFromIntError;
 use zip::{ZipArchive, result::ZipError};
@@ -18,6 +19,33 @@ pub enum Error {
     FromUtf8(FromUtf8Error),
     TryFromInt(TryFromIntError),
     Png(PngError),
+    Io(PathBuf, std::io::Error),
+    AbsolutePath(PathBuf),
+    PathOutsidePak(PathBuf),
+    UnsupportedFormatVersion(u32),
+    IncompatibleGame {
+        supported: VersionRange,
+        game: Version,
+    },
+    ContentHashMismatch {
+        stamped: ContentHash,
+        computed: ContentHash,
+    },
+    WrongFileType {
+        path: PathBuf,
+        expected: &'static str,
+    },
+    EmptyFile(PathBuf),
+    FileTooLarge {
+        path: PathBuf,
+        size: usize,
+    },
+    SpecOutOfBounds {
+        spec: &'static str,
+        sheet: SheetIndex,
+        needed_wh: (usize, usize),
+        sheet_wh: (usize, usize),
+    },
 }
 
 impl core::fmt::Display for Error {
@@ -42,6 +70,36 @@ impl core::fmt::Display for Error {
             Png(e) => {
                 write!(f, "{e}")
             },
+            Io(path, e) => {
+                write!(f, "{}: {e}", path.display())
+            },
+            AbsolutePath(path) => {
+                write!(f, "Manifest paths must be relative. {} is not.", path.display())
+            },
+            PathOutsidePak(path) => {
+                write!(f, "Manifest paths must stay inside the pak, so they cannot contain \"..\". {} does.", path.display())
+            },
+            UnsupportedFormatVersion(version) => {
+                write!(f, "Pak format version {version} is not supported. Versions up to {FORMAT_VERSION} are.")
+            },
+            IncompatibleGame { supported, game } => {
+                write!(f, "This pak works with game versions {supported}, but this is version {game}.")
+            },
+            ContentHashMismatch { stamped, computed } => {
+                write!(f, "This pak's contents hash to {computed:016x}, but it was packed as {stamped:016x}. It may be corrupted.")
+            },
+            WrongFileType { path, expected } => {
+                write!(f, "{} should be {expected}, but is not.", path.display())
+            },
+            EmptyFile(path) => {
+                write!(f, "{} is empty.", path.display())
+            },
+            FileTooLarge { path, size } => {
+                write!(f, "{} is {size} bytes, but files can be at most {MAX_FILE_SIZE} bytes.", path.display())
+            },
+            SpecOutOfBounds { spec, sheet, needed_wh: (needed_w, needed_h), sheet_wh: (sheet_w, sheet_h) } => {
+                write!(f, "The {spec} spec needs spritesheet {sheet} to be at least {needed_w}x{needed_h}, but it is {sheet_w}x{sheet_h}.")
+            },
         }
     }
 }
@@ -68,6 +126,20 @@ impl std::error::Error for Error {
             Png(e) => {
                 Some(e)
             },
+            Io(_, e) => {
+                Some(e)
+            },
+            AbsolutePath(_)
+            | PathOutsidePak(_)
+            | UnsupportedFormatVersion(_)
+            | IncompatibleGame { .. }
+            | ContentHashMismatch { .. }
+            | WrongFileType { .. }
+            | EmptyFile(_)
+            | FileTooLarge { .. }
+            | SpecOutOfBounds { .. } => {
+                None
+            },
         }
     }
 }
@@ -94,66 +166,318 @@ from_def!{
 
 pub const MANIFEST_FILENAME: &str = "manifest.rn";
 
-pub fn from_reader<R>(reader: R) -> Result<Pak, Error> 
+/// The newest version of the pak format that this version of the game can read.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Larger files than this are rejected when packing. That is far more than any
+/// file in a pak should need, so anything larger is probably a mistake.
+pub const MAX_FILE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Packed paks have their content hash written into the zip comment, after this.
+const CONTENT_HASH_PREFIX: &str = "ripe content hash: ";
+
+mod content_hash {
+    //! FNV-1a. We want something that is stable across platforms and versions of
+    //! Rust, which rules out the standard library's hashers.
+    use super::ContentHash;
+
+    pub const START: ContentHash = 0xcbf2_9ce4_8422_2325;
+
+    pub fn add(hash: &mut ContentHash, bytes: &[u8]) {
+        // Include the length, so that moving bytes from the end of one file to the
+        // start of the next changes the hash.
+        for &byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            *hash ^= ContentHash::from(byte);
+            *hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// `seed` is passed along to the config, which can use it to generate parts of itself.
+pub fn from_reader<R>(reader: R, seed: Seed) -> Result<Pak, Error> 
     where R: PakReader
 {
     let mut archive = ZipArchive::new(reader)?;
 
-    macro_rules! by_path {
-        ($path: expr) => ({
-            let path = $path;
-            archive.by_path($path).map_err(|e| Error::ZipWithPath(path.clone(), e))?
-        })
+    let stamped = std::str::from_utf8(archive.comment()).ok()
+        .and_then(|comment| comment.strip_prefix(CONTENT_HASH_PREFIX))
+        .and_then(|hex| ContentHash::from_str_radix(hex.trim(), 16).ok());
+
+    let pak = from_files(|path| read_from_archive(&mut archive, path), seed)?;
+
+    // Paks packed before content hashes were stamped in won't have one, which is fine.
+    if let Some(stamped) = stamped
+        && stamped != pak.content_hash {
+        return Err(Error::ContentHashMismatch { stamped, computed: pak.content_hash });
     }
 
-    let manifest = {
-        let mut manifest_file = by_path!(PathBuf::from(MANIFEST_FILENAME));
-    
-        let mut manifest_buffer = Vec::with_capacity(
-            manifest_file.size().try_into()?
+    Ok(pak)
+}
+
+/// Reads only the main spritesheet of a packed pak, which is enough to draw text
+/// with, say to show why the rest of some other pak did not load.
+pub fn spritesheet_from_reader<R>(reader: R) -> Result<Spritesheet, Error>
+    where R: PakReader
+{
+    let mut archive = ZipArchive::new(reader)?;
+
+    let manifest_code: String = read_from_archive(&mut archive, MANIFEST_FILENAME.as_ref())?.try_into()?;
+
+    let manifest = config::parse_manifest(&manifest_code)?;
+
+    let spritesheet_buffer = read_from_archive(&mut archive, &manifest.spritesheet_path)?;
+
+    let png_frame = read_png_frame(std::io::Cursor::new(&spritesheet_buffer))?;
+
+    Ok(spritesheet_from_png_frame(&png_frame))
+}
+
+fn read_from_archive<R>(archive: &mut ZipArchive<R>, path: &Path) -> Result<Vec<u8>, Error>
+    where R: PakReader
+{
+    let mut file = archive.by_path(path)
+        .map_err(|e| Error::ZipWithPath(path.to_owned(), e))?;
+
+    let mut buffer = Vec::with_capacity(
+        file.size().try_into()?
+    );
+
+    file.read_to_end(&mut buffer).map_err(ZipError::from)?;
+
+    Ok(buffer)
+}
+
+/// Reads a pak that has not been packed yet, as a directory with a manifest in it.
+pub fn from_dir(dir: &Path, seed: Seed) -> Result<Pak, Error> {
+    from_files(|path| read_from_dir(dir, path), seed)
+}
+
+pub fn read_from_dir(dir: &Path, path: &Path) -> Result<Vec<u8>, Error> {
+    let path = dir.join(path);
+
+    std::fs::read(&path).map_err(|e| Error::Io(path, e))
+}
+
+/// Checks that the pak in `dir` loads, then writes it to `writer` in the packed
+/// form that `from_reader` reads. Returns the manifest, say for naming the output.
+pub fn pack<W>(dir: &Path, writer: W) -> Result<Manifest, Error>
+    where W: std::io::Write + std::io::Seek
+{
+    use std::io::Write;
+
+    // This is the same code the game uses to read the unpacked directory, so if this
+    // succeeds, the packed version should load as well. At least with this seed;
+    // `ripe-fuzz` can check configs that use the seed with many more of them.
+    let pak = from_dir(dir, <_>::default())?;
+
+    let manifest_string = String::from_utf8(
+        read_from_dir(dir, MANIFEST_FILENAME.as_ref())?
+    )?;
+
+    let manifest = config::parse_manifest(&manifest_string)?;
+
+    check_files(dir, &manifest)?;
+
+    let mut zip = zip::ZipWriter::new(writer);
+
+    zip.set_comment(format!("{CONTENT_HASH_PREFIX}{:016x}", pak.content_hash));
+
+    // The same file can be used more than once, say in multiple sound banks, but
+    // it only needs to be in the pak once.
+    let mut written = std::collections::BTreeSet::new();
+
+    for rel_path in manifest.paths() {
+        if !written.insert(rel_path) {
+            continue
+        }
+
+        zip.start_file_from_path(
+            rel_path,
+            zip::write::SimpleFileOptions::default(),
+        )?;
+
+        zip.write_all(&read_from_dir(dir, rel_path)?).map_err(ZipError::from)?;
+    }
+
+    zip.start_file_from_path(
+        MANIFEST_FILENAME,
+        zip::write::SimpleFileOptions::default(),
+    )?;
+
+    zip.write_all(manifest_string.as_bytes()).map_err(ZipError::from)?;
+
+    zip.finish()?;
+
+    Ok(manifest)
+}
+
+/// Checks for things that would load fine, or fail with a confusing error, but
+/// are probably mistakes, like a sound that is not actually an Ogg file.
+fn check_files(dir: &Path, manifest: &Manifest) -> Result<(), Error> {
+    const PNG: (&str, &[u8]) = ("a PNG image", b"\x89PNG\r\n\x1a\n");
+    const OGG: (&str, &[u8]) = ("an Ogg Vorbis file", b"OggS");
+
+    let expected_types = [
+        (manifest.spritesheet_path.as_path(), Some(PNG)),
+    ].into_iter()
+        .chain(
+            manifest.spritesheets.iter()
+                .map(|sheet| (sheet.path.as_path(), Some(PNG)))
+        )
+        .chain(
+            manifest.sounds.iter()
+                .flat_map(|bank| bank.paths.iter())
+                .map(|path| (path.as_path(), Some(OGG)))
+        )
+        .chain(
+            manifest.music.iter()
+                .map(|track| (track.path.as_path(), Some(OGG)))
         );
-    
-        manifest_file.read_to_end(&mut manifest_buffer).map_err(ZipError::from)?;
-    
-        let manifest_code: String = manifest_buffer.try_into()?;
-    
+
+    for path in manifest.paths() {
+        let bytes = read_from_dir(dir, path)?;
+
+        if bytes.is_empty() {
+            return Err(Error::EmptyFile(path.to_owned()));
+        }
+
+        if bytes.len() > MAX_FILE_SIZE {
+            return Err(Error::FileTooLarge { path: path.to_owned(), size: bytes.len() });
+        }
+    }
+
+    for (path, expected) in expected_types {
+        let Some((expected, magic)) = expected else { continue };
+
+        if !read_from_dir(dir, path)?.starts_with(magic) {
+            return Err(Error::WrongFileType { path: path.to_owned(), expected });
+        }
+    }
+
+    Ok(())
+}
+
+/// Everything about loading a pak that does not depend on where the files are kept.
+/// `read_file` is passed paths relative to the root of the pak, starting with
+/// `MANIFEST_FILENAME`.
+///
+/// The content hash is of every file that is read, in the order they are read:
+/// the manifest, then each of `Manifest::paths`.
+pub fn from_files(
+    mut read_file: impl FnMut(&Path) -> Result<Vec<u8>, Error>,
+    seed: Seed,
+) -> Result<Pak, Error> {
+    let mut content_hash = content_hash::START;
+
+    let mut read_file = |path: &Path| -> Result<Vec<u8>, Error> {
+        let bytes = read_file(path)?;
+
+        content_hash::add(&mut content_hash, &bytes);
+
+        Ok(bytes)
+    };
+
+    let manifest = {
+        let manifest_code: String = read_file(Path::new(MANIFEST_FILENAME))?.try_into()?;
+
         config::parse_manifest(&manifest_code)?
     };
 
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(Error::UnsupportedFormatVersion(manifest.format_version));
+    }
+
+    if !manifest.game_versions.contains(GAME_VERSION) {
+        return Err(Error::IncompatibleGame {
+            supported: manifest.game_versions,
+            game: GAME_VERSION,
+        });
+    }
+
+    for path in manifest.paths() {
+        check_manifest_path(path)?;
+    }
+
     let config = {
-        let mut config_file = by_path!(&manifest.config_path);
-    
-        let mut config_buffer = Vec::with_capacity(
-            config_file.size().try_into()?
-        );
-    
-        config_file.read_to_end(&mut config_buffer).map_err(ZipError::from)?;
-    
-        let config_code: String = config_buffer.try_into()?;
-    
-        config::parse(&config_code)?
+        let config_code: String = read_file(&manifest.config_path)?.try_into()?;
+
+        config::parse(&config_code, seed)?
     };
 
+    let mut spritesheets = Vec::with_capacity(1 + manifest.spritesheets.len());
 
-    let mut spritesheet_file = by_path!(&manifest.spritesheet_path);
+    let spritesheet_paths = core::iter::once(&manifest.spritesheet_path)
+        .chain(manifest.spritesheets.iter().map(|sheet| &sheet.path));
 
-    let mut spritesheet_buffer = Vec::with_capacity(
-        spritesheet_file.size().try_into()?
-    );
+    for path in spritesheet_paths {
+        let spritesheet_buffer = read_file(path)?;
 
-    spritesheet_file.read_to_end(&mut spritesheet_buffer).map_err(ZipError::from)?;
-    
-    // TODO? Are we actually getting enough extra compression
-    // out of a png to make having a png library included
-    // be worth it?
-    let png_frame = read_png_frame(std::io::Cursor::new(&spritesheet_buffer))?;
+        // TODO? Are we actually getting enough extra compression
+        // out of a png to make having a png library included
+        // be worth it?
+        let png_frame = read_png_frame(std::io::Cursor::new(&spritesheet_buffer))?;
 
-    let spritesheet = spritesheet_from_png_frame(&png_frame);
+        spritesheets.push(spritesheet_from_png_frame(&png_frame));
+    }
+
+    for (spec, pieces) in manifest.specs.named_pieces() {
+        // The config crate makes sure that each spec's sheet exists, but a missing
+        // one would be as out of bounds as it gets.
+        let sheet_wh = spritesheets.get(usize::from(pieces.sheet))
+            .map(|sheet: &Spritesheet| (sheet.width, sheet.len() / sheet.width))
+            .unwrap_or((0, 0));
+
+        let needed_wh = pieces.min_sheet_wh();
+
+        if needed_wh.0 > sheet_wh.0 || needed_wh.1 > sheet_wh.1 {
+            return Err(Error::SpecOutOfBounds {
+                spec,
+                sheet: pieces.sheet,
+                needed_wh,
+                sheet_wh,
+            });
+        }
+    }
+
+    let mut banks = Vec::with_capacity(manifest.sounds.len());
+
+    for bank_spec in &manifest.sounds {
+        let mut variants = Vec::with_capacity(bank_spec.paths.len());
+
+        for path in bank_spec.paths.iter() {
+            // These are decoded when played, so that a pak full of sounds does not
+            // take a long time to load.
+            variants.push(sound::Data::from(read_file(path)?));
+        }
+
+        banks.push(sound::Bank {
+            name: bank_spec.name.clone(),
+            variants: variants.try_into().expect("Sound banks should never be empty"),
+        });
+    }
+
+    let mut tracks = Vec::with_capacity(manifest.music.len());
+
+    for track_spec in &manifest.music {
+        tracks.push(music::Track {
+            name: track_spec.name.clone(),
+            data: music::Data::from(read_file(&track_spec.path)?),
+        });
+    }
+
+    // These are not used by the game, but they are still part of the pak.
+    for path in &manifest.extra_assets {
+        read_file(path)?;
+    }
 
     Ok(Pak {
         config,
-        spritesheet,
+        spritesheets: spritesheets.try_into().expect("There should always be the main spritesheet"),
         specs: manifest.specs,
+        sounds: sound::Banks(banks),
+        music: music::Tracks(tracks),
+        metadata: manifest.metadata,
+        content_hash,
     })
 }
 
@@ -263,4 +587,60 @@ pub fn spritesheet_from_png_frame(frame: &PngFrame) -> Spritesheet {
         cells: pixels.try_into().expect("No pixels found for spritesheet"),
         width: usize::try_from(frame.info.width).expect("Not expected to be run on less than 32 bit platforms"),
     }
-}
\ No newline at end of file
+}
+
+/// Manifest paths are joined onto the pak's directory when reading from one, so
+/// they must not be able to point anywhere else.
+fn check_manifest_path(path: &Path) -> Result<(), Error> {
+    use std::path::Component;
+
+    for component in path.components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir => {
+                return Err(Error::AbsolutePath(path.to_owned()));
+            },
+            Component::ParentDir => {
+                return Err(Error::PathOutsidePak(path.to_owned()));
+            },
+            Component::CurDir | Component::Normal(_) => {},
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn manifest_paths_must_stay_inside_the_pak() {
+    for fine in ["config.rn", "./sounds/click.ogg", "sheets/..hidden.png"] {
+        assert!(check_manifest_path(Path::new(fine)).is_ok(), "{fine}");
+    }
+
+    assert!(matches!(check_manifest_path(Path::new("/etc/passwd")), Err(Error::AbsolutePath(_))));
+
+    for outside in ["../secret.png", "sounds/../../secret.ogg", "sounds/.."] {
+        assert!(matches!(check_manifest_path(Path::new(outside)), Err(Error::PathOutsidePak(_))), "{outside}");
+    }
+}
+
+#[cfg(test)]
+mod pack_works {
+    use super::*;
+
+    #[test]
+    fn on_the_default_example() {
+        let dir = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../../examples/default"));
+
+        let mut packed = std::io::Cursor::new(Vec::new());
+        pack(dir, &mut packed).unwrap();
+        packed.set_position(0);
+
+        let unpacked = from_dir(dir, <_>::default()).unwrap();
+        let repacked = from_reader(packed, <_>::default()).unwrap();
+
+        assert_eq!(repacked.spritesheets.len(), unpacked.spritesheets.len());
+        assert_eq!(repacked.spritesheets[0].slice(), unpacked.spritesheets[0].slice());
+        assert_eq!(repacked.sounds.0.len(), unpacked.sounds.0.len());
+        assert_eq!(repacked.music.0.len(), unpacked.music.0.len());
+        assert_eq!(repacked.content_hash, unpacked.content_hash);
+    }
+}
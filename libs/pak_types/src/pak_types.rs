@@ -1,11 +1,15 @@
 #![deny(unreachable_patterns)]
 
 use gfx_sizes::*;
-use vec1::Grid1;
+use vec1::{Grid1, Vec1};
+
+use serde::{Deserialize, Serialize};
 
 pub mod unscaled {
     ///! Values are in pixels.
 
+    use serde::{Deserialize, Serialize};
+
     pub type Inner = i16;
     // Useful for intermediate calculations
     pub type NextUp = i32;
@@ -30,7 +34,7 @@ pub mod unscaled {
         ($($name: ident, $inner_name: ident = $inner_type: ident)+) => {
             $(
                 pub type $inner_name = $inner_type;
-                #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+                #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
                 pub struct $name(pub $inner_name);
 
                 impl $name {
@@ -68,7 +72,7 @@ pub mod unscaled {
     }
 
     pub type WInner = Inner;
-    #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct W(WInner);
 
     impl W {
@@ -90,7 +94,7 @@ pub mod unscaled {
     }
 
     pub type HInner = Inner;
-    #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Copy, Clone, Default, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct H(HInner);
 
     impl H {
@@ -303,7 +307,7 @@ pub mod unscaled {
         }
     }
 
-    #[derive(Clone, Copy, Default, Debug, PartialEq, Eq)]
+    #[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Deserialize, Serialize)]
     pub struct XY {
         pub x: X,
         pub y: Y,
@@ -500,7 +504,7 @@ pub mod unscaled {
         W H
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
     pub struct WH {
         pub w: W,
         pub h: H,
@@ -516,7 +520,7 @@ pub mod unscaled {
         }
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
     pub struct XYD {
         pub xd: XD,
         pub yd: YD,
@@ -531,7 +535,7 @@ pub mod unscaled {
         }
     }
 
-    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
     pub struct Rect {
         pub x: X,
         pub y: Y,
@@ -1087,8 +1091,13 @@ pub mod sprite {
 
     pub type TileCount = u8;
 
+    /// Which of a pak's spritesheets to draw from. 0 is the one from the manifest's
+    /// `spritesheet_path`, and the ones listed in its `spritesheets` follow, in order.
+    pub type SheetIndex = u8;
+
     #[derive(Clone, Debug)]
     pub struct Spec<Marker> {
+        sheet: SheetIndex,
         offset: WH,
         tile: WH,
         tiles_per_row: TileCount,
@@ -1096,6 +1105,10 @@ pub mod sprite {
     }
 
     impl <Marker> Spec<Marker> {
+        pub fn sheet(&self) -> SheetIndex {
+            self.sheet
+        }
+
         pub fn tile(&self) -> WH {
             self.tile
         }
@@ -1172,21 +1185,18 @@ pub mod sprite {
             )
         }
 
-        /// Not advised for general use, but only when initally constructing the specs
-        /// while retaining the default values from Specs.
-        // TODO? Is there a clean way to allow that to work, and avoid exposing this?
-        // Exposing this is not the biggest deal of course. Keeping the rune stuff in
-        // one crate seems more important than this project's code "relying too much"
-        // on itself.
+        /// Mostly useful when constructing specs while retaining the default values
+        /// from Specs. See `Specs::try_map_named_pieces`.
         pub fn pieces(&self) -> SpecPieces {
             SpecPieces {
+                sheet: self.sheet,
                 offset: self.offset,
                 tile: self.tile,
                 tiles_per_row: self.tiles_per_row,
             }
         }
 
-        pub fn xy_from_tile_sprite<TileSprite: Into<u16>>(&self, tile_sprite: TileSprite) -> XY<Renderable> {
+        pub fn xy_from_tile_sprite<TileSprite: Into<u16>>(&self, tile_sprite: TileSprite) -> SheetXY {
             let tile = self.tile();
             let tiles_per_row = unscaled::Inner::from(self.tiles_per_row());
             let tile_sprite: unscaled::Inner = tile_sprite.into().try_into().expect("tile_sprite too large");
@@ -1198,14 +1208,30 @@ pub mod sprite {
         }
     }
 
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct SpecPieces {
+        pub sheet: SheetIndex,
         pub offset: WH,
         pub tile: WH,
         pub tiles_per_row: TileCount,
     }
 
-    pub fn spec<Marker>(SpecPieces { offset, tile, tiles_per_row }: SpecPieces) -> Spec<Marker> {
+    impl SpecPieces {
+        /// The width and height of the part of the spritesheet that a spec with these
+        /// pieces can draw from, starting from the top left of the spritesheet. Any
+        /// rows after the first are not included, since we don't know how many there are.
+        /// Tiles that run past the end of the spritesheet are drawn as transparent.
+        pub fn min_sheet_wh(&self) -> (usize, usize) {
+            (
+                usize::from(self.offset.w) + usize::from(self.tile.w) * usize::from(self.tiles_per_row),
+                usize::from(self.offset.h) + usize::from(self.tile.h),
+            )
+        }
+    }
+
+    pub fn spec<Marker>(SpecPieces { sheet, offset, tile, tiles_per_row }: SpecPieces) -> Spec<Marker> {
         Spec::<Marker> {
+            sheet,
             offset,
             tile,
             tiles_per_row,
@@ -1226,21 +1252,31 @@ pub mod sprite {
     }
 
     impl <Marker> XY<Marker> {
-        pub fn apply(self, spec: &Spec<Marker>) -> XY<Renderable> {
-            XY::<Renderable>{
-                x: self.x.apply(spec),
-                y: self.y.apply(spec),
+        pub fn apply(self, spec: &Spec<Marker>) -> SheetXY {
+            SheetXY {
+                sheet: spec.sheet,
+                xy: XY::<Renderable>{
+                    x: self.x.apply(spec),
+                    y: self.y.apply(spec),
+                },
             }
         }
     }
 
     /// Marker
-    /// The rendering commands store only allow `sprite::XY<Renderable>`
-    /// so all other types must be converted to `sprite::XY<Renderable>`
+    /// The rendering commands store only allow `sprite::XY<Renderable>`, inside a
+    /// `sprite::SheetXY`, so all other types must be converted to that
     /// via a `sprite::Spec<A>` for the appropriate `A`.
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
     pub struct Renderable;
 
+    /// A position on a particular spritesheet.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct SheetXY {
+        pub sheet: SheetIndex,
+        pub xy: XY<Renderable>,
+    }
+
     macro_rules! specs_and_markers_def {
         (
             $(
@@ -1269,6 +1305,7 @@ pub mod sprite {
                     Self {
                         $(
                             $field: spec::<$marker> (SpecPieces{
+                                sheet: 0,
                                 offset: WH{ w: W::new($offset_w), h: H::new($offset_h) },
                                 tile: WH{ w: W::new($tile_w), h: H::new($tile_h) },
                                 tiles_per_row: $tiles_per_row,
@@ -1277,6 +1314,31 @@ pub mod sprite {
                     }
                 }
             }
+
+            impl Specs {
+                /// Each spec's pieces, along with the name of its field, which is also
+                /// the key used for it in manifests.
+                pub fn named_pieces(&self) -> Vec<(&'static str, SpecPieces)> {
+                    vec![
+                        $(
+                            (stringify!($field), self.$field.pieces()),
+                        )+
+                    ]
+                }
+
+                /// Replaces each spec with one made from what `f` returns, when passed
+                /// the name of the spec's field and its current pieces.
+                pub fn try_map_named_pieces<E>(
+                    &mut self,
+                    mut f: impl FnMut(&'static str, SpecPieces) -> Result<SpecPieces, E>,
+                ) -> Result<(), E> {
+                    $(
+                        self.$field = spec::<$marker>(f(stringify!($field), self.$field.pieces())?);
+                    )+
+
+                    Ok(())
+                }
+            }
         }
     }
 
@@ -1380,6 +1442,34 @@ pub mod sprite {
 }
 pub use sprite::Specs;
 
+#[cfg(test)]
+mod specs_works {
+    use super::*;
+
+    #[test]
+    fn on_the_default_spritesheet() {
+        for (name, pieces) in Specs::default().named_pieces() {
+            let (w, h) = pieces.min_sheet_wh();
+
+            assert!(w <= GFX_WIDTH && h <= GFX_HEIGHT, "{name} needs {w}x{h}");
+        }
+    }
+
+    #[test]
+    fn when_mapping_every_spec() {
+        let mut specs = Specs::default();
+
+        specs.try_map_named_pieces(|_, mut pieces| -> Result<_, ()> {
+            pieces.tiles_per_row += 1;
+            Ok(pieces)
+        }).unwrap();
+
+        for ((name, before), (_, after)) in Specs::default().named_pieces().into_iter().zip(specs.named_pieces()) {
+            assert_eq!(after.tiles_per_row, before.tiles_per_row + 1, "{name}");
+        }
+    }
+}
+
 /// 64k entity definitions ought to be enough for anybody!
 pub type DefId = u16;
 // TODO? allow large enough deltas to represent going from DefId::MIN to DefId::MAX?
@@ -1393,7 +1483,7 @@ pub type SegmentWidth = std::num::NonZeroUsize;
 /// 64k world segments ought to be enough for anybody!
 pub type SegmentId = u16;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Speech {
     pub text: String,
 }
@@ -1445,17 +1535,45 @@ macro_rules! consts_def {
     };
 }
 
+/// Every kind of hallway, one per line. This is the only list of them, so adding a
+/// hallway means adding a line here, with the name of its `HallwaySpec` variant, its
+/// parameters, the constant configs use to pick it, and the type of its state, which
+/// must implement `hallway::Hallway`. The state types are only used by `world`.
+///
+/// Passes the list to the given macro, which must be in scope where this is called.
+#[macro_export]
+macro_rules! hallway_kinds {
+    ($callback: ident) => {
+        $callback!{
+            IcePuzzle(IcePuzzleParams) ICE_PUZZLE = 1 => ice_puzzle::State,
+            // Staff Whacking Ordeal Required, Duh
+            SWORD(SWORDParams) SWORD = 2 => sword::State,
+            // Boldly Or Leisurely Dashing
+            // or
+            // Boulders Often Lope Downwards
+            BOLD(BOLDParams) BOLD = 3 => bold::State,
+            HexHop(HexHopParams) HEX_HOP = 4 => hex_hop::State,
+            HexTwiddle(HexTwiddleParams) HEX_TWIDDLE = 5 => hex_twiddle::State,
+            CubeMaze(CubeMazeParams) CUBE_MAZE = 6 => cube_maze::State,
+            KeycardShuffle(KeycardShuffleParams) KEYCARD_SHUFFLE = 7 => keycard_shuffle::State,
+        }
+    }
+}
+
 mod hallway_spec {
+    use serde::{Deserialize, Serialize};
+    use std::ops::RangeInclusive;
+
     macro_rules! def {
         (
-            $($enum_name: ident $const_name: ident = $value: expr),+ $(,)?
+            $($enum_name: ident ($params: ident) $const_name: ident = $value: expr => $_state: ty),+ $(,)?
         ) => {
             #[derive(Clone, Copy, Debug, Default)]
             pub enum HallwaySpec {
                 #[default]
                 None,
                 $(
-                    $enum_name,
+                    $enum_name($params),
                 )+
             }
 
@@ -1471,11 +1589,12 @@ mod hallway_spec {
                 }
             }
 
+            /// Returns the spec for the given kind, with the default parameters for that kind.
             pub fn const_to_hallway_spec(kind: consts::HallwayKind) -> Option<HallwaySpec> {
                 Some(match kind {
                     consts::NONE => HallwaySpec::None,
                     $(
-                        consts::$const_name => HallwaySpec::$enum_name,
+                        consts::$const_name => HallwaySpec::$enum_name(<_>::default()),
                     )+
                     _ => return None,
                 })
@@ -1483,14 +1602,154 @@ mod hallway_spec {
         }
     }
 
-    def!{
-        IcePuzzle ICE_PUZZLE = 1,
-        SWORD SWORD = 2,
-        BOLD BOLD = 3,
-        HexHop HEX_HOP = 4,
-        HexTwiddle HEX_TWIDDLE = 5,
-        CubeMaze CUBE_MAZE = 6,
-        KeycardShuffle KEYCARD_SHUFFLE = 7,
+    hallway_kinds!(def);
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct IcePuzzleParams {
+        /// In tiles. `None` means as wide as fits on the screen. Larger values are
+        /// also clamped to that.
+        pub width: Option<u8>,
+        /// In tiles. `None` means as tall as fits on the screen. Larger values are
+        /// also clamped to that.
+        pub height: Option<u8>,
+        /// The most moves the first level can need. Each later level can need one more.
+        pub max_steps: u8,
+        /// How many levels need to be cleared to get through.
+        pub levels: u8,
+    }
+
+    impl IcePuzzleParams {
+        pub const SIDE_LENGTH: RangeInclusive<u8> = 3..=64;
+        // Generation time grows exponentially with the step count, so we keep
+        // `MAX_STEPS.end() + LEVELS.end()` small.
+        pub const MAX_STEPS: RangeInclusive<u8> = 1..=6;
+        pub const LEVELS: RangeInclusive<u8> = 1..=3;
+    }
+
+    impl Default for IcePuzzleParams {
+        fn default() -> Self {
+            Self {
+                width: None,
+                height: None,
+                max_steps: 4,
+                levels: 1,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct SWORDParams {
+        /// How many switches, each with a door they toggle, to try to place.
+        pub switch_count: u8,
+    }
+
+    impl SWORDParams {
+        pub const SWITCH_COUNT: RangeInclusive<u8> = 0..=8;
+    }
+
+    impl Default for SWORDParams {
+        fn default() -> Self {
+            Self {
+                switch_count: 3,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct BOLDParams {
+        /// The percentage of open tiles that start with a boulder on them.
+        pub boulder_percent: u8,
+        /// The percentage of open tiles that start with a gem on them.
+        pub gem_percent: u8,
+    }
+
+    impl BOLDParams {
+        /// Applies to each of the percentages, and to their sum.
+        pub const PERCENT: RangeInclusive<u8> = 0..=100;
+    }
+
+    impl Default for BOLDParams {
+        fn default() -> Self {
+            Self {
+                boulder_percent: 12,
+                gem_percent: 13,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct HexHopParams {
+        /// How many hexes out from the center hex the level extends.
+        pub radius: u8,
+    }
+
+    impl HexHopParams {
+        pub const RADIUS: RangeInclusive<u8> = 2..=4;
+    }
+
+    impl Default for HexHopParams {
+        fn default() -> Self {
+            Self {
+                radius: 3,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct HexTwiddleParams {
+        /// How many hexes out from the center hex the level extends.
+        pub radius: u8,
+    }
+
+    impl HexTwiddleParams {
+        pub const RADIUS: RangeInclusive<u8> = 2..=3;
+    }
+
+    impl Default for HexTwiddleParams {
+        fn default() -> Self {
+            Self {
+                radius: 2,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct CubeMazeParams {
+        /// The width and height of each face of the cube, in tiles. Must be odd.
+        pub size: u8,
+    }
+
+    impl CubeMazeParams {
+        pub const SIZE: RangeInclusive<u8> = 7..=13;
+    }
+
+    impl Default for CubeMazeParams {
+        fn default() -> Self {
+            Self {
+                size: 11,
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct KeycardShuffleParams {
+        pub lock_count: u8,
+        /// Each lock needs between one and this many cards to open.
+        pub max_cards_per_lock: u8,
+    }
+
+    impl KeycardShuffleParams {
+        pub const LOCK_COUNT: RangeInclusive<u8> = 1..=8;
+        pub const MAX_CARDS_PER_LOCK: RangeInclusive<u8> = 1..=2;
+    }
+
+    impl Default for KeycardShuffleParams {
+        fn default() -> Self {
+            Self {
+                lock_count: 5,
+                max_cards_per_lock: 2,
+            }
+        }
     }
 }
 pub use hallway_spec::const_to_hallway_spec;
@@ -1501,8 +1760,10 @@ pub mod config {
     use vec1::{Grid1, Vec1};
     use crate::{
         consts::{EntityDefFlags, TileFlags},
-        DefId, OnCollect, SegmentWidth, Specs, Speech, TileSprite
+        script,
+        DefId, OnCollect, Scripts, SegmentWidth, Specs, Speech, TileSprite
     };
+    use std::ops::RangeInclusive;
     use std::path::PathBuf;
 
     /// A configuration WorldSegment that can be used to construct game::WorldSegments later.
@@ -1513,14 +1774,76 @@ pub mod config {
         pub segments: Vec1<WorldSegment>,
         pub entities: Vec1<EntityDef>,
         pub hallways: Vec1<HallwaySpec>,
+        pub spheres: SpheresSpec,
+        pub music: MusicSpec,
+        pub scripts: Scripts,
+    }
+
+    /// Which of the manifest's music tracks play where, by name. Anywhere without
+    /// a track, or with a name the manifest does not have, is silent.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct MusicSpec {
+        /// Indexed the same as `Config::segments`.
+        pub segments: Vec<Option<String>>,
+        /// Indexed the same as `Config::hallways`.
+        pub hallways: Vec<Option<String>>,
+        pub victory: Option<String>,
+    }
+
+    /// How the world is split up into spheres of progression. Each sphere is a separate
+    /// puzzle, with the goal of getting the key that unlocks the door to the next one.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct SpheresSpec {
+        pub min_count: u8,
+        pub max_count: u8,
+        /// How many segments each sphere has.
+        pub min_segments: u8,
+        pub max_segments: u8,
+        /// The percentage of items and NPCs, besides the ones holding each sphere's
+        /// key, that are placed back in an earlier sphere instead.
+        pub push_back_percent: u8,
+    }
+
+    impl SpheresSpec {
+        /// Each sphere after the first needs a different kind of locked door, so the
+        /// config also needs to have enough of those.
+        pub const COUNT: RangeInclusive<u8> = 1..=16;
+        pub const SEGMENTS: RangeInclusive<u8> = 1..=15;
+        pub const PERCENT: RangeInclusive<u8> = 0..=100;
+    }
+
+    impl Default for SpheresSpec {
+        fn default() -> Self {
+            Self {
+                min_count: 2,
+                max_count: 5,
+                min_segments: 2,
+                max_segments: 3,
+                push_back_percent: 20,
+            }
+        }
     }
 
     #[derive(Clone, Debug)]
     pub struct Manifest {
         pub name: String,
+        /// Which version of the pak format the pak was written for. Paks without
+        /// one are taken to be version 1.
+        pub format_version: u32,
+        /// The versions of the game that the pak works with.
+        pub game_versions: VersionRange,
+        pub metadata: Metadata,
         pub config_path: PathBuf,
         pub spritesheet_path: PathBuf,
+        /// Spritesheets besides the one at `spritesheet_path`, which specs can
+        /// pick by name.
+        pub spritesheets: Vec<SpritesheetSpec>,
         pub specs: Specs,
+        pub sounds: Vec<SoundBankSpec>,
+        pub music: Vec<TrackSpec>,
+        /// Any other files that should be packed along with the pak, like a
+        /// readme or a license.
+        pub extra_assets: Vec<PathBuf>,
     }
 
     impl Manifest {
@@ -1529,9 +1852,199 @@ pub mod config {
                 self.config_path.as_path(),
                 self.spritesheet_path.as_path()
             ].into_iter()
+                .chain(
+                    self.spritesheets.iter()
+                        .map(|sheet| sheet.path.as_path())
+                )
+                .chain(
+                    self.sounds.iter()
+                        .flat_map(|bank| bank.paths.iter().map(PathBuf::as_path))
+                )
+                .chain(
+                    self.music.iter()
+                        .map(|track| track.path.as_path())
+                )
+                .chain(
+                    self.extra_assets.iter()
+                        .map(PathBuf::as_path)
+                )
+        }
+    }
+
+    /// Descriptive information about a pak, for people rather than the game.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct Metadata {
+        pub title: String,
+        pub author: String,
+        pub description: String,
+    }
+
+    /// The version of the game, which paks can say that they are compatible with.
+    /// This comes from the workspace's `Cargo.toml`, so it stays in step with the
+    /// version of `ripe`.
+    pub const GAME_VERSION: Version = Version::from_cargo(env!("CARGO_PKG_VERSION"));
+
+    /// A `major.minor.patch` version number.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct Version {
+        pub major: u16,
+        pub minor: u16,
+        pub patch: u16,
+    }
+
+    impl core::fmt::Display for Version {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+        }
+    }
+
+    impl Version {
+        /// For versions from Cargo, which are always like `"1.2.3"`, maybe followed
+        /// by a suffix like `"-alpha"`, which is ignored. Panics on anything else,
+        /// which at compile time is a compile error.
+        const fn from_cargo(s: &str) -> Self {
+            let bytes = s.as_bytes();
+
+            let mut parts = [0u16; 3];
+            let mut part_index = 0;
+
+            let mut i = 0;
+            while i < bytes.len() {
+                match bytes[i] {
+                    digit @ b'0'..=b'9' => {
+                        parts[part_index] = parts[part_index] * 10 + (digit - b'0') as u16;
+                    },
+                    b'.' => {
+                        part_index += 1;
+                        assert!(part_index < parts.len(), "Cargo versions have three parts");
+                    },
+                    _ => break,
+                }
+
+                i += 1;
+            }
+
+            Self {
+                major: parts[0],
+                minor: parts[1],
+                patch: parts[2],
+            }
+        }
+    }
+
+    impl core::str::FromStr for Version {
+        type Err = ();
+
+        /// Missing trailing parts are taken to be 0, so `"1"` is the same as `"1.0.0"`.
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let mut parts = s.trim().split('.');
+
+            let mut next = || -> Result<u16, ()> {
+                match parts.next() {
+                    None => Ok(0),
+                    Some(part) => part.parse().map_err(|_| ()),
+                }
+            };
+
+            let version = Self {
+                major: next()?,
+                minor: next()?,
+                patch: next()?,
+            };
+
+            if parts.next().is_some() {
+                return Err(())
+            }
+
+            Ok(version)
         }
     }
 
+    /// An inclusive range of versions, where a missing `max` means there is no
+    /// upper limit.
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct VersionRange {
+        pub min: Version,
+        pub max: Option<Version>,
+    }
+
+    impl VersionRange {
+        pub fn contains(&self, version: Version) -> bool {
+            version >= self.min
+            && self.max.is_none_or(|max| version <= max)
+        }
+    }
+
+    impl core::fmt::Display for VersionRange {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self.max {
+                Some(max) => write!(f, "{} to {}", self.min, max),
+                None => write!(f, "{} or later", self.min),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod version_works {
+        use super::*;
+
+        #[test]
+        fn on_these_strings() {
+            assert_eq!("1.2.3".parse(), Ok(Version { major: 1, minor: 2, patch: 3 }));
+            assert_eq!("0.1".parse(), Ok(Version { major: 0, minor: 1, patch: 0 }));
+            assert_eq!("".parse::<Version>(), Err(()));
+            assert_eq!("1.2.3.4".parse::<Version>(), Err(()));
+            assert_eq!("1.x".parse::<Version>(), Err(()));
+        }
+
+        #[test]
+        fn in_these_ranges() {
+            let v = |s: &str| s.parse::<Version>().unwrap();
+
+            let range = VersionRange { min: v("0.1"), max: Some(v("0.2.5")) };
+            assert!(range.contains(v("0.1.0")));
+            assert!(range.contains(v("0.2.5")));
+            assert!(!range.contains(v("0.0.9")));
+            assert!(!range.contains(v("0.3")));
+
+            let open = VersionRange { min: v("1"), max: None };
+            assert!(open.contains(v("100.0.0")));
+        }
+
+        #[test]
+        fn from_cargo_on_these_strings() {
+            assert_eq!(Version::from_cargo("1.2.3"), Version { major: 1, minor: 2, patch: 3 });
+            assert_eq!(Version::from_cargo("10.20.30-alpha.1"), Version { major: 10, minor: 20, patch: 30 });
+            assert_eq!(GAME_VERSION.to_string().parse(), Ok(GAME_VERSION));
+            assert_eq!(GAME_VERSION.to_string(), env!("CARGO_PKG_VERSION"));
+        }
+    }
+
+    /// A named set of sounds, one of which is picked at random each time a sound
+    /// effect with that name is requested.
+    #[derive(Clone, Debug)]
+    pub struct SoundBankSpec {
+        pub name: String,
+        /// Paths to Ogg Vorbis files.
+        pub paths: Vec1<PathBuf>,
+    }
+
+    /// A named piece of music, which loops for as long as it is playing.
+    #[derive(Clone, Debug)]
+    pub struct TrackSpec {
+        pub name: String,
+        /// A path to an Ogg Vorbis file.
+        pub path: PathBuf,
+    }
+
+    /// A named spritesheet, which specs can say they are on.
+    #[derive(Clone, Debug)]
+    pub struct SpritesheetSpec {
+        pub name: String,
+        /// A path to a PNG file.
+        pub path: PathBuf,
+    }
+
     pub type SpeechesList = Vec<Vec1<Speech>>;
 
     #[derive(Clone, Debug)]
@@ -1543,9 +2056,19 @@ pub mod config {
         pub tile_sprite: TileSprite,
         pub wants: Vec<DefId>,
         pub on_collect: OnCollect,
-    }
-
-    pub use crate::hallway_spec::HallwaySpec;
+        pub scripts: script::Names,
+    }
+
+    pub use crate::hallway_spec::{
+        HallwaySpec,
+        IcePuzzleParams,
+        SWORDParams,
+        BOLDParams,
+        HexHopParams,
+        HexTwiddleParams,
+        CubeMazeParams,
+        KeycardShuffleParams,
+    };
 }
 pub use config::{Config, EntityDef, SpeechesList};
 
@@ -1557,6 +2080,18 @@ pub mod consts {
         TRANSFORM = 1,
     }
 
+    pub type ScriptActionKind = u8;
+
+    consts_def!{
+        ALL_SCRIPT_ACTION_KINDS: ScriptActionKind;
+        GIVE_ITEM = 1,
+        TAKE_ITEM = 2,
+        TRANSFORM_ALL = 3,
+        TALK = 4,
+        MESSAGE = 5,
+        DOOR_TO = 6,
+    }
+
     pub type EntityDefIdRefKind = u8;
 
     consts_def!{
@@ -1599,23 +2134,265 @@ pub const STEPPABLE: EntityFlags = 1 << 1;
 pub const VICTORY: EntityFlags = 1 << 2;
 pub const DOOR: EntityFlags = 1 << 3;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Transform {
     pub from: DefId,
     pub to: DefId,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum CollectAction {
     Transform(Transform),
 }
 
 pub type OnCollect = Vec<CollectAction>;
 
+/// Rune functions, named by entity definitions, that run when the player does
+/// things with those entities. Scripts cannot touch the world directly. Instead
+/// they are given a `View` of it, and return `Action`s for the game to carry out.
+pub mod script {
+    use crate::{DefId, SegmentId, Transform};
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+    use xs::Seed;
+
+    /// When a script can run.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Hook {
+        /// When the player interacts with the entity.
+        Interact,
+        /// When the player steps onto the entity.
+        Step,
+        /// When the player picks the entity up off of the floor.
+        Collect,
+    }
+
+    impl Hook {
+        /// The name of the entity definition field that names the function.
+        pub fn field_name(self) -> &'static str {
+            match self {
+                Hook::Interact => "on_interact",
+                Hook::Step => "on_step",
+                Hook::Collect => "on_collect",
+            }
+        }
+    }
+
+    /// The names of the functions to run for each `Hook`, if any.
+    #[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+    pub struct Names {
+        pub on_interact: Option<String>,
+        pub on_step: Option<String>,
+        pub on_collect: Option<String>,
+    }
+
+    impl Names {
+        pub const NONE: Self = Self {
+            on_interact: None,
+            on_step: None,
+            on_collect: None,
+        };
+
+        pub fn get(&self, hook: Hook) -> Option<&str> {
+            match hook {
+                Hook::Interact => self.on_interact.as_deref(),
+                Hook::Step => self.on_step.as_deref(),
+                Hook::Collect => self.on_collect.as_deref(),
+            }
+        }
+    }
+
+    /// What a script gets to see of the world.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct View {
+        /// The definition of the entity that named the script.
+        pub def_id: DefId,
+        /// What that entity is holding.
+        pub inventory: Vec<DefId>,
+        pub player_inventory: Vec<DefId>,
+        /// The segment the player is in.
+        pub segment_id: SegmentId,
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Action {
+        /// Gives the player a new item with this definition.
+        GiveItem(DefId),
+        /// Takes an item with this definition from the player, if they have one.
+        TakeItem(DefId),
+        /// Transforms every entity with the `from` definition, the same way
+        /// `CollectAction::Transform` does.
+        Transform(Transform),
+        /// Shows one of the speech lists of an entity definition. `state` is the
+        /// index of the list in the definition's `speeches`.
+        Talk { def_id: DefId, state: u8 },
+        /// Shows a short message by the player, which fades away.
+        Message(String),
+        /// Sends the player through a door to the tile at `x`, `y` in the segment,
+        /// by way of the hallway between here and there, if there is one.
+        DoorTo { segment_id: SegmentId, x: u16, y: u16 },
+    }
+
+    /// Why a script did not give back a list of actions.
+    #[derive(Debug)]
+    pub enum Failure {
+        /// It ran for longer than scripts are allowed to, so it was stopped.
+        OutOfBudget,
+        /// It failed, or returned something other than a list of actions.
+        Message(String),
+    }
+
+    pub trait Run {
+        /// Calls the function called `name`, for `hook`. Anything random the
+        /// function does should depend only on `seed`.
+        fn run(&self, hook: Hook, name: &str, seed: Seed, view: &View) -> Result<Vec<Action>, Failure>;
+    }
+
+    /// Runs the scripts of the config that was loaded, if there is one.
+    #[derive(Clone, Default)]
+    pub struct Scripts(Option<Arc<dyn Run>>);
+
+    impl Scripts {
+        pub fn new(run: Arc<dyn Run>) -> Self {
+            Self(Some(run))
+        }
+
+        pub fn run(&self, hook: Hook, name: &str, seed: Seed, view: &View) -> Result<Vec<Action>, Error> {
+            let Some(run) = &self.0 else {
+                return Err(Error::NothingToRun { hook, name: name.to_owned() });
+            };
+
+            run.run(hook, name, seed, view)
+                .map_err(|failure| match failure {
+                    Failure::OutOfBudget => Error::OutOfBudget { hook, name: name.to_owned() },
+                    Failure::Message(message) => Error::Run { hook, name: name.to_owned(), message },
+                })
+        }
+    }
+
+    impl core::fmt::Debug for Scripts {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            f.debug_tuple("Scripts")
+                .field(&if self.0.is_some() { "loaded" } else { "none" })
+                .finish()
+        }
+    }
+
+    #[derive(Debug)]
+    pub enum Error {
+        /// No scripts were loaded, but an entity named one anyway.
+        NothingToRun { hook: Hook, name: String },
+        /// The function failed, or returned something other than a list of actions.
+        Run { hook: Hook, name: String, message: String },
+        /// The function ran for too long, which most likely means it loops forever.
+        OutOfBudget { hook: Hook, name: String },
+        UnknownDefId { hook: Hook, name: String, def_id: DefId },
+        NoSpeech { hook: Hook, name: String, def_id: DefId, state: u8 },
+        NoSuchTile { hook: Hook, name: String, segment_id: SegmentId, x: u16, y: u16 },
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use Error::*;
+            match self {
+                NothingToRun { hook, name } => write!(
+                    f,
+                    "{name}, the {} script, could not be run, since no scripts are loaded",
+                    hook.field_name(),
+                ),
+                Run { hook, name, message } => write!(
+                    f,
+                    "{name}, the {} script, failed:\n{message}",
+                    hook.field_name(),
+                ),
+                OutOfBudget { hook, name } => write!(
+                    f,
+                    "{name}, the {} script, ran for too long, so it was stopped. Does it loop forever?",
+                    hook.field_name(),
+                ),
+                UnknownDefId { hook, name, def_id } => write!(
+                    f,
+                    "{name}, the {} script, referred to entity {def_id}, but there is no entity with that index in entities",
+                    hook.field_name(),
+                ),
+                NoSpeech { hook, name, def_id, state } => write!(
+                    f,
+                    "{name}, the {} script, asked for speeches[{state}] of entity {def_id}, but it has no such speeches",
+                    hook.field_name(),
+                ),
+                NoSuchTile { hook, name, segment_id, x, y } => write!(
+                    f,
+                    "{name}, the {} script, sent the player to {x}, {y} in segment {segment_id}, but there is no such tile in segments",
+                    hook.field_name(),
+                ),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+}
+pub use script::Scripts;
+
 pub type Spritesheet = Grid1<ARGB, usize>;
 
+/// Indexed by `sprite::SheetIndex`.
+pub type Spritesheets = Vec1<Spritesheet>;
+
+pub mod sound {
+    use std::sync::Arc;
+    use vec1::Vec1;
+
+    /// The contents of an Ogg Vorbis file. This is shared so that it can be
+    /// handed to whatever is playing the sound without copying.
+    pub type Data = Arc<[u8]>;
+
+    #[derive(Clone, Debug)]
+    pub struct Bank {
+        pub name: String,
+        pub variants: Vec1<Data>,
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Banks(pub Vec<Bank>);
+
+    impl Banks {
+        pub fn get(&self, name: &str) -> Option<&Bank> {
+            self.0.iter().find(|bank| bank.name == name)
+        }
+    }
+}
+
+pub mod music {
+    use std::sync::Arc;
+
+    /// The contents of an Ogg Vorbis file, which is looped while it plays.
+    pub type Data = Arc<[u8]>;
+
+    #[derive(Clone, Debug)]
+    pub struct Track {
+        pub name: String,
+        pub data: Data,
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Tracks(pub Vec<Track>);
+
+    impl Tracks {
+        pub fn get(&self, name: &str) -> Option<&Track> {
+            self.0.iter().find(|track| track.name == name)
+        }
+    }
+}
+
+/// Identifies the contents of a pak, the same way whether it is packed or not.
+pub type ContentHash = u64;
+
 pub struct Pak {
     pub config: Config,
-    pub spritesheet: Spritesheet,
+    pub spritesheets: Spritesheets,
     pub specs: Specs,
+    pub sounds: sound::Banks,
+    pub music: music::Tracks,
+    pub metadata: config::Metadata,
+    pub content_hash: ContentHash,
 }
@@ -1,5 +1,5 @@
 use platform_types::{
-    PakReader,
+    PakSource,
     StateParams,
 };
 
@@ -25,12 +25,19 @@ use app::*;
 )]
 mod hot_app {
     use gfx_sizes::{ARGB};
-    use platform_types::{SFX};
+    use platform_types::{Speaker};
     pub use app::State;
     hot_functions_from_file!("libs/app/src/app.rs");
 }
 
 pub fn run(mut state: app::State) {
+    let mut recorder = record::Recorder::start(&state);
+
+    // Recordings need to start from a fresh state, to be replayable.
+    if !recorder.is_recording() {
+        save::load(&mut state);
+    }
+
     let event_loop = EventLoop::new();
 
     let builder = WindowBuilder::new()
@@ -55,7 +62,18 @@ pub fn run(mut state: app::State) {
 
     let mut graphics_context = unsafe { GraphicsContext::new(window) }.unwrap();
 
-    let mut sound_handler = init_sound_handler();
+    let mut sound_handler = init_sound_handler(state.sounds.clone(), state.music.clone());
+
+    let key_bindings = bindings::load(&state);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let mut gamepads = gilrs::Gilrs::new()
+        .map_err(|e| {
+            if let Some(logger) = state.params.error_logger {
+                logger(&format!("Gamepads are not available: {e}"));
+            }
+        })
+        .ok();
 
     #[cfg(not(target_arch = "wasm32"))]
     let mut loop_helper = spin_sleep::LoopHelper::builder()
@@ -63,6 +81,20 @@ pub fn run(mut state: app::State) {
 
     let mut just_gained_focus = true;
 
+    // The canvas pixel that the mouse is over, and whether its button is down.
+    #[cfg(target_arch = "wasm32")]
+    let mut pointer: (Option<(clip::X, clip::Y)>, bool) = (None, false);
+
+    // The finger that is acting as the pointer, if any. Other fingers are ignored
+    // until it is lifted, so that a second one cannot take the pointer over.
+    #[cfg(target_arch = "wasm32")]
+    let mut touch_id: Option<u64> = None;
+
+    let mut frames_since_save = 0;
+
+    // Reloading partway through a recording would make it impossible to replay.
+    let mut pak_watcher = watch::PakWatcher::start(!recorder.is_recording());
+
     event_loop.run(move |event, _, control_flow| {
         let window = graphics_context.window();
 
@@ -70,45 +102,102 @@ pub fn run(mut state: app::State) {
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
                 window_id,
-            } if window_id == window.id() => *control_flow = ControlFlow::Exit,
+            } if window_id == window.id() => {
+                save::save(&state);
+                recorder.write(&state);
+                *control_flow = ControlFlow::Exit
+            },
             Event::WindowEvent {
                 event: WindowEvent::KeyboardInput{
                     input: winit::event::KeyboardInput {
                         state: element_state,
-                        virtual_keycode: Some(keycode),
+                        scancode,
+                        virtual_keycode,
                         ..
                     },
                     ..
                 },
                 window_id,
             } if window_id == window.id() => {
-                use winit::event::{ElementState, VirtualKeyCode as VK};
-                use platform_types::Button;
+                use winit::event::ElementState;
 
-                let button = match keycode {
-                    VK::Return => Button::START,
-                    VK::RShift => Button::SELECT,
-                    VK::Up => Button::UP,
-                    VK::Left => Button::LEFT,
-                    VK::Right => Button::RIGHT,
-                    VK::Down => Button::DOWN,
+                #[cfg(not(target_arch = "wasm32"))]
+                if virtual_keycode == Some(winit::event::VirtualKeyCode::F12) {
+                    if element_state == ElementState::Pressed {
+                        screenshot::save(&state, &output_frame_buffer);
+                    }
+                    return
+                }
 
-                    VK::Z => Button::A,
-                    VK::X => Button::B,
+                let keys = virtual_keycode.map(bindings::Key::Virtual)
+                    .into_iter()
+                    .chain(std::iter::once(bindings::Key::Scancode(scancode)));
 
-                    // For those using the Dvorak layout.
-                    VK::Semicolon => Button::A,
-                    VK::Q => Button::B,
+                for key in keys {
+                    for button in key_bindings.buttons(&key) {
+                        match element_state {
+                            ElementState::Pressed => recorder.press(&mut state, button),
+                            ElementState::Released => recorder.release(&mut state, button),
+                        }
+                    }
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                window_id,
+            } if window_id == window.id() => {
+                pointer.0 = wasm::canvas_xy(position, window.scale_factor());
+
+                if pointer.1 {
+                    wasm::send_pointer(&mut state, &output_frame_buffer, pointer.0);
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput {
+                    state: element_state,
+                    button: winit::event::MouseButton::Left,
+                    ..
+                },
+                window_id,
+            } if window_id == window.id() => {
+                pointer.1 = element_state == winit::event::ElementState::Pressed;
 
-                    #[cfg(feature = "refresh")]
-                    VK::F5 => Button::RESET,
+                wasm::send_pointer(
+                    &mut state,
+                    &output_frame_buffer,
+                    if pointer.1 { pointer.0 } else { None },
+                );
+            }
+            #[cfg(target_arch = "wasm32")]
+            Event::WindowEvent {
+                event: WindowEvent::Touch(winit::event::Touch { phase, location, id, .. }),
+                window_id,
+            } if window_id == window.id() => {
+                use winit::event::TouchPhase;
 
-                    _ => return,
+                // `None` for touches by fingers other than the one acting as the pointer.
+                let xy = match phase {
+                    TouchPhase::Started if touch_id.is_none() => {
+                        touch_id = Some(id);
+
+                        Some(wasm::canvas_xy(location, window.scale_factor()))
+                    },
+                    TouchPhase::Moved if touch_id == Some(id) => {
+                        Some(wasm::canvas_xy(location, window.scale_factor()))
+                    },
+                    TouchPhase::Ended
+                    | TouchPhase::Cancelled if touch_id == Some(id) => {
+                        touch_id = None;
+
+                        Some(None)
+                    },
+                    _ => None,
                 };
 
-                match element_state {
-                    ElementState::Pressed => app::press(&mut state, button),
-                    ElementState::Released => app::release(&mut state, button),
+                if let Some(xy) = xy {
+                    wasm::send_pointer(&mut state, &output_frame_buffer, xy);
                 }
             }
             Event::WindowEvent {
@@ -117,11 +206,56 @@ pub fn run(mut state: app::State) {
             } if window_id == window.id() => {
                 just_gained_focus = true;
             }
+            Event::WindowEvent {
+                event: WindowEvent::Focused(false),
+                window_id,
+            } if window_id == window.id() => {
+                // On the web there's no reliable close event, so this is our best
+                // chance to save before the tab goes away.
+                save::save(&state);
+                frames_since_save = 0;
+            }
             Event::MainEventsCleared => {
+                if pak_watcher.changed() {
+                    if let Some(logger) = state.params.logger {
+                        logger("Pak changed, reloading.");
+                    }
+
+                    // Errors in the new pak end up in the error screen, so there's
+                    // nothing else to handle here. The save of the game that was
+                    // being played is left alone until something is done in the new
+                    // one. Saves are checked against the pak's hash, so if the new
+                    // pak is different, the old save will not be loaded with it.
+                    state = app::reload(&state);
+                    set_sounds(&mut sound_handler, state.sounds.clone(), state.music.clone());
+                    // Make sure the new state gets shown, even if nothing else changes.
+                    just_gained_focus = true;
+                }
+
+                #[cfg(not(target_arch = "wasm32"))]
+                if let Some(gamepads) = &mut gamepads {
+                    while let Some(gilrs::Event { event, .. }) = gamepads.next_event() {
+                        let (pad_button, is_pressed) = match event {
+                            gilrs::EventType::ButtonPressed(pad_button, _) => (pad_button, true),
+                            gilrs::EventType::ButtonReleased(pad_button, _) => (pad_button, false),
+                            _ => continue,
+                        };
+
+                        for button in key_bindings.buttons(&bindings::Key::Pad(pad_button)) {
+                            if is_pressed {
+                                recorder.press(&mut state, button);
+                            } else {
+                                recorder.release(&mut state, button);
+                            }
+                        }
+                    }
+                }
+
                 // `frame` comes from the `app` crate.
-                let (commands, gfx, sounds) = frame(&mut state);
+                let (commands, spritesheets, speaker) = frame(&mut state);
+                recorder.end_frame();
 
-                handle_sounds(&mut sound_handler, sounds);
+                handle_sounds(&mut sound_handler, speaker);
 
                 {
                     let size = window.inner_size();
@@ -132,7 +266,7 @@ pub fn run(mut state: app::State) {
                 let needs_redraw = render::render(
                     &mut output_frame_buffer,
                     commands,
-                    gfx,
+                    spritesheets,
                 );
 
                 if NeedsRedraw::Yes == needs_redraw
@@ -146,6 +280,14 @@ pub fn run(mut state: app::State) {
 
                 just_gained_focus = false;
 
+                frames_since_save += 1;
+                if frames_since_save >= save::AUTOSAVE_INTERVAL_FRAMES {
+                    save::save(&state);
+                    // Write recordings as we go too, so they survive a crash.
+                    recorder.write(&state);
+                    frames_since_save = 0;
+                }
+
                 #[cfg(not(target_arch = "wasm32"))]
                 {
                     loop_helper.loop_sleep();
@@ -165,7 +307,8 @@ mod wasm {
     };
     use wasm_bindgen::JsCast;
     use web_sys::HtmlCanvasElement;
-    use platform_types::{SFX};
+    use platform_types::{music, sound, Speaker, SFX};
+    use render::{clip, FrameBuffer};
 
     pub fn set_canvas(builder: WindowBuilder) -> WindowBuilder {
         let canvas = get_canvas();
@@ -192,6 +335,49 @@ mod wasm {
         style.remove_property("height").unwrap();
     }
 
+    /// Takes a pointer position from winit to the pixel of the canvas that it is
+    /// over. The page stretches the canvas to fit, so these are not the same.
+    pub fn canvas_xy(
+        position: winit::dpi::PhysicalPosition<f64>,
+        scale_factor: f64,
+    ) -> Option<(clip::X, clip::Y)> {
+        let canvas = get_canvas();
+
+        let (client_w, client_h) = (canvas.client_width(), canvas.client_height());
+        if client_w <= 0 || client_h <= 0 {
+            return None
+        }
+
+        let css = position.to_logical::<f64>(scale_factor);
+
+        let x = css.x * f64::from(canvas.width()) / f64::from(client_w);
+        let y = css.y * f64::from(canvas.height()) / f64::from(client_h);
+
+        if x < 0. || y < 0. {
+            return None
+        }
+
+        Some((x as clip::X, y as clip::Y))
+    }
+
+    /// Passes the pointer along to the app, with `None` meaning it is not down
+    /// anywhere on the screen.
+    pub fn send_pointer(
+        state: &mut app::State,
+        frame_buffer: &FrameBuffer,
+        canvas_xy: Option<(clip::X, clip::Y)>,
+    ) {
+        let xy = canvas_xy.and_then(|xy| render::unscaled_xy(
+            (frame_buffer.width, frame_buffer.height),
+            xy,
+        ));
+
+        match xy {
+            Some(xy) => app::pointer_down(state, xy),
+            None => app::pointer_up(state),
+        }
+    }
+
     fn get_canvas() -> HtmlCanvasElement {
         let window = web_sys::window().unwrap();
         let document = window.document().unwrap();
@@ -205,12 +391,45 @@ mod wasm {
 
     pub type SoundHandler = ();
 
-    pub fn init_sound_handler() -> SoundHandler {
-        ()
+    // The page plays the sounds, so the ones from the pak are handed over to it, to
+    // be played by name. The page has no music yet.
+    pub fn init_sound_handler(banks: sound::Banks, tracks: music::Tracks) -> SoundHandler {
+        set_sounds(&mut (), banks, tracks)
+    }
+
+    pub(super) fn set_sounds(_: &mut SoundHandler, banks: sound::Banks, _: music::Tracks) {
+        fn inner(banks: &sound::Banks) -> Option<()> {
+            use js_sys::{Array, Function, Object, Reflect, Uint8Array};
+            use wasm_bindgen::JsValue;
+
+            let window = web_sys::window()?;
+
+            let handler = Reflect::get(
+                &window,
+                &JsValue::from_str("setSounds")
+            ).ok()?.dyn_into::<Function>().ok()?;
+
+            let js_banks = Object::new();
+
+            for bank in &banks.0 {
+                let variants: Array = bank.variants.iter()
+                    .map(|data| Uint8Array::from(&data[..]))
+                    .collect();
+
+                Reflect::set(&js_banks, &JsValue::from_str(&bank.name), &variants).ok()?;
+            }
+
+            handler.call1(&JsValue::undefined(), &js_banks).ok()?;
+
+            Some(())
+        }
+
+        // Sound is inessential, so ignore errors.
+        let _ = inner(&banks);
     }
 
-    pub(super) fn handle_sounds(_: &mut SoundHandler, requests: &[SFX]) {
-        fn inner(request: SFX) -> Option<()> {
+    pub(super) fn handle_sounds(_: &mut SoundHandler, speaker: &Speaker) {
+        fn inner(request: &SFX) -> Option<()> {
             use js_sys::{Function, Reflect};
             use wasm_bindgen::JsValue;
 
@@ -221,18 +440,12 @@ mod wasm {
                 &JsValue::from_str("soundHandler")
             ).ok()?.dyn_into::<Function>().ok()?;
 
-            let request_string = match request {
-                SFX::CardPlace => "cardPlace",
-                SFX::CardSlide => "cardSlide",
-                SFX::ButtonPress => "buttonPress",
-            };
-
-            handler.call1(&JsValue::undefined(), &request_string.into()).ok()?;
+            handler.call1(&JsValue::undefined(), &request.name().into()).ok()?;
 
             Some(())
         }
 
-        for &request in requests {
+        for request in speaker.slice() {
             // Sound is inessential, so ignore errors.
             let _ = inner(request);
         }
@@ -262,7 +475,7 @@ pub fn get_state_params() -> StateParams {
         seed,
         logger: Some(logger),
         error_logger: Some(error_logger),
-        pak_loader: None,
+        pak_loader: Some(watch::pak_loader),
     }
 }
 
@@ -276,14 +489,15 @@ pub fn get_state_params() -> StateParams {
         eprintln!("{}", s);
     }
 
-    fn pak_loader() -> Option<Box<dyn PakReader>> {
-        let mut args = std::env::args();
-        args.next(); // exe name
-    
-        let override_config: Option<Box<dyn PakReader>> = args.next().and_then(
-            |file_name| -> Option<Box<dyn PakReader>> {
-                match std::fs::File::open(file_name) {
-                    Ok(file) => Some(Box::new(file)),
+    fn pak_loader() -> Option<PakSource> {
+        let override_config: Option<PakSource> = pak_path().and_then(
+            |path| -> Option<PakSource> {
+                if path.is_dir() {
+                    return Some(PakSource::Dir(path))
+                }
+
+                match std::fs::File::open(path) {
+                    Ok(file) => Some(PakSource::Packed(Box::new(file))),
                     Err(_) => None
                 }
             }
@@ -299,6 +513,15 @@ pub fn get_state_params() -> StateParams {
     }
 }
 
+/// The pak passed on the command line, if any.
+#[cfg(not(target_arch = "wasm32"))]
+fn pak_path() -> Option<std::path::PathBuf> {
+    let mut args = std::env::args_os();
+    args.next(); // exe name
+
+    args.next().map(std::path::PathBuf::from)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 fn new_seed() -> xs::Seed {
     let time = std::time::SystemTime::now()
@@ -311,95 +534,772 @@ fn new_seed() -> xs::Seed {
     }
 }
 
+mod bindings {
+    //! The keys, and gamepad buttons, that press each `Button`. On Desktop, these can
+    //! be changed with a file, in the format described in `platform_types::bindings`.
+    use platform_types::Button;
+    use winit::event::VirtualKeyCode as VK;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Key {
+        Virtual(VK),
+        /// The physical position of a key, regardless of the keyboard layout.
+        Scancode(u32),
+        #[cfg(not(target_arch = "wasm32"))]
+        Pad(gilrs::Button),
+    }
+
+    pub type Bindings = platform_types::bindings::Bindings<Key>;
+
+    fn defaults() -> Bindings {
+        use Key::*;
+
+        Bindings::new(vec![
+            (Virtual(VK::Return), Button::START),
+            (Virtual(VK::RShift), Button::SELECT),
+            (Virtual(VK::Up), Button::UP),
+            (Virtual(VK::Left), Button::LEFT),
+            (Virtual(VK::Right), Button::RIGHT),
+            (Virtual(VK::Down), Button::DOWN),
+
+            (Virtual(VK::Z), Button::A),
+            (Virtual(VK::X), Button::B),
+
+            // For those using the Dvorak layout.
+            (Virtual(VK::Semicolon), Button::A),
+            (Virtual(VK::Q), Button::B),
+
+            #[cfg(feature = "refresh")]
+            (Virtual(VK::F5), Button::RESET),
+
+            // These follow where the buttons are on an NES controller.
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::East), Button::A),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::South), Button::B),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::Select), Button::SELECT),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::Start), Button::START),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::DPadUp), Button::UP),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::DPadDown), Button::DOWN),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::DPadLeft), Button::LEFT),
+            #[cfg(not(target_arch = "wasm32"))]
+            (Pad(gilrs::Button::DPadRight), Button::RIGHT),
+        ])
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn load(_: &app::State) -> Bindings {
+        defaults()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    const BINDINGS_PATH: &str = "ripe-keys.txt";
+
+    /// Falls back to the defaults if the file has any problems, since a game with
+    /// no way to press anything would not be much use.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load(state: &app::State) -> Bindings {
+        let mut bindings = defaults();
+
+        let text = match std::fs::read_to_string(BINDINGS_PATH) {
+            Ok(text) => text,
+            // No file, so the defaults are wanted.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return bindings,
+            Err(e) => {
+                error_log(state, &format!("Could not read {BINDINGS_PATH}: {e}"));
+                return bindings
+            },
+        };
+
+        if let Err(e) = bindings.apply(&text, parse_key) {
+            error_log(state, &format!("Could not use {BINDINGS_PATH}, using the default keys instead: {e}"));
+        }
+
+        bindings
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn error_log(state: &app::State, s: &str) {
+        if let Some(logger) = state.params.error_logger {
+            logger(s);
+        }
+    }
+
+    /// Keys are written as their name, like `Z` or `Return`, or as `Scancode<number>`
+    /// for the physical key with that scancode. Gamepad buttons are written as `Pad`
+    /// followed by the button's name, like `PadSouth` or `PadDPadUp`.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn parse_key(name: &str) -> Option<Key> {
+        if let Some(scancode) = name.strip_prefix("Scancode") {
+            return scancode.parse().ok().map(Key::Scancode)
+        }
+
+        if let Some(pad_name) = name.strip_prefix("Pad") {
+            return PAD_BUTTON_NAMES.iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(pad_name))
+                .map(|&(_, pad_button)| Key::Pad(pad_button))
+        }
+
+        KEY_NAMES.iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, keycode)| Key::Virtual(keycode))
+    }
+
+    macro_rules! names {
+        ($const_name: ident : $type: ty = $($name: ident),+ $(,)?) => {
+            #[cfg(not(target_arch = "wasm32"))]
+            const $const_name: &[(&str, $type)] = &[
+                $( (stringify!($name), <$type>::$name), )+
+            ];
+        }
+    }
+
+    // F12 is left out, since it takes screenshots.
+    names!{
+        KEY_NAMES: VK =
+        Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+        A, B, C, D, E, F, G, H, I, J, K, L, M,
+        N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+        Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11,
+        Insert, Home, Delete, End, PageDown, PageUp,
+        Left, Up, Right, Down,
+        Back, Return, Space, Tab,
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4,
+        Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+        NumpadAdd, NumpadDivide, NumpadDecimal, NumpadEnter, NumpadMultiply, NumpadSubtract,
+        Apostrophe, Backslash, Comma, Equals, Grave, LBracket, Minus, Period, RBracket, Semicolon, Slash,
+        LAlt, LControl, LShift, RAlt, RControl, RShift,
+    }
+
+    names!{
+        PAD_BUTTON_NAMES: gilrs::Button =
+        South, East, North, West, C, Z,
+        LeftTrigger, LeftTrigger2, RightTrigger, RightTrigger2,
+        Select, Start, Mode, LeftThumb, RightThumb,
+        DPadUp, DPadDown, DPadLeft, DPadRight,
+    }
+}
+
+mod save {
+    /// About once a minute, at 60 frames per second.
+    pub const AUTOSAVE_INTERVAL_FRAMES: u32 = 60 * 60;
+
+    pub fn save(state: &app::State) {
+        match app::save(state) {
+            // Nothing to save, say because we are showing an error.
+            None => {},
+            Some(Ok(bytes)) => {
+                if let Err(e) = write(&bytes) {
+                    error_log(state, &format!("Could not write save: {e}"));
+                }
+            },
+            Some(Err(e)) => {
+                error_log(state, &format!("{e}"));
+            },
+        }
+    }
+
+    pub fn load(state: &mut app::State) {
+        let bytes = match read() {
+            // No save yet, so we're starting a new game.
+            Ok(None) => return,
+            Ok(Some(bytes)) => bytes,
+            Err(e) => {
+                error_log(state, &format!("Could not read save: {e}"));
+                return
+            },
+        };
+
+        if let Err(e) = app::load(state, &bytes) {
+            // Leave the previous save alone, in case the player wants to recover it.
+            error_log(state, &format!("Could not load save, starting a new game instead: {e}"));
+        }
+    }
+
+    fn error_log(state: &app::State, s: &str) {
+        if let Some(logger) = state.params.error_logger {
+            logger(s);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    const SAVE_PATH: &str = "ripe.save";
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn write(bytes: &[u8]) -> Result<(), String> {
+        // Write to a temporary file first, so that quitting partway through
+        // cannot leave a half-written save behind.
+        let temp_path = format!("{SAVE_PATH}.tmp");
+
+        std::fs::write(&temp_path, bytes)
+            .and_then(|()| std::fs::rename(&temp_path, SAVE_PATH))
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read() -> Result<Option<Vec<u8>>, String> {
+        match std::fs::read(SAVE_PATH) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    const STORAGE_KEY: &str = "ripe.save";
+
+    #[cfg(target_arch = "wasm32")]
+    fn write(bytes: &[u8]) -> Result<(), String> {
+        super::web_storage::write(STORAGE_KEY, bytes)
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read() -> Result<Option<Vec<u8>>, String> {
+        super::web_storage::read(STORAGE_KEY)
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
-use wasm::{init_sound_handler, handle_sounds};
+mod web_storage {
+    //! Bytes kept in the browser's localStorage, which survive reloading the page.
+
+    fn storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or("No window")?
+            .local_storage()
+            .map_err(|e| format!("{e:?}"))?
+            .ok_or_else(|| "No localStorage".to_string())
+    }
+
+    // localStorage only holds strings, so we store the bytes as hex.
+    pub fn write(key: &str, bytes: &[u8]) -> Result<(), String> {
+        use std::fmt::Write;
+
+        let mut hex = String::with_capacity(bytes.len() * 2);
+        for byte in bytes {
+            let _ = write!(hex, "{byte:02x}");
+        }
+
+        storage()?
+            .set_item(key, &hex)
+            .map_err(|e| format!("{e:?}"))
+    }
+
+    pub fn read(key: &str) -> Result<Option<Vec<u8>>, String> {
+        let Some(hex) = storage()?
+            .get_item(key)
+            .map_err(|e| format!("{e:?}"))? else {
+            return Ok(None)
+        };
+
+        if hex.len() % 2 != 0 {
+            return Err(format!("Stored {key} has an odd length"));
+        }
+
+        (0..hex.len())
+            .step_by(2)
+            .map(|i|
+                hex.get(i..i + 2)
+                    .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                    .ok_or_else(|| format!("Stored {key} is not hex at index {i}"))
+            )
+            .collect::<Result<Vec<u8>, String>>()
+            .map(Some)
+    }
+
+    pub fn remove(key: &str) -> Result<(), String> {
+        storage()?
+            .remove_item(key)
+            .map_err(|e| format!("{e:?}"))
+    }
+}
 
 #[cfg(not(target_arch = "wasm32"))]
-use not_wasm::{init_sound_handler, handle_sounds};
+mod record {
+    use app::replay::Recording;
+    use platform_types::Button;
+
+    /// Set this environment variable to a path to record all input to that path.
+    const RECORD_ENV_VAR: &str = "RIPE_RECORD";
+
+    pub struct Recorder(Option<(std::path::PathBuf, Recording)>);
+
+    impl Recorder {
+        pub fn start(state: &app::State) -> Self {
+            Self(
+                std::env::var_os(RECORD_ENV_VAR)
+                    .map(|path| (path.into(), Recording::new(state)))
+            )
+        }
+
+        pub fn is_recording(&self) -> bool {
+            self.0.is_some()
+        }
+
+        pub fn press(&mut self, state: &mut app::State, button: Button) {
+            match &mut self.0 {
+                Some((_, recording)) => recording.press(state, button),
+                None => app::press(state, button),
+            }
+        }
+
+        pub fn release(&mut self, state: &mut app::State, button: Button) {
+            match &mut self.0 {
+                Some((_, recording)) => recording.release(state, button),
+                None => app::release(state, button),
+            }
+        }
+
+        pub fn end_frame(&mut self) {
+            if let Some((_, recording)) = &mut self.0 {
+                recording.end_frame();
+            }
+        }
+
+        pub fn write(&self, state: &app::State) {
+            let Some((path, recording)) = &self.0 else { return };
+
+            if let Err(e) = std::fs::write(path, recording.to_bytes()) {
+                if let Some(logger) = state.params.error_logger {
+                    logger(&format!("Could not write recording to {}: {e}", path.display()));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod record {
+    use platform_types::Button;
+
+    // There's no convenient place to put a recording in the browser, so this does
+    // nothing but pass the input along.
+    pub struct Recorder;
+
+    impl Recorder {
+        pub fn start(_: &app::State) -> Self {
+            Self
+        }
+
+        pub fn is_recording(&self) -> bool {
+            false
+        }
+
+        pub fn press(&mut self, state: &mut app::State, button: Button) {
+            app::press(state, button)
+        }
+
+        pub fn release(&mut self, state: &mut app::State, button: Button) {
+            app::release(state, button)
+        }
+
+        pub fn end_frame(&mut self) {}
+
+        pub fn write(&self, _: &app::State) {}
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod watch {
+    //! Watching the pak passed on the command line, so that changes to it show up
+    //! without restarting.
+    use std::{
+        path::{Path, PathBuf},
+        time::SystemTime,
+    };
+
+    /// About twice a second, at 60 frames per second.
+    const CHECK_INTERVAL_FRAMES: u32 = 30;
+
+    struct Watched {
+        path: PathBuf,
+        last_modified: Option<SystemTime>,
+        // A tool might still be writing the pak when we first notice a change, so we
+        // wait until it has stayed the same for a whole check interval.
+        pending: Option<Option<SystemTime>>,
+        frames_since_check: u32,
+    }
+
+    pub struct PakWatcher(Option<Watched>);
+
+    impl PakWatcher {
+        pub fn start(enabled: bool) -> Self {
+            Self(
+                super::pak_path()
+                    .filter(|_| enabled)
+                    .map(|path| Watched {
+                        last_modified: last_modified(&path),
+                        path,
+                        pending: None,
+                        frames_since_check: 0,
+                    })
+            )
+        }
+
+        /// Returns true once for each change to the pak, after it has settled.
+        pub fn changed(&mut self) -> bool {
+            let Some(watched) = &mut self.0 else { return false };
+
+            watched.frames_since_check += 1;
+            if watched.frames_since_check < CHECK_INTERVAL_FRAMES {
+                return false
+            }
+            watched.frames_since_check = 0;
+
+            let modified = last_modified(&watched.path);
+
+            if modified == watched.last_modified {
+                watched.pending = None;
+                return false
+            }
+
+            if watched.pending != Some(modified) {
+                watched.pending = Some(modified);
+                return false
+            }
+
+            watched.last_modified = modified;
+            watched.pending = None;
+
+            true
+        }
+    }
+
+    /// For directories, this is the latest time that anything inside was modified.
+    fn last_modified(path: &Path) -> Option<SystemTime> {
+        let metadata = std::fs::metadata(path).ok()?;
+
+        if !metadata.is_dir() {
+            return metadata.modified().ok()
+        }
+
+        std::fs::read_dir(path).ok()?
+            .filter_map(Result::ok)
+            .filter_map(|entry| last_modified(&entry.path()))
+            .max()
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod watch {
+    //! Paks picked by the player on the page. The page hands over the bytes of a
+    //! pak by setting `window.pendingPak` to a `Uint8Array`, with an empty one
+    //! meaning to go back to the default pak. The last pak is remembered in
+    //! localStorage, so it is still there after reloading the page.
+    use std::cell::RefCell;
+    use platform_types::PakSource;
+    use wasm_bindgen::{JsCast, JsValue};
+
+    const PENDING_PAK_KEY: &str = "pendingPak";
+
+    const STORAGE_KEY: &str = "ripe.pak";
+
+    thread_local! {
+        // localStorage has a size limit, which large paks can go over, so we keep
+        // the current pak here as well.
+        static CURRENT_PAK: RefCell<Option<Vec<u8>>> = RefCell::new(None);
+    }
+
+    pub fn pak_loader() -> Option<PakSource> {
+        let bytes = CURRENT_PAK.with(|current| current.borrow().clone())
+            .or_else(|| super::web_storage::read(STORAGE_KEY).ok().flatten())?;
+
+        Some(PakSource::Packed(Box::new(std::io::Cursor::new(bytes))))
+    }
+
+    pub struct PakWatcher;
+
+    impl PakWatcher {
+        pub fn start(_: bool) -> Self {
+            Self
+        }
+
+        /// Returns true once for each pak the player picks.
+        pub fn changed(&mut self) -> bool {
+            let Some(bytes) = take_pending_pak() else { return false };
+
+            let stored = if bytes.is_empty() {
+                CURRENT_PAK.with(|current| *current.borrow_mut() = None);
+                super::web_storage::remove(STORAGE_KEY)
+            } else {
+                let stored = super::web_storage::write(STORAGE_KEY, &bytes);
+                CURRENT_PAK.with(|current| *current.borrow_mut() = Some(bytes));
+                stored
+            };
+
+            if let Err(e) = stored {
+                web_sys::console::error_1(
+                    &format!("Could not remember the pak for next time: {e}").into()
+                );
+            }
+
+            true
+        }
+    }
+
+    fn take_pending_pak() -> Option<Vec<u8>> {
+        use js_sys::{Reflect, Uint8Array};
+
+        let window = web_sys::window()?;
+        let key = JsValue::from_str(PENDING_PAK_KEY);
+
+        let pending = Reflect::get(&window, &key).ok()?;
+        if pending.is_undefined() || pending.is_null() {
+            return None
+        }
+
+        let _ = Reflect::delete_property(&window, &key);
+
+        Some(pending.dyn_into::<Uint8Array>().ok()?.to_vec())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod screenshot {
+    use render::FrameBuffer;
+
+    /// Writes the last rendered frame to a new file in the current working directory.
+    pub fn save(state: &app::State, frame_buffer: &FrameBuffer) {
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+
+        let path = format!("ripe-screenshot-{millis}.png");
+
+        match super::write_png(path.as_ref(), frame_buffer) {
+            Ok(()) => if let Some(logger) = state.params.logger {
+                logger(&format!("Saved screenshot to {path}"));
+            },
+            Err(e) => if let Some(logger) = state.params.error_logger {
+                logger(&e);
+            },
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_png(path: &std::path::Path, frame_buffer: &render::FrameBuffer) -> Result<(), String> {
+    let bytes = headless::to_png(frame_buffer)
+        .map_err(|e| format!("Could not encode {}: {e}", path.display()))?;
+
+    std::fs::write(path, bytes)
+        .map_err(|e| format!("Could not write {}: {e}", path.display()))
+}
+
+/// How many frames `headless` runs for. If this is not set, it runs until the last
+/// event in the `HEADLESS_SCRIPT_ENV_VAR` script, or for one frame without a script.
+#[cfg(not(target_arch = "wasm32"))]
+const HEADLESS_FRAMES_ENV_VAR: &str = "RIPE_HEADLESS_FRAMES";
+
+/// A path to a script of button presses and releases, for `headless` to follow.
+/// See `headless::script` for the format.
+#[cfg(not(target_arch = "wasm32"))]
+const HEADLESS_SCRIPT_ENV_VAR: &str = "RIPE_HEADLESS_SCRIPT";
+
+/// Runs the game without opening a window, then writes the last frame to `path` as
+/// a PNG. The number of frames and the input come from environment variables.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn headless(path: &std::path::Path, params: StateParams) -> Result<(), String> {
+    let events = match std::env::var_os(HEADLESS_SCRIPT_ENV_VAR) {
+        None => Vec::new(),
+        Some(script_path) => {
+            let script_path = std::path::Path::new(&script_path);
+
+            let text = std::fs::read_to_string(script_path)
+                .map_err(|e| format!("{}: {e}", script_path.display()))?;
+
+            headless::script::parse(&text)
+                .map_err(|e| format!("{}: {e}", script_path.display()))?
+        },
+    };
+
+    let frame_count = match std::env::var(HEADLESS_FRAMES_ENV_VAR) {
+        Ok(frames) => frames.parse()
+            .map_err(|e| format!("{HEADLESS_FRAMES_ENV_VAR} was \"{frames}\": {e}"))?,
+        // Run just long enough for everything in the script to happen.
+        Err(_) => events.last().map_or(1, |event| event.frame + 1),
+    };
+
+    let mut state = app::State::new(params);
+
+    let frame_buffer = headless::run(&mut state, frame_count, &events);
+
+    write_png(path, &frame_buffer)
+}
+
+/// Runs the recording at `path` without opening a window, and reports how it ended.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn replay(path: &std::path::Path, params: StateParams) -> Result<(), String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+    let recording = app::replay::Recording::from_bytes(&bytes)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+
+    let state = app::replay::run(&recording, params)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+
+    match state.game_state {
+        Ok(_) => Ok(()),
+        Err(error_state) => Err(format!("Replay ended in an error:\n{}", error_state.error)),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+use wasm::{init_sound_handler, set_sounds, handle_sounds};
+
+#[cfg(not(target_arch = "wasm32"))]
+use not_wasm::{init_sound_handler, set_sounds, handle_sounds};
 
 #[cfg(all(
     not(target_arch = "wasm32"),
     feature = "non-web-sound"
 ))]
 mod not_wasm {
-    use platform_types::SFX;
+    use platform_types::{music, music_player::{self, Player}, sound, Speaker};
 
     use rodio::{
         decoder::Decoder,
         OutputStream,
+        Sink,
         Source,
     };
+    use std::collections::HashMap;
     use std::sync::mpsc::{channel, Sender};
+    use xs::Xs;
+
+    enum Message {
+        Sfx(sound::Data),
+        StartTrack(TrackHandle, music::Data),
+        SetVolume(TrackHandle, f32),
+        StopTrack(TrackHandle),
+    }
+
+    type TrackHandle = u32;
+
+    /// Passes what the music player wants along to the sound thread, since that is
+    /// where the output stream lives.
+    struct Backend {
+        sender: Sender<Message>,
+        next_handle: TrackHandle,
+    }
+
+    // Sound is inessential, so these all ignore errors.
+    impl music_player::Backend for Backend {
+        type Handle = TrackHandle;
+
+        fn start(&mut self, track: &music::Track) -> Self::Handle {
+            let handle = self.next_handle;
+            self.next_handle = self.next_handle.wrapping_add(1);
+
+            let _ = self.sender.send(Message::StartTrack(handle, track.data.clone()));
+
+            handle
+        }
+
+        fn set_volume(&mut self, handle: &Self::Handle, volume: f32) {
+            let _ = self.sender.send(Message::SetVolume(*handle, volume));
+        }
+
+        fn stop(&mut self, handle: Self::Handle) {
+            let _ = self.sender.send(Message::StopTrack(handle));
+        }
+    }
 
     pub struct SoundHandler {
-        sender: Sender<SFX>
+        sender: Sender<Message>,
+        banks: sound::Banks,
+        rng: Xs,
+        music: Player<Backend>,
     }
 
-    pub fn init_sound_handler() -> SoundHandler {
-        let (sender, receiver) = channel();
+    pub fn init_sound_handler(banks: sound::Banks, tracks: music::Tracks) -> SoundHandler {
+        let (sender, receiver) = channel::<Message>();
 
         std::thread::spawn(move || {
-            let mut rng = xs::from_seed(super::new_seed());
-
             let output = match OutputStream::try_default() {
                 Ok(output) => output,
                 // No point in leaving this thread running if we can't play sounds.
                 Err(_) => return,
             };
 
-            while let Ok(request) = receiver.recv() {
-                macro_rules! i_b {
-                    ($name: literal) => {
-                        include_bytes!(concat!(
-                            "../../../static/sounds/",
-                            $name,
-                            ".ogg"
-                        ))
-                    }
-                }
-                let sounds: &[&[u8]] = match request {
-                    SFX::CardPlace => &[
-                        i_b!("cardPlace1"),
-                        i_b!("cardPlace2"),
-                        i_b!("cardPlace3"),
-                    ],
-                    SFX::CardSlide => &[
-                        i_b!("cardSlide1"),
-                        i_b!("cardSlide2"),
-                        i_b!("cardSlide3"),
-                    ],
-                    SFX::ButtonPress => &[
-                        i_b!("buttonPress1"),
-                        i_b!("buttonPress2"),
-                        i_b!("buttonPress3"),
-                    ],
-                };
+            let mut sinks = HashMap::new();
+
+            while let Ok(message) = receiver.recv() {
+                match message {
+                    Message::Sfx(data) => {
+                        // If one sound file is messed up, don't break all the sounds.
+                        if let Ok(decoder) = Decoder::new_vorbis(
+                            std::io::Cursor::new(data)
+                        ) {
+                            let _ = output.1.play_raw(
+                                decoder.convert_samples()
+                            );
+                        }
+                    },
+                    Message::StartTrack(handle, data) => {
+                        let Ok(sink) = Sink::try_new(&output.1) else { continue };
+                        let Ok(decoder) = Decoder::new_vorbis(
+                            std::io::Cursor::new(data)
+                        ) else { continue };
 
-                let data: &[u8] = sounds[
-                    xs::index(&mut rng, 0..sounds.len())
-                ];
+                        sink.set_volume(0.0);
+                        sink.append(decoder.repeat_infinite());
 
-                // If one sound file is messed up, don't break all the sounds.
-                if let Ok(decoder) = Decoder::new_vorbis(
-                    std::io::Cursor::new(data)
-                ) {
-                    let _ = output.1.play_raw(
-                        decoder.convert_samples()
-                    );
+                        sinks.insert(handle, sink);
+                    },
+                    Message::SetVolume(handle, volume) => {
+                        if let Some(sink) = sinks.get(&handle) {
+                            sink.set_volume(volume);
+                        }
+                    },
+                    Message::StopTrack(handle) => {
+                        if let Some(sink) = sinks.remove(&handle) {
+                            sink.stop();
+                        }
+                    },
                 }
             }
         });
 
         SoundHandler {
-            sender,
+            sender: sender.clone(),
+            banks,
+            rng: xs::from_seed(super::new_seed()),
+            music: Player::new(
+                Backend {
+                    sender,
+                    next_handle: 0,
+                },
+                tracks,
+            ),
         }
     }
 
-    pub(super) fn handle_sounds(handler: &mut SoundHandler, requests: &[SFX]) {
-        for &request in requests {
+    pub(super) fn set_sounds(handler: &mut SoundHandler, banks: sound::Banks, tracks: music::Tracks) {
+        handler.banks = banks;
+        handler.music.set_tracks(tracks);
+    }
+
+    pub(super) fn handle_sounds(handler: &mut SoundHandler, speaker: &Speaker) {
+        for request in speaker.slice() {
+            // Paks only need to include the sounds they care about.
+            let Some(bank) = handler.banks.get(request.name()) else { continue };
+
+            let variants = bank.variants.slice();
+
+            let data = variants[xs::index(&mut handler.rng, 0..variants.len())].clone();
+
             // Sound is inessential, so ignore errors.
-            let _ = handler.sender.send(request);
+            let _ = handler.sender.send(Message::Sfx(data));
         }
+
+        handler.music.update(speaker.track());
     }
 }
 
@@ -408,15 +1308,17 @@ mod not_wasm {
     not(feature = "non-web-sound")
 ))]
 mod not_wasm {
-    use platform_types::SFX;
+    use platform_types::{music, sound, Speaker};
 
     pub struct SoundHandler;
 
-    pub fn init_sound_handler() -> SoundHandler {
+    pub fn init_sound_handler(_: sound::Banks, _: music::Tracks) -> SoundHandler {
         SoundHandler
     }
 
-    pub(super) fn handle_sounds(_: &mut SoundHandler, _: &[SFX]) {
+    pub(super) fn set_sounds(_: &mut SoundHandler, _: sound::Banks, _: music::Tracks) {}
+
+    pub(super) fn handle_sounds(_: &mut SoundHandler, _: &Speaker) {
         // Sound is disabled
     }
 }
@@ -137,7 +137,7 @@ pub mod command {
 
     #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
     pub struct Command {
-        sprite_xy: sprite::XY<sprite::Renderable>,
+        sprite_xy: sprite::SheetXY,
         rect: Rect,
         colour_override: ARGB,
     }
@@ -145,7 +145,7 @@ pub mod command {
     impl Command {
         /// If this returns None, then there's no useful command to render, because it wouldn't affect any pixels.
         pub fn new(
-            mut sprite_xy: sprite::XY<sprite::Renderable>,
+            mut sprite_xy: sprite::SheetXY,
             rect: unscaled::Rect,
             colour_override: ARGB,
         ) -> Option<Self> {
@@ -210,8 +210,8 @@ pub mod command {
                 return None
             }
 
-            sprite_xy.x += unscaled::W::new(x_min_clip_amount);
-            sprite_xy.y += unscaled::H::new(y_min_clip_amount);
+            sprite_xy.xy.x += unscaled::W::new(x_min_clip_amount);
+            sprite_xy.xy.y += unscaled::H::new(y_min_clip_amount);
 
             Some(Command {
                 rect: clipped,
@@ -221,7 +221,7 @@ pub mod command {
         }
 
         pub fn rect(&self) -> Rect { self.rect }
-        pub fn sprite_xy(&self) -> sprite::XY<sprite::Renderable> { self.sprite_xy }
+        pub fn sprite_xy(&self) -> sprite::SheetXY { self.sprite_xy }
         pub fn colour_override(&self) -> ARGB { self.colour_override }
 
         pub fn clipped_to(&self, clip_rect: unscaled::Rect) -> Option<Self> {
@@ -325,13 +325,16 @@ mod command_new_works {
     #[test]
     fn on_this_found_example() {
         let actual = Command::new(
-            sprite::XY {
-                x: sprite::x(
-                    256,
-                ),
-                y: sprite::y(
-                    432,
-                ),
+            sprite::SheetXY {
+                sheet: 0,
+                xy: sprite::XY {
+                    x: sprite::x(
+                        256,
+                    ),
+                    y: sprite::y(
+                        432,
+                    ),
+                },
             },
             unscaled::Rect {
                 x: unscaled::X(
@@ -358,9 +361,20 @@ mod command_new_works {
 pub struct Input {
     pub gamepad: Button,
     pub previous_gamepad: Button,
+    /// Where the mouse button or finger is held down on the screen, if anywhere.
+    pub pointer: Option<unscaled::XY>,
+    pub previous_pointer: Option<unscaled::XY>,
+    /// Where the pointer was let go since the last frame, if it was let go over the
+    /// same thing it went down on.
+    pub tap: Option<unscaled::XY>,
 }
 
 impl Input {
+    /// Where the pointer was tapped since the last frame, if it was.
+    pub fn tapped_this_frame(&self) -> Option<unscaled::XY> {
+        self.tap
+    }
+
     #[allow(unused)]
     pub fn pressed_this_frame(&self, buttons: Button) -> bool {
         !self.previous_gamepad.contains(buttons) && self.gamepad.contains(buttons)
@@ -400,21 +414,47 @@ impl Input {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub enum SFX {
-    CardPlace,
-    CardSlide,
-    ButtonPress,
+/// A sound effect, identified by the name of a sound bank in the pak's manifest.
+/// If the pak has no bank with that name, nothing is played.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SFX(&'static str);
+
+impl SFX {
+    pub const CARD_PLACE: Self = Self::constant("cardPlace");
+    pub const CARD_SLIDE: Self = Self::constant("cardSlide");
+    pub const BUTTON_PRESS: Self = Self::constant("buttonPress");
+    /// When a switch gets hit.
+    pub const SWITCH: Self = Self::constant("switch");
+    /// When something small gets squashed.
+    pub const SQUASH: Self = Self::constant("squash");
+    /// When the player gets hit, and has to start over.
+    pub const HIT: Self = Self::constant("hit");
+    /// When the player walks into something that does not move.
+    pub const BUMP: Self = Self::constant("bump");
+    /// When something mechanical clicks into place.
+    pub const CLICK: Self = Self::constant("click");
+
+    const fn constant(name: &'static str) -> Self {
+        Self(name)
+    }
+
+    pub fn name(&self) -> &str {
+        self.0
+    }
 }
 
 pub struct Speaker {
     requests: Vec<SFX>,
+    /// The name of the music track in the pak's manifest that should be playing.
+    /// Unlike the sound effects, this stays put from frame to frame.
+    track: Option<String>,
 }
 
 impl Default for Speaker {
     fn default() -> Self {
         Speaker {
             requests: Vec::with_capacity(8),
+            track: None,
         }
     }
 }
@@ -431,6 +471,250 @@ impl Speaker {
     pub fn slice(&self) -> &[SFX] {
         &self.requests
     }
+
+    pub fn request_track(&mut self, track: Option<&str>) {
+        if self.track.as_deref() != track {
+            self.track = track.map(str::to_owned);
+        }
+    }
+
+    pub fn track(&self) -> Option<&str> {
+        self.track.as_deref()
+    }
+}
+
+pub mod music_player {
+    //! Crossfades between looping music tracks, as the requested track changes.
+    //! The actual playing is left to a `Backend`.
+    use pak_types::music;
+
+    /// How many frames it takes for one track to fade out, while the next fades in.
+    pub const CROSSFADE_FRAMES: u16 = 60;
+
+    pub trait Backend {
+        type Handle;
+
+        /// Starts `track` looping, silently.
+        fn start(&mut self, track: &music::Track) -> Self::Handle;
+
+        /// `volume` is between 0.0 and 1.0, inclusive.
+        fn set_volume(&mut self, handle: &Self::Handle, volume: f32);
+
+        fn stop(&mut self, handle: Self::Handle);
+    }
+
+    struct Playing<Handle> {
+        name: String,
+        handle: Handle,
+        /// How far into fading in this track is, out of `CROSSFADE_FRAMES`.
+        frames: u16,
+    }
+
+    impl <Handle> Playing<Handle> {
+        fn volume(&self) -> f32 {
+            f32::from(self.frames) / f32::from(CROSSFADE_FRAMES)
+        }
+    }
+
+    pub struct Player<B: Backend> {
+        pub backend: B,
+        tracks: music::Tracks,
+        current: Option<Playing<B::Handle>>,
+        fading_out: Vec<Playing<B::Handle>>,
+    }
+
+    impl <B: Backend> Player<B> {
+        pub fn new(backend: B, tracks: music::Tracks) -> Self {
+            Self {
+                backend,
+                tracks,
+                current: None,
+                fading_out: Vec::with_capacity(2),
+            }
+        }
+
+        /// Stops whatever is playing, since the new tracks may not include it.
+        pub fn set_tracks(&mut self, tracks: music::Tracks) {
+            for playing in self.current.take().into_iter().chain(self.fading_out.drain(..)) {
+                self.backend.stop(playing.handle);
+            }
+
+            self.tracks = tracks;
+        }
+
+        /// Call this once a frame, with the name of the track that should be playing.
+        pub fn update(&mut self, requested: Option<&str>) {
+            if self.current.as_ref().map(|playing| playing.name.as_str()) != requested {
+                if let Some(previous) = self.current.take() {
+                    self.fading_out.push(previous);
+                }
+
+                if let Some(name) = requested {
+                    // If we are going back to a track that hasn't finished fading out
+                    // yet, fade it back in from where it is, instead of restarting it.
+                    if let Some(i) = self.fading_out.iter().position(|playing| playing.name == name) {
+                        self.current = Some(self.fading_out.swap_remove(i));
+                    } else if let Some(track) = self.tracks.get(name) {
+                        self.current = Some(Playing {
+                            name: name.to_owned(),
+                            handle: self.backend.start(track),
+                            frames: 0,
+                        });
+                    }
+                }
+            }
+
+            if let Some(playing) = &mut self.current {
+                if playing.frames < CROSSFADE_FRAMES {
+                    playing.frames += 1;
+                    self.backend.set_volume(&playing.handle, playing.volume());
+                }
+            }
+
+            let mut i = 0;
+            while i < self.fading_out.len() {
+                let playing = &mut self.fading_out[i];
+                playing.frames = playing.frames.saturating_sub(1);
+
+                if playing.frames == 0 {
+                    let playing = self.fading_out.swap_remove(i);
+                    self.backend.stop(playing.handle);
+                } else {
+                    self.backend.set_volume(&playing.handle, playing.volume());
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// A backend that plays nothing, but keeps track of what would be playing, and
+    /// how loud. Useful when there is no sound device, including in tests.
+    #[derive(Debug, Default)]
+    pub struct Null {
+        next_handle: u32,
+        playing: Vec<(u32, String, f32)>,
+    }
+
+    impl Null {
+        pub fn volume(&self, name: &str) -> Option<f32> {
+            self.playing.iter()
+                .find(|(_, playing_name, _)| playing_name == name)
+                .map(|&(_, _, volume)| volume)
+        }
+
+        pub fn playing_count(&self) -> usize {
+            self.playing.len()
+        }
+    }
+
+    impl Backend for Null {
+        type Handle = u32;
+
+        fn start(&mut self, track: &music::Track) -> Self::Handle {
+            let handle = self.next_handle;
+            self.next_handle = self.next_handle.wrapping_add(1);
+
+            self.playing.push((handle, track.name.clone(), 0.0));
+
+            handle
+        }
+
+        fn set_volume(&mut self, handle: &Self::Handle, volume: f32) {
+            if let Some(playing) = self.playing.iter_mut().find(|(h, ..)| h == handle) {
+                playing.2 = volume;
+            }
+        }
+
+        fn stop(&mut self, handle: Self::Handle) {
+            self.playing.retain(|(h, ..)| *h != handle);
+        }
+    }
+
+    #[cfg(test)]
+    mod player_works {
+        use super::*;
+
+        fn tracks() -> music::Tracks {
+            music::Tracks(
+                ["walking", "hallway"].into_iter()
+                    .map(|name| music::Track {
+                        name: name.to_owned(),
+                        data: music::Data::from(&[][..]),
+                    })
+                    .collect()
+            )
+        }
+
+        fn run(player: &mut Player<Null>, requested: Option<&str>, frames: u16) {
+            for _ in 0..frames {
+                player.update(requested);
+            }
+        }
+
+        #[test]
+        fn when_crossfading_between_tracks() {
+            let mut player = Player::new(Null::default(), tracks());
+
+            run(&mut player, Some("walking"), CROSSFADE_FRAMES);
+
+            assert_eq!(player.backend.volume("walking"), Some(1.0));
+            assert_eq!(player.backend.playing_count(), 1);
+
+            run(&mut player, Some("hallway"), CROSSFADE_FRAMES / 2);
+
+            let walking = player.backend.volume("walking").unwrap();
+            let hallway = player.backend.volume("hallway").unwrap();
+            assert!(walking > 0.0 && walking < 1.0, "{walking}");
+            assert!(hallway > 0.0 && hallway < 1.0, "{hallway}");
+
+            run(&mut player, Some("hallway"), CROSSFADE_FRAMES);
+
+            assert_eq!(player.backend.volume("walking"), None);
+            assert_eq!(player.backend.volume("hallway"), Some(1.0));
+        }
+
+        #[test]
+        fn when_going_back_before_the_fade_out_finishes() {
+            let mut player = Player::new(Null::default(), tracks());
+
+            run(&mut player, Some("walking"), CROSSFADE_FRAMES);
+            run(&mut player, Some("hallway"), 10);
+            run(&mut player, Some("walking"), 1);
+
+            // The track fades back in, instead of starting over.
+            assert_eq!(player.backend.playing_count(), 2);
+            assert!(player.backend.volume("walking").unwrap() > 0.5);
+        }
+
+        #[test]
+        fn when_the_track_is_missing_or_none() {
+            let mut player = Player::new(Null::default(), tracks());
+
+            run(&mut player, Some("walking"), CROSSFADE_FRAMES);
+            run(&mut player, Some("not in the pak"), CROSSFADE_FRAMES);
+
+            assert_eq!(player.backend.playing_count(), 0);
+
+            run(&mut player, Some("walking"), CROSSFADE_FRAMES);
+            run(&mut player, None, CROSSFADE_FRAMES);
+
+            assert_eq!(player.backend.playing_count(), 0);
+        }
+
+        #[test]
+        fn when_the_tracks_are_replaced() {
+            let mut player = Player::new(Null::default(), tracks());
+
+            run(&mut player, Some("walking"), 1);
+            player.set_tracks(<_>::default());
+
+            assert_eq!(player.backend.playing_count(), 0);
+
+            run(&mut player, Some("walking"), 1);
+
+            assert_eq!(player.backend.playing_count(), 0);
+        }
+    }
 }
 
 // These values are deliberately picked to be the same as the ones in NES' input registers.
@@ -493,10 +777,187 @@ pub mod button {
         pub fn remove(&mut self, other: Self) {
             self.0 &= !other.0;
         }
+
+        /// The name used for this button in text, like scripts and key binding files.
+        pub fn name(self) -> &'static str {
+            match self {
+                Self::A => "A",
+                Self::B => "B",
+                Self::SELECT => "SELECT",
+                Self::START => "START",
+                Self::UP => "UP",
+                Self::DOWN => "DOWN",
+                Self::LEFT => "LEFT",
+                Self::RIGHT => "RIGHT",
+                #[cfg(feature = "refresh")]
+                Self::RESET => "RESET",
+                _ => "?",
+            }
+        }
+
+        /// The inverse of `name`, ignoring case.
+        pub fn from_name(name: &str) -> Option<Self> {
+            Self::ALL.into_iter().find(|button| button.name().eq_ignore_ascii_case(name))
+        }
     }
 }
 pub use button::Button;
 
+pub mod bindings {
+    //! Which keys, or other inputs, press which buttons. These can be changed with
+    //! plain text, where each line looks like `<button> <key> <key> ...`, for example
+    //! `A Z Semicolon`. Each button that is mentioned loses its default keys, so a
+    //! line with only a button on it unbinds that button. Blank lines, and everything
+    //! after a `#`, are ignored.
+    use crate::Button;
+
+    #[derive(Debug, PartialEq, Eq)]
+    pub enum Error {
+        UnknownButton { line: usize, got: String },
+        UnknownKey { line: usize, got: String },
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            use Error::*;
+            match self {
+                UnknownButton { line, got } => write!(
+                    f,
+                    "Line {line}: Expected one of {}, got \"{got}\"",
+                    Button::ALL.map(Button::name).join(", ")
+                ),
+                UnknownKey { line, got } => write!(f, "Line {line}: Unknown key \"{got}\""),
+            }
+        }
+    }
+
+    impl std::error::Error for Error {}
+
+    #[derive(Clone, Debug)]
+    pub struct Bindings<Key> {
+        pairs: Vec<(Key, Button)>,
+    }
+
+    impl <Key: PartialEq> Bindings<Key> {
+        pub fn new(pairs: Vec<(Key, Button)>) -> Self {
+            Self { pairs }
+        }
+
+        /// A key can be bound to more than one button.
+        pub fn buttons<'bindings>(&'bindings self, key: &'bindings Key) -> impl Iterator<Item = Button> + 'bindings {
+            self.pairs.iter()
+                .filter(move |(k, _)| k == key)
+                .map(|&(_, button)| button)
+        }
+
+        /// Nothing is changed if there are any errors in `text`.
+        pub fn apply(
+            &mut self,
+            text: &str,
+            parse_key: impl Fn(&str) -> Option<Key>,
+        ) -> Result<(), Error> {
+            let mut replacements: Vec<(Button, Vec<Key>)> = Vec::with_capacity(Button::ALL.len());
+
+            for (i, raw_line) in text.lines().enumerate() {
+                let line = i + 1;
+
+                let content = raw_line.split('#').next().unwrap_or_default();
+
+                let mut fields = content.split_whitespace();
+
+                let Some(name) = fields.next() else {
+                    // Blank line
+                    continue
+                };
+                let button = Button::from_name(name)
+                    .ok_or_else(|| Error::UnknownButton { line, got: name.to_string() })?;
+
+                let index = match replacements.iter().position(|(b, _)| *b == button) {
+                    Some(index) => index,
+                    None => {
+                        replacements.push((button, Vec::new()));
+                        replacements.len() - 1
+                    }
+                };
+
+                for key_name in fields {
+                    let key = parse_key(key_name)
+                        .ok_or_else(|| Error::UnknownKey { line, got: key_name.to_string() })?;
+
+                    replacements[index].1.push(key);
+                }
+            }
+
+            for (button, keys) in replacements {
+                self.pairs.retain(|&(_, b)| b != button);
+                self.pairs.extend(keys.into_iter().map(|key| (key, button)));
+            }
+
+            Ok(())
+        }
+    }
+
+    #[cfg(test)]
+    mod apply_works {
+        use super::*;
+
+        fn parse_key(name: &str) -> Option<char> {
+            let mut chars = name.chars();
+
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => None,
+            }
+        }
+
+        fn defaults() -> Bindings<char> {
+            Bindings::new(vec![
+                ('z', Button::A),
+                ('x', Button::B),
+                ('w', Button::UP),
+            ])
+        }
+
+        #[test]
+        fn on_this_example() {
+            let mut bindings = defaults();
+
+            bindings.apply("
+                # Replaces z, but x and w keep working.
+                a j k
+                start j # Pressing j presses both A and START.
+                a l
+                up
+            ", parse_key).unwrap();
+
+            assert_eq!(bindings.buttons(&'z').count(), 0);
+            assert_eq!(bindings.buttons(&'x').collect::<Vec<_>>(), vec![Button::B]);
+            assert_eq!(bindings.buttons(&'j').collect::<Vec<_>>(), vec![Button::A, Button::START]);
+            assert_eq!(bindings.buttons(&'k').collect::<Vec<_>>(), vec![Button::A]);
+            assert_eq!(bindings.buttons(&'l').collect::<Vec<_>>(), vec![Button::A]);
+            assert_eq!(bindings.buttons(&'w').count(), 0);
+        }
+
+        #[test]
+        fn on_these_bad_lines() {
+            let mut bindings = defaults();
+
+            assert_eq!(
+                bindings.apply("a j\nC k", parse_key),
+                Err(Error::UnknownButton { line: 2, got: "C".to_string() })
+            );
+            assert_eq!(
+                bindings.apply("a jj", parse_key),
+                Err(Error::UnknownKey { line: 1, got: "jj".to_string() })
+            );
+
+            // The good line before the bad one did not get applied.
+            assert_eq!(bindings.buttons(&'z').collect::<Vec<_>>(), vec![Button::A]);
+            assert_eq!(bindings.buttons(&'j').count(), 0);
+        }
+    }
+}
+
 pub type Logger = Option<fn(&str) -> ()>;
 
 pub trait PakReader
@@ -509,7 +970,14 @@ where
     Self: std::io::Read + std::io::Seek
 {}
 
-pub type PakLoader = Option<fn() -> Option<Box<dyn PakReader>>>;
+pub enum PakSource {
+    /// A pak as written by `ripe-pack`.
+    Packed(Box<dyn PakReader>),
+    /// A directory containing a manifest, as would be passed to `ripe-pack`.
+    Dir(std::path::PathBuf),
+}
+
+pub type PakLoader = Option<fn() -> Option<PakSource>>;
 
 #[derive(Clone, Copy)]
 pub struct StateParams {
@@ -2,7 +2,9 @@
 ///! Hexagonal coordinates.
 ///! We follow the q, r, and s naming convention used in https://www.redblobgames.com/grids/hexagons/
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum Dir {
     #[default]
     DecRIncS, // Up
@@ -88,22 +90,22 @@ pub type Distance = u8;
 /// Used as output, Seems safer to be larger, as a result.
 pub type ManhattanDistance = u16;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct Q(pub Inner);
 
 impl Q { pub fn diff(self) -> Diff { self.0 as Diff } }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct R(pub Inner);
 
 impl R { pub fn diff(self) -> Diff { self.0 as Diff } }
 
 #[allow(unused)]
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct S(pub Inner);
 
 /// We can avoid storing `S` by computing it as needed based only on q and r.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct QRS {
     // We put R first so the default sorting layers the hexes from back to front.
     pub r: R,
@@ -163,15 +165,15 @@ impl QRS {
 }
 
 /// A delta in Q space, as opposed to a Q, which is a point.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct QD(pub Inner);
 
 /// A delta in R space, as opposed to an R, which is a point.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct RD(pub Inner);
 
 /// A delta in S space, as opposed to an S, which is a point.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct SD(pub Inner);
 
 macro_rules! shared_d_def {
@@ -326,7 +328,7 @@ shared_d_def!{
 }
 
 /// A delta in QRS space, as opposed to a QRS, which is a point.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct QRSD {
     qd: QD,
     rd: RD,
@@ -387,7 +389,7 @@ impl QRSD {
     }
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct Targeting {
     pub source: QRS,
     pub target: QRS,
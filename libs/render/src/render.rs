@@ -2,6 +2,8 @@
 use gfx_sizes::ARGB;
 use pak_types::{
     sprite,
+    unscaled,
+    Spritesheet,
 };
 use platform_types::{
     Command,
@@ -82,8 +84,9 @@ mod hash {
         u16(hash, x_max.u16());
         u16(hash, y_max.u16());
 
-        u16(hash, sprite_xy.x.into());
-        u16(hash, sprite_xy.y.into());
+        bytes(hash, &[sprite_xy.sheet]);
+        u16(hash, sprite_xy.xy.x.into());
+        u16(hash, sprite_xy.xy.y.into());
 
         bytes(hash, &colour_override.to_ne_bytes());
     }
@@ -186,15 +189,16 @@ mod reset_then_hash_commands_around_a_swap_produces_identical_current_and_prev_c
     fn on_this_one_element_slice() {
         let mut h_c = HashCells::default();
 
-        let mut command = command::Command::default();
-        command.rect = command::Rect::from_unscaled(
+        let command = command::Command::new(
+            sprite::SheetXY::default(),
             unscaled::Rect {
                 x: unscaled::X(0),
                 y: unscaled::Y(0),
-                w: unscaled::W(CELLS_W),
-                h: unscaled::H(CELLS_H),
-            }
-        );
+                w: unscaled::W::new(CELLS_W as unscaled::Inner),
+                h: unscaled::H::new(CELLS_H as unscaled::Inner),
+            },
+            0,
+        ).expect("should be on screen");
 
         let commands = &[command];
 
@@ -989,10 +993,153 @@ mod wide_tests {
     }
 }
 
+/// How the unscaled screen is scaled up and centered within a frame buffer of a
+/// given size, with black bars filling in the rest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Letterbox {
+    multiplier: u16,
+    left_bar_width: clip::W,
+    right_bar_width: clip::W,
+    top_bar_height: clip::H,
+    bottom_bar_height: clip::H,
+}
+
+fn letterbox((width, height): (clip::W, clip::H)) -> Option<Letterbox> {
+    let width_multiplier = width / command::WIDTH;
+    let height_multiplier = height / command::HEIGHT;
+    let multiplier = core::cmp::min(width_multiplier, height_multiplier);
+    if multiplier == 0 {
+        return None;
+    }
+
+    let vertical_bars_width: clip::W = width - (multiplier * command::WIDTH);
+
+    let horizontal_bars_height: clip::H = height - (multiplier * command::HEIGHT);
+
+    Some(Letterbox {
+        multiplier,
+        left_bar_width: (vertical_bars_width + 1) / 2,
+        right_bar_width: vertical_bars_width / 2,
+        top_bar_height: (horizontal_bars_height + 1) / 2,
+        bottom_bar_height: horizontal_bars_height / 2,
+    })
+}
+
+/// Maps a point in a frame buffer of the given size, as passed to `render`, back
+/// to the unscaled screen. Returns `None` if the point is within the black bars,
+/// or outside of the frame buffer entirely.
+pub fn unscaled_xy(
+    (width, height): (clip::W, clip::H),
+    (x, y): (clip::X, clip::Y),
+) -> Option<unscaled::XY> {
+    let Letterbox {
+        multiplier,
+        left_bar_width,
+        top_bar_height,
+        ..
+    } = letterbox((width, height))?;
+
+    let x = (x.checked_sub(left_bar_width)?) / multiplier;
+    let y = (y.checked_sub(top_bar_height)?) / multiplier;
+
+    if x >= command::WIDTH || y >= command::HEIGHT {
+        return None;
+    }
+
+    Some(unscaled::XY {
+        x: unscaled::X(unscaled::Inner::try_from(x).ok()?),
+        y: unscaled::Y(unscaled::Inner::try_from(y).ok()?),
+    })
+}
+
+#[cfg(test)]
+mod unscaled_xy_works {
+    use super::*;
+
+    const SIZE: (clip::W, clip::H) = (command::WIDTH * 2 + 3, command::HEIGHT * 2);
+
+    #[test]
+    fn on_the_top_left_pixel() {
+        assert_eq!(
+            unscaled_xy(SIZE, (2, 0)),
+            Some(unscaled::XY { x: unscaled::X(0), y: unscaled::Y(0) })
+        );
+        assert_eq!(
+            unscaled_xy(SIZE, (3, 1)),
+            Some(unscaled::XY { x: unscaled::X(0), y: unscaled::Y(0) })
+        );
+    }
+
+    #[test]
+    fn on_the_bottom_right_pixel() {
+        assert_eq!(
+            unscaled_xy(SIZE, (2 + command::WIDTH * 2 - 1, command::HEIGHT * 2 - 1)),
+            Some(unscaled::XY {
+                x: unscaled::X(command::WIDTH_SIGNED - 1),
+                y: unscaled::Y(command::HEIGHT_SIGNED - 1),
+            })
+        );
+    }
+
+    #[test]
+    fn on_the_bars() {
+        assert_eq!(unscaled_xy(SIZE, (1, 0)), None);
+        assert_eq!(unscaled_xy(SIZE, (2 + command::WIDTH * 2, 0)), None);
+        assert_eq!(unscaled_xy(SIZE, (2, command::HEIGHT * 2)), None);
+    }
+
+    #[test]
+    fn on_a_too_small_frame_buffer() {
+        assert_eq!(unscaled_xy((command::WIDTH - 1, command::HEIGHT), (0, 0)), None);
+    }
+}
+
+#[cfg(test)]
+mod render_works {
+    use super::*;
+
+    #[test]
+    fn on_a_sprite_that_runs_past_the_end_of_its_spritesheet() {
+        const WHITE: ARGB = 0xFFFF_FFFF;
+
+        let spritesheets = [Spritesheet {
+            cells: vec![WHITE; 4].try_into().expect("should not be empty"),
+            width: 2,
+        }];
+
+        // Only the bottom right pixel of the 2x2 sheet is inside it.
+        let command = Command::new(
+            sprite::SheetXY {
+                sheet: 0,
+                xy: sprite::XY { x: sprite::x(1), y: sprite::y(1) },
+            },
+            unscaled::Rect {
+                x: unscaled::X(0),
+                y: unscaled::Y(0),
+                w: unscaled::W::new(4),
+                h: unscaled::H::new(4),
+            },
+            0,
+        ).expect("should be on screen");
+
+        let mut frame_buffer = FrameBuffer::from_size((command::WIDTH, command::HEIGHT));
+
+        render(&mut frame_buffer, &[command], &spritesheets);
+
+        let width = usize::from(command::WIDTH);
+        let buffer = &frame_buffer.unscaled_buffer;
+
+        assert_eq!(buffer[..4], [WHITE, colours::BLACK, colours::BLACK, colours::BLACK]);
+        for row in 1..4 {
+            assert_eq!(buffer[row * width..row * width + 4], [colours::BLACK; 4], "row {row}");
+        }
+    }
+}
+
 pub fn render(
     frame_buffer: &mut FrameBuffer,
     commands: &[Command],
-    (gfx, gfx_width): (&[ARGB], usize),
+    spritesheets: &[Spritesheet],
 ) -> NeedsRedraw {
     if frame_buffer.width < command::WIDTH
     || frame_buffer.height < command::HEIGHT {
@@ -1000,25 +1147,16 @@ pub fn render(
         frame_buffer.height = command::HEIGHT;
     }
 
-    let width_multiplier = frame_buffer.width / command::WIDTH;
-    let height_multiplier = frame_buffer.height / command::HEIGHT;
-    let multiplier = core::cmp::min(width_multiplier, height_multiplier);
-    if multiplier == 0 {
-        debug_assert!(multiplier != 0);
+    let Some(Letterbox {
+        multiplier,
+        left_bar_width,
+        right_bar_width,
+        top_bar_height,
+        bottom_bar_height,
+    }) = letterbox((frame_buffer.width, frame_buffer.height)) else {
+        debug_assert!(false, "multiplier was 0");
         return NeedsRedraw::No;
-    }
-
-    let vertical_bars_width: clip::W = frame_buffer.width - (multiplier * command::WIDTH);
-
-    let left_bar_width: clip::W = (vertical_bars_width + 1) / 2;
-
-    let right_bar_width: clip::W = vertical_bars_width / 2;
-
-    let horizontal_bars_height: clip::H = frame_buffer.height - (multiplier * command::HEIGHT);
-
-    let top_bar_height: clip::H = (horizontal_bars_height + 1) / 2;
-
-    let bottom_bar_height: clip::H = horizontal_bars_height / 2;
+    };
 
     let outer_clip_rect = clip::Rect {
         x: left_bar_width..(
@@ -1074,7 +1212,15 @@ pub fn render(
     }
 
     for &command in commands.iter() {
-        let sprite::XY { x: sprite_x, y: sprite_y } = command.sprite_xy();
+        let sprite::SheetXY {
+            sheet,
+            xy: sprite::XY { x: sprite_x, y: sprite_y },
+        } = command.sprite_xy();
+        let Some((gfx, gfx_width)) = spritesheets.get(usize::from(sheet))
+            .map(Spritesheet::slice) else {
+            debug_assert!(false, "There is no spritesheet at index {sheet}");
+            continue
+        };
         let colour_override = command.colour_override();
         let rect = command.rect();
 
@@ -1132,18 +1278,31 @@ pub fn render(
                 let base_src_i =
                     (sprite_y + y_iter_count) * src_w
                     + (sprite_x + x_iter_count);
-                debug_assert!(
-                    base_src_i < gfx.len(), 
-                    "({sprite_y} + {y_iter_count}) * {src_w} + ({sprite_x} + {x_iter_count})
-{base_src_i} >= {}
-({x_min} to {x_end}, {y_min} to {y_end})",
-                    gfx.len()
-                );
-                let gfx_colours = unsafe {
-                    wide::load!(
-                        gfx.as_ptr(),
-                        base_src_i
-                    )
+                let gfx_colours = if base_src_i + usize::from(wide::WIDTH) <= gfx.len() {
+                    // SAFETY: We just checked there are enough pixels after
+                    // `base_src_i` to read the whole width from.
+                    unsafe {
+                        wide::load!(
+                            gfx.as_ptr(),
+                            base_src_i
+                        )
+                    }
+                } else {
+                    // The end of the spritesheet, or past it, which a pak can ask
+                    // for. Whatever is missing is drawn as transparent.
+                    let mut padded = [0; wide::WIDTH as usize];
+
+                    let available = gfx.get(base_src_i..).unwrap_or_default();
+                    for (colour, &gfx_colour) in padded.iter_mut().zip(available) {
+                        *colour = gfx_colour;
+                    }
+
+                    // SAFETY: `padded` is the whole width long.
+                    unsafe {
+                        wide::load!(
+                            padded.as_ptr()
+                        )
+                    }
                 };
 
                 let is_full_alpha_mask = wide::eq_mask_u32!(
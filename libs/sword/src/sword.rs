@@ -4,10 +4,13 @@
 ///! S.W.O.R.D.: Staff Whacking Ordeal Required, Duh
 
 use gfx::{Commands, AddDrawCommands};
-use platform_types::{sprite, unscaled, Button, Dir, Input, Speaker};
+use hallway::Hallway;
+use platform_types::{config::SWORDParams, sprite, unscaled, Button, Dir, Input, Speaker, SFX};
 use vec1::{Grid1, Grid1Spec, Vec1, vec1};
 use xs::{Seed, Xs};
 
+use serde::{Deserialize, Serialize};
+
 use std::collections::{BTreeMap};
 use std::num::{NonZeroU8, NonZeroU16};
 
@@ -52,7 +55,7 @@ pub type Index = usize;
 */
 pub type NeighborMask = u8;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TileIndex {
     Wall(NeighborMask),
     Floor,
@@ -101,27 +104,29 @@ pub const LOWER_RIGHT: NeighborFlag = unsafe { NeighborFlag::new_unchecked(1 <<
 */
 
 pub mod xy {
+    use serde::{Deserialize, Serialize};
+
     pub type Inner = u16;
     /// A signed type large enough to hold the difference between two Inner
     /// values.
     pub type Diff = i32;
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct X(pub Inner);
 
     pub fn x(inner: Inner) -> X { X(inner) }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct Y(pub Inner);
 
     pub fn y(inner: Inner) -> Y { Y(inner) }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct W(pub Inner);
 
     pub fn w(inner: Inner) -> W { W(inner) }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct H(pub Inner);
 
     pub fn h(inner: Inner) -> H { H(inner) }
@@ -217,7 +222,7 @@ pub mod xy {
         Y, H
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct XY {
         pub x: X,
         pub y: Y,
@@ -264,7 +269,7 @@ pub mod xy {
         }
     }
 
-    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct WH {
         pub w: W,
         pub h: H,
@@ -345,7 +350,7 @@ fn dir_to(FromTo { from, to }: FromTo<XY>) -> Option<Dir8> {
 
 type SwordTileSpriteInner = u8;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum TileSprite {
     Sword(SwordTileSpriteInner),
     ToggleWall(NeighborMask)
@@ -427,7 +432,7 @@ pub const TILE_REQUIRED: TileFlags = 1 << 0;
 
 // TODO? reclaim the space that was used by the direction flags that we seem to have not ended up using
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Tile {
     pub sprite_index: TileIndex,
     pub flags: TileFlags,
@@ -441,8 +446,9 @@ impl Tile {
 
 mod position {
     use super::XY;
+    use serde::{Deserialize, Serialize};
 
-    #[derive(Clone, Copy, Debug, Default)]
+    #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
     pub struct Position {
         xy: XY,
         offset: offset::XY,
@@ -512,7 +518,7 @@ pub type EntityFlags = u8;
 const GONE: EntityFlags = 1 << 0;
 const RENDER_FACING: EntityFlags = 1 << 1;
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Entity {
     pub tile_sprite: TileSprite,
     pub toggle_group_id: ToggleGroupId,
@@ -520,7 +526,7 @@ pub struct Entity {
     pub facing: Dir8,
 }
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub struct Key {
     pub xy: XY,
 }
@@ -535,13 +541,13 @@ mod mobs {
         pub target_xy: XY,
     }
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     struct Entry {
         offset: offset::XY,
         entity: Entity,
     }
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Mobs {
         entries: BTreeMap<Key, Entry>,
     }
@@ -617,7 +623,7 @@ use mobs::Mobs;
 mod mechanisms {
     use super::*;
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Mechanisms {
         entities: BTreeMap<Key, Entity>,
     }
@@ -648,7 +654,7 @@ use mechanisms::Mechanisms;
 mod switches {
     use super::*;
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Switches {
         entities: BTreeMap<Key, Entity>,
     }
@@ -680,7 +686,7 @@ mod switches {
 }
 use switches::Switches;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
 pub enum Dir8 {
     #[default]
     UpLeft,
@@ -895,14 +901,14 @@ fn can_walk_onto(
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum AnimationKind {
     Reset,
 }
 
 pub type Frames = u16;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Animation {
     pub kind: AnimationKind,
     pub target_key: Key,
@@ -1086,9 +1092,10 @@ fn get_hit_effect(key: Key, switches: &Switches, mobs: &Mobs) -> HitEffect {
     effect
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct State {
     pub seed: Seed, // For restarting
+    pub params: SWORDParams, // Also for restarting
     pub rng: Xs,
     pub player: Entity,
     pub player_position: Position,
@@ -1100,13 +1107,13 @@ pub struct State {
 }
 
 impl State {
-    pub fn new(rng: &mut Xs, specs: &sprite::Specs) -> Self {
+    pub fn new(rng: &mut Xs, specs: &sprite::Specs, params: SWORDParams) -> Self {
         let seed = xs::new_seed(rng);
 
-        Self::init(seed, &specs.wall)
+        Self::init(seed, &specs.wall, params)
     }
 
-    fn init(seed: Seed, wall_spec: &sprite::Spec<sprite::Wall>) -> Self {
+    fn init(seed: Seed, wall_spec: &sprite::Spec<sprite::Wall>, params: SWORDParams) -> Self {
         let mut rng = xs::from_seed(seed);
 
         let mut player = Entity::default();
@@ -1442,7 +1449,7 @@ impl State {
 
             let mut free_group_id = FIRST_GROUP;
 
-            let target_complication_count = 3;
+            let target_complication_count = params.switch_count;
             let mut current_complication_count = 0;
             let max_bad_complication_count = 16;
             let mut bad_complication_attempts_count = 0;
@@ -1768,6 +1775,7 @@ impl State {
 
         Self {
             seed,
+            params,
             rng,
             player,
             player_position,
@@ -1779,8 +1787,8 @@ impl State {
         }
     }
 
-    fn restart(&mut self, wall_spec: &sprite::Spec::<sprite::Wall>) {
-        *self = Self::init(self.seed, wall_spec);
+    fn restart(&mut self, specs: &sprite::Specs) {
+        *self = Self::init(self.seed, &specs.wall, self.params);
     }
 
     pub fn is_complete(&self) -> bool {
@@ -1861,7 +1869,7 @@ impl State {
         commands: &mut Commands,
         specs: &sprite::Specs,
         input: Input,
-        _speaker: &mut Speaker,
+        speaker: &mut Speaker,
     ) {
         let sword_spec: &sprite::Spec::<sprite::SWORD> = &specs.sword;
         let wall_spec: &sprite::Spec::<sprite::Wall> = &specs.wall;
@@ -1897,7 +1905,7 @@ impl State {
             self.player.facing = self.player.facing.clockwise();
             player_moved = true;
         } else if input.pressed_this_frame(Button::START) {
-            self.restart(wall_spec);
+            self.restart(specs);
         }
 
         let staff_xy_pair = self.staff_xy_pair();
@@ -1916,7 +1924,7 @@ impl State {
                         // Start animation timer
                         self.animations.push(Animation::reset(key));
 
-                        // TODO Good place for SFX
+                        speaker.request_sfx(SFX::SWITCH);
                     }
 
                     // TODO? Is it worth building an acceleration structure for this lookup?
@@ -1932,7 +1940,7 @@ impl State {
                     }
                 }
                 RemoveRoach => {
-                    // TODO Good place for SFX
+                    speaker.request_sfx(SFX::SQUASH);
                     // TODO? Put a splat on the ground in this spot instead?
                     self.mobs.remove(key);
                 }
@@ -2002,9 +2010,9 @@ impl State {
             }
 
             if self.mobs.get(Key { xy: self.player_position.xy() }).is_some() {
-                // TODO good place for SFX
+                speaker.request_sfx(SFX::HIT);
 
-                self.restart(wall_spec);
+                self.restart(specs);
             }
         }
 
@@ -2150,3 +2158,33 @@ impl State {
         }
     }
 }
+
+impl Hallway for State {
+    type Params = SWORDParams;
+
+    fn new(rng: &mut Xs, specs: &sprite::Specs, params: Self::Params) -> Self {
+        Self::new(rng, specs, params)
+    }
+
+    fn update_and_render(
+        &mut self,
+        commands: &mut Commands,
+        specs: &sprite::Specs,
+        input: Input,
+        speaker: &mut Speaker,
+    ) {
+        self.update_and_render(commands, specs, input, speaker)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.is_complete()
+    }
+
+    fn reset(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+
+    fn give_up(&mut self, specs: &sprite::Specs) {
+        self.restart(specs)
+    }
+}
@@ -151,6 +151,20 @@ impl <T> From<Vec1<T>> for Vec<T> {
     }
 }
 
+impl <T: serde::Serialize> serde::Serialize for Vec1<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+impl <'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Vec1<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<T>::deserialize(deserializer)?
+            .try_into()
+            .map_err(|_: EmptyError| serde::de::Error::invalid_length(0, &"at least one element"))
+    }
+}
+
 #[macro_export]
 macro_rules! _vec1 {
     ($($element: expr),+ $(,)?) => {
@@ -164,7 +178,7 @@ macro_rules! _vec1 {
 }
 pub use _vec1 as vec1;
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
 pub struct Grid1<Element, Width = NonZeroUsize> {
     pub width: Width,
     // TODO Since usize is u32 on wasm, let's make a Vec32 type that makes that restriction clear, so we
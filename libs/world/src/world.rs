@@ -9,6 +9,8 @@ use models::{
 use vec1::Vec1;
 use xs::{Xs};
 
+use serde::{Deserialize, Serialize};
+
 pub const TILES_PER_ROW: TileSprite = 6;
 pub const WALL_SPRITE: TileSprite = 0;
 pub const FLOOR_SPRITE: TileSprite = 1;
@@ -35,6 +37,7 @@ mod entities {
     use models::{Entity, XY, SegmentId};
 
     use std::collections::{BTreeMap};
+    use serde::{Deserialize, Serialize};
 
     pub type Key = models::Location;
 
@@ -54,12 +57,16 @@ mod entities {
     // then instead consider just adding a field to Entity. If we actually run into perf issues due to
     // entities being large, then we'll know better then than now how to deal with them.
 
-    #[derive(Clone, Debug, Default)]
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     pub struct Entities {
         map: BTreeMap<Key, Entity>,
     }
 
     impl Entities {
+        pub fn all_entities(&self) -> impl Iterator<Item = (&Key, &Entity)> {
+            self.map.iter()
+        }
+
         pub fn all_entities_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
             self.map.values_mut()
         }
@@ -128,16 +135,21 @@ pub type EntityKey = entities::Key;
 pub mod hallway {
     use features::{invariant_assert};
     use crate::entities::Key as EntityKey;
+    use gfx::Commands;
     use models::{config::HallwaySpec, sprite::Specs};
+    use platform_types::{Input, Speaker};
     use xs::Xs;
 
+    pub use ::hallway::{Hallway, Progress, GIVE_UP_HOLD_FRAMES};
+
     use std::collections::BTreeMap;
+    use serde::{Deserialize, Serialize};
 
     macro_rules! state_def {
         (
-            $( $variant: ident ($type: ty) ),+ $(,)?
+            $( $variant: ident ($_params: ident) $_const_name: ident = $_value: expr => $type: ty ),+ $(,)?
         ) => {
-            #[derive(Clone, Debug)]
+            #[derive(Clone, Debug, Deserialize, Serialize)]
             pub enum State {
                 $( $variant($type), )+
             }
@@ -149,41 +161,61 @@ pub mod hallway {
                     // if other games can use each other's sprites.
                     match hallway {
                         HallwaySpec::None => None,
-                        $( HallwaySpec::$variant => Some(Self::$variant(<$type>::new(rng, &specs))), )+
+                        $( HallwaySpec::$variant(params) => Some(Self::$variant(<$type as Hallway>::new(rng, specs, params))), )+
                     }
                 }
-        
+
+                pub fn update_and_render(
+                    &mut self,
+                    commands: &mut Commands,
+                    specs: &Specs,
+                    input: Input,
+                    speaker: &mut Speaker,
+                ) {
+                    match self {
+                        $( Self::$variant(inner) => Hallway::update_and_render(inner, commands, specs, input, speaker), )+
+                    }
+                }
+
                 pub fn is_complete(&self) -> bool {
                     match self {
-                        $( Self::$variant(inner) => inner.is_complete(), )+
+                        $( Self::$variant(inner) => Hallway::is_complete(inner), )+
+                    }
+                }
+
+                pub fn reset(&mut self, specs: &Specs) {
+                    match self {
+                        $( Self::$variant(inner) => Hallway::reset(inner, specs), )+
+                    }
+                }
+
+                /// Called when the player backs out of the hallway, before they are
+                /// returned to where they came from.
+                pub fn give_up(&mut self, specs: &Specs) {
+                    match self {
+                        $( Self::$variant(inner) => Hallway::give_up(inner, specs), )+
+                    }
+                }
+
+                pub fn progress(&self) -> Option<Progress> {
+                    match self {
+                        $( Self::$variant(inner) => Hallway::progress(inner), )+
                     }
                 }
             }
         }
     }
 
-    state_def! {
-        IcePuzzle(ice_puzzle::State),
-        // Staff Whacking Ordeal Required, Duh
-        SWORD(sword::State),
-        // Boldly Or Leisurely Dashing
-        // or
-        // Boulders Often Lope Downwards
-        BOLD(bold::State),
-        HexHop(hex_hop::State),
-        HexTwiddle(hex_twiddle::State),
-        CubeMaze(cube_maze::State),
-        KeycardShuffle(keycard_shuffle::State),
-    }
+    models::hallway_kinds!(state_def);
 
-    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
     pub struct Key {
         lower: EntityKey,
         higher: EntityKey,
     }
 
     impl Key {
-        fn new(a: EntityKey, b: EntityKey) -> Self {
+        pub(crate) fn new(a: EntityKey, b: EntityKey) -> Self {
             let lower = core::cmp::min(a, b);
             let higher = core::cmp::max(a, b);
 
@@ -202,7 +234,7 @@ pub mod hallway {
         }
     }
 
-    #[derive(Clone, Default)]
+    #[derive(Clone, Default, Deserialize, Serialize)]
     pub struct States {
         map: BTreeMap<Key, State>,
     }
@@ -223,7 +255,31 @@ pub mod hallway {
 }
 pub use hallway::{States as HallwayStates};
 
-#[derive(Clone)]
+/// The names of the music tracks that play in each part of the world, taken from
+/// the config's `MusicSpec`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Music {
+    /// Indexed by `SegmentId`.
+    segments: Vec<Option<String>>,
+    hallways: std::collections::BTreeMap<hallway::Key, String>,
+    victory: Option<String>,
+}
+
+impl Music {
+    pub fn segment(&self, segment_id: SegmentId) -> Option<&str> {
+        self.segments.get(usize::from(segment_id))?.as_deref()
+    }
+
+    pub fn hallway(&self, a: EntityKey, b: EntityKey) -> Option<&str> {
+        self.hallways.get(&hallway::Key::new(a, b)).map(String::as_str)
+    }
+
+    pub fn victory(&self) -> Option<&str> {
+        self.victory.as_deref()
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
 pub struct World {
     pub segments: Vec1<WorldSegment>,
     /// The ID of the current segment we are in.
@@ -295,6 +351,98 @@ pub fn transform_all_matching(world: &mut World, from_def_id: DefId, to_def: &Mi
     }
 }
 
+/// Puts `item` into the inventory of the entity at `target_key`. If that is the
+/// player, then the item's `on_collect` actions happen too.
+pub fn push_inventory(
+    world: &mut World,
+    entity_defs: &[MiniEntityDef],
+    target_key: EntityKey,
+    item: Entity,
+) {
+    if target_key == world.player_key() {
+        for action in &item.transformable.on_collect {
+            match action {
+                CollectAction::Transform(Transform{ from, to }) => {
+                    if let Some(to_def) = entity_defs.get((*to) as usize) {
+                        transform_all_matching(world, *from, to_def);
+                    } else {
+                        invariant_assert!(false, "Why are we trying to transform something into something that doesn't exist? to {to}");
+                    }
+                }
+            }
+        }
+    }
+
+    if let Some(target) = world.get_entity_mut(target_key) {
+        target.inventory.push(item);
+    }
+}
+
+/// Moves the items with `def_id` from the giving entity to the receiving entity,
+/// satisfying the receiving entity's desire for it. The receiving entity hands over
+/// the last thing in their inventory as a reward, if they have anything.
+pub fn give_item(
+    world: &mut World,
+    entity_defs: &[MiniEntityDef],
+    giving_entity_key: EntityKey,
+    receiveing_entity_key: EntityKey,
+    def_id: DefId,
+) {
+    let Some(giving_entity_len) = world.get_entity(giving_entity_key)
+        .map(|g_e| g_e.inventory.len())
+    else {
+        return
+    };
+
+    // TODO? Worth checking if it's not there?
+    // TODO? Do we want to give every entity an inventory, and preserve every item?
+    // Iterate backward so we can remove without indexing errors
+    for i in (0..giving_entity_len).rev() {
+        let Some(item_def_id) = world.get_entity(giving_entity_key)
+            .map(|g_e| g_e.inventory[i].transformable.id)
+        else {
+            return
+        };
+
+        if item_def_id == def_id {
+            let Some(taken) = world.get_entity_mut(giving_entity_key)
+                .map(|g_e| g_e.inventory.remove(i)) else {
+                    return
+                };
+
+            // This option is a bit less hassle than implmenting a way to get mut refs to
+            // two distinct entities.
+            let mut reward_opt = None;
+
+            if let Some(receiveing_entity) = world.get_entity_mut(receiveing_entity_key) {
+                for desire in &mut receiveing_entity.transformable.wants {
+                    if desire.def_id == def_id {
+                        desire.state = models::DesireState::Satisfied;
+                        break
+                    }
+                }
+
+                // TODO? Allow associating particular desires with particular rewards
+                //       from the same NPC?
+
+                // Extract the reward before putting the taken item into the receiveing_entity's
+                // inventory, so they don't give it back.
+                if let Some(reward) = receiveing_entity.inventory.pop() {
+                    reward_opt = Some(reward);
+                }
+
+                receiveing_entity.inventory.push(taken);
+            } else {
+                debug_assert!(false, "Why did the item get taken if no one wants it?!");
+            }
+
+            if let Some(reward) = reward_opt {
+                push_inventory(world, entity_defs, giving_entity_key, reward);
+            }
+        }
+    }
+}
+
 mod random {
     use models::{
         config, consts::{TileFlags}, Location,
@@ -458,7 +606,7 @@ fn get_non_final_lock_and_keys<'defs>(
 #[cfg(test)]
 mod get_non_final_lock_and_keys_works {
     use super::*;
-    use models::{CollectAction, MiniEntityDef, Transform};
+    use models::{script, CollectAction, MiniEntityDef, Transform};
     use vec1::Vec1;
 
     const SOME_LOCKED_DOOR: MiniEntityDef = MiniEntityDef {
@@ -467,6 +615,7 @@ mod get_non_final_lock_and_keys_works {
         tile_sprite: 0,
         on_collect: vec![],
         wants: vec![],
+        scripts: script::Names::NONE,
     };
 
     const SOME_OPEN_DOOR: MiniEntityDef = MiniEntityDef {
@@ -475,6 +624,7 @@ mod get_non_final_lock_and_keys_works {
         tile_sprite: 0,
         on_collect: vec![],
         wants: vec![],
+        scripts: script::Names::NONE,
     };
 
     fn some_key() -> MiniEntityDef {
@@ -486,6 +636,7 @@ mod get_non_final_lock_and_keys_works {
                 Transform{ from: SOME_LOCKED_DOOR.id, to: SOME_OPEN_DOOR.id }
             )],
             wants: vec![],
+            scripts: script::Names::NONE,
         }
     }
 
@@ -495,6 +646,7 @@ mod get_non_final_lock_and_keys_works {
         tile_sprite: 0,
         on_collect: vec![],
         wants: vec![],
+        scripts: script::Names::NONE,
     };
 
     const OPEN_VICTORY_DOOR: MiniEntityDef = MiniEntityDef {
@@ -503,6 +655,7 @@ mod get_non_final_lock_and_keys_works {
         tile_sprite: 0,
         on_collect: vec![],
         wants: vec![],
+        scripts: script::Names::NONE,
     };
 
     fn victory_key() -> MiniEntityDef {
@@ -514,6 +667,7 @@ mod get_non_final_lock_and_keys_works {
                 Transform{ from: LOCKED_VICTORY_DOOR.id, to: OPEN_VICTORY_DOOR.id }
             )],
             wants: vec![],
+            scripts: script::Names::NONE,
         }
     }
 
@@ -574,6 +728,7 @@ pub struct Generated {
     // }
     pub goal_door_tile_sprite: TileSprite,
     pub hallway_states: HallwayStates,
+    pub music: Music,
 }
 
 pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<Generated, Error> {
@@ -593,8 +748,28 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
 
     let mut segments = Vec::with_capacity(16);
     let mut config_segments = Vec::with_capacity(16);
+    let mut music = Music {
+        victory: config.music.victory.clone(),
+        ..<_>::default()
+    };
+
+    // A bad config could have these the wrong way around, but that shouldn't crash.
+    fn range_inclusive(rng: &mut Xs, min: u8, max: u8) -> u8 {
+        let max = core::cmp::max(min, max);
+
+        xs::range(rng, u32::from(min)..u32::from(max) + 1) as u8
+    }
+
+    let spheres_spec = &config.spheres;
+
+    let sphere_count = range_inclusive(rng, spheres_spec.min_count, spheres_spec.max_count);
 
-    let target_segment_count = xs::range(rng, 4..12);
+    let sphere_sizes: Vec<u8> = (0..sphere_count)
+        // Every sphere needs at least one segment to put its key in.
+        .map(|_| range_inclusive(rng, core::cmp::max(spheres_spec.min_segments, 1), spheres_spec.max_segments))
+        .collect();
+
+    let target_segment_count: u32 = sphere_sizes.iter().map(|&size| u32::from(size)).sum();
 
     for _ in 0..target_segment_count {
         // TODO? Cap the number of segments, or just be okay with the first room never being in the 5 billions, etc?
@@ -624,6 +799,9 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
         config_segments.push(
             config_segment
         );
+        music.segments.push(
+            config.music.segments.get(index).cloned().flatten()
+        );
     }
 
     let Ok(segments) = Vec1::try_from(segments) else {
@@ -883,40 +1061,27 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
     let mut spheres = Vec::with_capacity(16);
 
     {
-        let initial_lak_index = xs::index(rng, 0..non_final_lock_and_keys.len());
-        let mut lak_index = initial_lak_index;
-
-        const MIN_PER_SPHERE: u8 = 2;
-        const MAX_PER_SPHERE: u8 = 10;
+        let mut lak_index = xs::index(rng, 0..non_final_lock_and_keys.len());
 
-        // A partitioning of the segment ID range into chunks.
+        // A partitioning of the segment ID range into chunks, one for each sphere.
         // No segment ID appears in more than one chunk.
-        let mut chunks: Vec<Vec<SegmentId>> = Vec::with_capacity(
-            (segments_count / SegmentId::from(MIN_PER_SPHERE)).into()
-        );
-        chunks.push(Vec::with_capacity(MAX_PER_SPHERE.into()));
-
-        for id in 0..segments_count {
-            let final_index = chunks.len() - 1;
-            let chunk = &mut chunks[final_index];
+        let mut chunks: Vec<Vec<SegmentId>> = Vec::with_capacity(sphere_sizes.len());
 
-            let len = chunk.len();
+        let mut next_id: SegmentId = 0;
+        for &size in &sphere_sizes {
+            let mut chunk = Vec::with_capacity(size.into());
 
-            if len < usize::from(MIN_PER_SPHERE)
-            // This attempts to produce a uniform range
-            || xs::range(rng, 0..MAX_PER_SPHERE.into()) == 0 {
-                // Add to existing chunk
-                chunk.push(id);
-            } else {
-                // Start new chunk
-                let mut new_chunk = Vec::with_capacity(MAX_PER_SPHERE.into());
-                new_chunk.push(id);
-                chunks.push(new_chunk);
+            for _ in 0..size {
+                chunk.push(next_id);
+                next_id += 1;
             }
+
+            chunks.push(chunk);
         }
+        invariant_assert!(next_id == segments_count);
 
         let chunks_len = chunks.len();
-        invariant_assert!(chunks_len >= 2, "We need at least two chunks for the joining to work properly");
+        invariant_assert!(chunks.iter().all(|chunk| !chunk.is_empty()), "Every sphere needs at least one segment");
 
         macro_rules! place_door_pair {
             ($door_def: expr, $segment_ids: expr) => {
@@ -977,6 +1142,10 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
 
                 'insert: {
                     if let Some(s) = hallway::State::from_spec(*hallway, rng, specs) {
+                        if let Some(Some(track)) = config.music.hallways.get(hallway_index) {
+                            music.hallways.insert(hallway::Key::new(key_i, key_j), track.clone());
+                        }
+
                         hallway_states.insert(key_i, key_j, s);
                     }
                 }
@@ -1004,6 +1173,12 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
             // ... so we can talk about the next chunk
             let next_chunk_index = chunk_index + 1;
 
+            // Each sphere needs its own kind of lock, so that the key for one sphere
+            // does not open up later ones too.
+            if chunk_index >= non_final_lock_and_keys.len() {
+                return Err(Error::NotEnoughNonFinalLockAndKeysFound);
+            }
+
             let edge_lak = &non_final_lock_and_keys[lak_index];
 
             lak_index += 1;
@@ -1011,10 +1186,6 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
                 lak_index = 0;
             }
 
-            if lak_index == initial_lak_index {
-                return Err(Error::NotEnoughNonFinalLockAndKeysFound);
-            }
-
             let segment_id = {
                 let chunk = &chunks[chunk_index];
                 chunk[xs::index(rng, 0..chunk.len())]
@@ -1082,6 +1253,7 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
         _world: &World,
         spheres: &[Sphere<'defs>],
         all_desires: &[DesireRef<'defs>],
+        push_back_percent: u8,
     ) -> Constraints<'defs> {
         invariant_assert!(_world.segments.len() <= SegmentId::MAX as usize);
 
@@ -1093,31 +1265,50 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
         let max_sub_target_len = average_target_len * 2;
         invariant_assert!(max_sub_target_len <= u32::MAX as usize); // For random selection later
 
-        let initial_index = xs::index(rng, 0..all_desires.len());
-
         let mut item_specs: Vec<_> = Vec::with_capacity(overall_target_len);
 
+        // Keep track of the used desires, so we don't use the same one twice.
+        let mut used_desires = vec![false; all_desires.len()];
+
         let mut tries = 0;
         while item_specs.len() < overall_target_len && tries < 16 {
             tries += 1;
             item_specs.clear();
+            used_desires.fill(false);
 
-            for sphere in spheres {
-                // TODO: have multiple spheres, delineated by locked doors.
-                // Treat each sphere as a separate puzzle, with unlocking
-                // the next door as the goal.
-                // Keep track of the used desires, so we don't use the same one twice.
+            for sphere_index in 0..spheres.len() {
+                // Each sphere is a separate puzzle, with getting the key to the next
+                // sphere as the goal.
+                let sphere = &spheres[sphere_index];
 
                 let segment_ids = &sphere.segment_ids;
                 invariant_assert!(segment_ids.len() <= u32::MAX as usize);
 
                 macro_rules! random_segment_id {
                     () => {
-                        // TODO: We can randomly push some things back into previous spheres, for variety.
                         segment_ids[xs::index(rng, 0..segment_ids.len())]
                     }
                 }
 
+                // By the time the player can get into this sphere, they can get back
+                // to all the previous ones, so things needed here can go there too.
+                let earlier_spheres = &spheres[..sphere_index];
+
+                macro_rules! random_maybe_earlier_segment_id {
+                    () => {
+                        if !earlier_spheres.is_empty()
+                        && xs::range(rng, 0..100) < u32::from(push_back_percent) {
+                            let earlier_ids = &earlier_spheres[
+                                xs::index(rng, 0..earlier_spheres.len())
+                            ].segment_ids;
+
+                            earlier_ids[xs::index(rng, 0..earlier_ids.len())]
+                        } else {
+                            random_segment_id!()
+                        }
+                    }
+                }
+
                 // Start with a solvable puzzle, then add steps, keeping it solvable
                 item_specs.push(ItemSpec{
                     item_def: sphere.goal_item_def,
@@ -1126,20 +1317,32 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
 
                 let sub_target_len = xs::index(rng, 1..max_sub_target_len);
 
-                let mut index = xs::index(rng, 0..all_desires.len());
+                let initial_index = xs::index(rng, 0..all_desires.len());
+                let mut index = initial_index;
 
                 let initial_spec_len = item_specs.len();
 
                 while item_specs.len() - initial_spec_len < sub_target_len {
                     // Select the index or not, at a rate proportional to how many we need.
-                    if (xs::index(rng, 0..all_desires.len() + 1)) < sub_target_len {
+                    if !used_desires[index]
+                    && (xs::index(rng, 0..all_desires.len() + 1)) < sub_target_len {
                         let Some(last) = item_specs.pop() else {
                             invariant_assert!(false, "item_specs.pop() == None");
                             continue
                         };
 
+                        used_desires[index] = true;
+
                         let desire = all_desires[index];
 
+                        // The sphere's key stays in the sphere, so that the spheres
+                        // are still unlocked in order.
+                        let pocket_segment_id = if core::ptr::eq(last.item_def, sphere.goal_item_def) {
+                            random_segment_id!()
+                        } else {
+                            random_maybe_earlier_segment_id!()
+                        };
+
                         item_specs.push(ItemSpec{
                             item_def: desire.item_def,
                             location: last.location,
@@ -1147,7 +1350,7 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
 
                         item_specs.push(ItemSpec{
                             item_def: last.item_def,
-                            location: AbstractLocation::NpcPocket(desire.mob_def, random_segment_id!()),
+                            location: AbstractLocation::NpcPocket(desire.mob_def, pocket_segment_id),
                         });
                     }
 
@@ -1169,7 +1372,13 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
         }
     }
 
-    let constraints: Constraints = select_constraints(rng, &world, &spheres, &all_desires);
+    let constraints: Constraints = select_constraints(
+        rng,
+        &world,
+        &spheres,
+        &all_desires,
+        spheres_spec.push_back_percent,
+    );
 
     for item_spec in constraints.item_specs {
         let mut attempts = 0;
@@ -1302,5 +1511,566 @@ pub fn generate(rng: &mut Xs, config: &Config, specs: &sprite::Specs) -> Result<
         entity_defs,
         goal_door_tile_sprite,
         hallway_states,
+        music,
     })
-}
\ No newline at end of file
+}
+
+pub mod solve {
+    //! Checks whether a generated world can actually be finished, by searching through
+    //! the ways the player can change the world: collecting items and giving items to
+    //! those that want them. Hallways are assumed to be completable. What scripts do
+    //! is not known here, so the search never steps onto, collects, or interacts with
+    //! an entity that would run one, and worlds that need scripts come out unsolvable.
+
+    use crate::{entity_key, give_item, is_passable, push_inventory, EntityKey, Generated, World};
+    use models::{script::Hook, xy_to_i, DefId, DesireState, XY};
+
+    use std::collections::BTreeSet;
+
+    pub const DEFAULT_STATE_LIMIT: usize = 1 << 14;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Step {
+        Collect(EntityKey),
+        Give { to: EntityKey, def_id: DefId },
+    }
+
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub enum Outcome {
+        /// The steps that lead to a victory door being reachable.
+        Solvable(Vec<Step>),
+        Unsolvable,
+        /// More states than the limit would need to be looked at to decide.
+        GaveUp,
+    }
+
+    impl Outcome {
+        pub fn is_solvable(&self) -> bool {
+            matches!(self, Self::Solvable(_))
+        }
+    }
+
+    pub fn solve(generated: &Generated) -> Outcome {
+        solve_with_limit(generated, DEFAULT_STATE_LIMIT)
+    }
+
+    pub fn solve_with_limit(generated: &Generated, state_limit: usize) -> Outcome {
+        let mut seen = BTreeSet::new();
+        let mut stack = vec![(generated.world.clone(), Vec::new())];
+
+        seen.insert(WorldState::new(&generated.world));
+
+        while let Some((world, steps)) = stack.pop() {
+            if seen.len() > state_limit {
+                return Outcome::GaveUp
+            }
+
+            let reachable = explore(&world);
+
+            if reachable.victory {
+                return Outcome::Solvable(steps)
+            }
+
+            let mut children = Vec::with_capacity(reachable.collectable.len() + reachable.interactable.len());
+
+            for &(from, to) in &reachable.interactable {
+                let Some(def_id) = give(generated, &world, from, to) else { continue };
+
+                let mut child = world.clone();
+                stand_on(&mut child, from);
+                // `give` already checked the desire is there, so this is safe to do again.
+                mark_in_sight(&mut child, to);
+                give_item(&mut child, &generated.entity_defs, from, to, def_id);
+
+                children.push((child, Step::Give { to, def_id }));
+            }
+
+            // Pushed last so they are tried first, since collecting is rarely a mistake.
+            for &key in &reachable.collectable {
+                let mut child = world.clone();
+                stand_on(&mut child, key);
+
+                let Some(item) = child.mobs.remove(key) else { continue };
+                push_inventory(&mut child, &generated.entity_defs, key, item);
+
+                children.push((child, Step::Collect(key)));
+            }
+
+            for (child, step) in children {
+                if seen.insert(WorldState::new(&child)) {
+                    let mut child_steps = steps.clone();
+                    child_steps.push(step);
+                    stack.push((child, child_steps));
+                }
+            }
+        }
+
+        Outcome::Unsolvable
+    }
+
+    struct Reachable {
+        victory: bool,
+        collectable: BTreeSet<EntityKey>,
+        /// Pairs of where the player would stand, and what they would interact with.
+        interactable: BTreeSet<(EntityKey, EntityKey)>,
+    }
+
+    fn neighbours(XY { x, y }: XY) -> impl Iterator<Item = XY> {
+        [
+            XY { x: x.dec(), y },
+            XY { x: x.inc(), y },
+            XY { x, y: y.dec() },
+            XY { x, y: y.inc() },
+        ].into_iter()
+            // Saturation can give back the same position
+            .filter(move |&xy| xy != XY { x, y })
+    }
+
+    fn explore(world: &World) -> Reachable {
+        let mut output = Reachable {
+            victory: false,
+            collectable: BTreeSet::new(),
+            interactable: BTreeSet::new(),
+        };
+
+        let start = world.player_key();
+        let mut visited = BTreeSet::from([start]);
+        let mut to_visit = vec![start];
+
+        while let Some(current) = to_visit.pop() {
+            for xy in neighbours(current.xy) {
+                let key = entity_key(current.segment_id, xy);
+
+                let mob = world.mobs.get(key);
+
+                if let Some(mob) = mob {
+                    if !mob.transformable.wants.is_empty() && mob.script(Hook::Interact).is_none() {
+                        output.interactable.insert((current, key));
+                    }
+
+                    if mob.script(Hook::Step).is_some() || mob.script(Hook::Collect).is_some() {
+                        continue
+                    }
+                }
+
+                if !can_walk_onto(world, key) {
+                    continue
+                }
+
+                // This mirrors the order of checks in the game's walking code.
+                match mob {
+                    Some(mob) if mob.is_collectable() => {
+                        output.collectable.insert(key);
+                    },
+                    Some(mob) if mob.is_victory() => {
+                        output.victory = true;
+                        return output
+                    },
+                    Some(mob) if mob.is_door() => {
+                        // The player arrives standing on the door at the other end, so
+                        // what we track as visited are the places doors lead to.
+                        let target = mob.door_target;
+                        if visited.insert(target) {
+                            to_visit.push(target);
+                        }
+                    },
+                    _ => {
+                        if visited.insert(key) {
+                            to_visit.push(key);
+                        }
+                    },
+                }
+            }
+        }
+
+        output
+    }
+
+    fn can_walk_onto(world: &World, key: EntityKey) -> bool {
+        let Some(segment) = world.segments.get(usize::from(key.segment_id)) else {
+            return false
+        };
+        let Ok(i) = xy_to_i(segment, key.xy.x, key.xy.y) else {
+            return false
+        };
+        let Some(tile) = segment.get(i) else {
+            return false
+        };
+
+        if let Some(mob) = world.mobs.get(key) {
+            if !mob.is_steppable() {
+                return false
+            }
+        }
+
+        is_passable(tile)
+    }
+
+    fn stand_on(world: &mut World, key: EntityKey) {
+        world.segment_id = key.segment_id;
+        world.player.xy = key.xy;
+    }
+
+    fn mark_in_sight(world: &mut World, key: EntityKey) {
+        let inventory: Vec<DefId> = world.player.inventory.iter()
+            .map(|e| e.transformable.id)
+            .collect();
+
+        if let Some(mob) = world.mobs.get_mut(key) {
+            for desire in &mut mob.transformable.wants {
+                if desire.state == DesireState::Unsatisfied
+                && inventory.contains(&desire.def_id) {
+                    desire.state = DesireState::SatisfactionInSight;
+                }
+            }
+        }
+    }
+
+    /// Mirrors what happens when the player interacts with something: Returns the
+    /// ID of the item that would be handed over, if any.
+    fn give(generated: &Generated, world: &World, from: EntityKey, to: EntityKey) -> Option<DefId> {
+        let mut mob = world.mobs.get(to)?.clone();
+
+        let mut given = None;
+        for desire in &mut mob.transformable.wants {
+            if desire.state == DesireState::Unsatisfied
+            && world.player.inventory.iter().any(|e| e.transformable.id == desire.def_id) {
+                desire.state = DesireState::SatisfactionInSight;
+                given = Some(desire.def_id);
+            }
+        }
+
+        let given = given?;
+
+        // Without anything to say, the conversation that hands the item over never starts.
+        let speeches = generated.speeches.get(mob.speeches_key())?;
+        if speeches.is_empty() {
+            return None
+        }
+
+        // Standing somewhere else is always possible, but we want to be sure the player is where we think.
+        debug_assert!(from.segment_id == to.segment_id);
+
+        Some(given)
+    }
+
+    /// The parts of an entity that the search can change.
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct EntityState {
+        def_id: DefId,
+        /// The def IDs of what it wants, and how satisfied each want is.
+        wants: Vec<(DefId, u8)>,
+        inventory: Vec<DefId>,
+    }
+
+    impl EntityState {
+        fn new(entity: &models::Entity) -> Self {
+            Self {
+                def_id: entity.def_id(),
+                wants: entity.transformable.wants.iter()
+                    .map(|desire| (desire.def_id, desire.state.clone() as u8))
+                    .collect(),
+                inventory: entity.inventory.iter().map(models::Entity::def_id).collect(),
+            }
+        }
+    }
+
+    /// Everything about a world that the search can change, kept whole rather than
+    /// hashed, since a collision would skip a state that might have been the way out.
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct WorldState {
+        player_key: EntityKey,
+        player: EntityState,
+        /// In key order, since that is the order `Entities` keeps them in.
+        mobs: Vec<(EntityKey, EntityState)>,
+    }
+
+    impl WorldState {
+        fn new(world: &World) -> Self {
+            Self {
+                player_key: world.player_key(),
+                player: EntityState::new(&world.player),
+                mobs: world.mobs.all_entities()
+                    .map(|(key, entity)| (*key, EntityState::new(entity)))
+                    .collect(),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod solve_works {
+        use super::*;
+        use crate::{generate, testing};
+
+        fn assert_many_seeds_are_solvable(config: &models::config::Config) {
+            let specs = <_>::default();
+
+            let mut unsolvable = Vec::new();
+
+            for seed_index in 0..200u128 {
+                let seed = seed_index.to_le_bytes();
+                let mut rng = xs::from_seed(seed);
+
+                let generated = generate(&mut rng, config, &specs)
+                    .unwrap_or_else(|e| panic!("{seed:?}: {e:?}"));
+
+                let outcome = solve(&generated);
+                if !outcome.is_solvable() {
+                    unsolvable.push((seed, outcome));
+                }
+            }
+
+            assert!(unsolvable.is_empty(), "{unsolvable:#?}");
+        }
+
+        // Hallways don't affect solvability, and generating them is slower.
+        fn config_without_hallways() -> models::config::Config {
+            testing::example_config_with_hallways(
+                vec1::Vec1::singleton(models::config::HallwaySpec::None)
+            )
+        }
+
+        #[test]
+        fn on_many_seeds_of_the_example_config() {
+            assert_many_seeds_are_solvable(&config_without_hallways());
+        }
+
+        #[test]
+        fn on_many_seeds_with_as_many_spheres_as_there_are_locks_for() {
+            let mut config = config_without_hallways();
+            // One more than the amount of non-final locked doors in the example config.
+            config.spheres = models::config::SpheresSpec {
+                min_count: 9,
+                max_count: 9,
+                min_segments: 1,
+                max_segments: 2,
+                push_back_percent: 100,
+            };
+
+            assert_many_seeds_are_solvable(&config);
+        }
+
+        #[test]
+        fn on_a_world_without_its_goal_door() {
+            let config = testing::example_config();
+            let mut rng = xs::from_seed([7; 16]);
+
+            let mut generated = generate(&mut rng, &config, &<_>::default()).unwrap();
+
+            let goal_keys: Vec<EntityKey> = generated.world.mobs.all_entities()
+                .filter(|(_, e)| e.is_door() && e.transformable.tile_sprite == generated.goal_door_tile_sprite)
+                .map(|(k, _)| *k)
+                .collect();
+            assert!(!goal_keys.is_empty(), "precondition failure");
+
+            for key in goal_keys {
+                generated.world.mobs.remove(key);
+            }
+
+            assert_eq!(solve(&generated), Outcome::Unsolvable);
+        }
+
+        #[test]
+        fn on_a_world_that_could_only_be_finished_by_scripts() {
+            let config = testing::example_config();
+            let mut rng = xs::from_seed([8; 16]);
+
+            let mut generated = generate(&mut rng, &config, &<_>::default()).unwrap();
+            assert!(solve(&generated).is_solvable(), "precondition failure");
+
+            for mob in generated.world.mobs.all_entities_mut() {
+                if !mob.is_victory() {
+                    let scripts = &mut mob.transformable.scripts;
+                    scripts.on_interact = Some("unknown".to_owned());
+                    scripts.on_step = Some("unknown".to_owned());
+                }
+            }
+
+            assert_eq!(solve(&generated), Outcome::Unsolvable);
+        }
+    }
+}
+
+/// Helpers for tests in this crate and crates that depend on it, which want a config
+/// without needing to go through the Rune parsing.
+#[cfg(any(test, feature = "testing"))]
+pub mod testing {
+    use models::{
+        config::{Config, EntityDef, HallwaySpec, WorldSegment},
+        consts::{COLLECTABLE, DOOR, NOT_SPAWNED_AT_START, STEPPABLE, VICTORY, EntityDefFlags, TileFlags},
+        CollectAction, DefId, Speech, Transform,
+    };
+    use vec1::{Vec1, vec1};
+
+    /// A config equivalent to the one in `examples/default`, except with every kind of
+    /// hallway enabled, so that generated worlds exercise all of them.
+    pub fn example_config() -> Config {
+        example_config_with_hallways(vec1![
+            HallwaySpec::IcePuzzle(<_>::default()),
+            HallwaySpec::SWORD(<_>::default()),
+            HallwaySpec::BOLD(<_>::default()),
+            HallwaySpec::HexHop(<_>::default()),
+            HallwaySpec::HexTwiddle(<_>::default()),
+            HallwaySpec::CubeMaze(<_>::default()),
+            HallwaySpec::KeycardShuffle(<_>::default()),
+        ])
+    }
+
+    pub fn example_config_with_hallways(hallways: Vec1<HallwaySpec>) -> Config {
+        const MOB: EntityDefFlags = 0;
+        const ITEM: EntityDefFlags = STEPPABLE | COLLECTABLE;
+        const OPEN_DOOR: EntityDefFlags = DOOR | STEPPABLE;
+        const END_DOOR: EntityDefFlags = OPEN_DOOR | VICTORY | NOT_SPAWNED_AT_START;
+        const LOCKED_DOOR: EntityDefFlags = DOOR;
+
+        fn speeches(lists: &[&[&str]]) -> Vec<Vec1<Speech>> {
+            lists.iter()
+                .map(|list| {
+                    list.iter()
+                        .map(|&s| Speech::from(s))
+                        .collect::<Vec<_>>()
+                        .try_into()
+                        .expect("Test speeches should not be empty")
+                })
+                .collect()
+        }
+
+        let mut entities = vec![
+            EntityDef {
+                flags: ITEM,
+                inventory_description: speeches(&[&["a chest."]]),
+                tile_sprite: 18,
+                ..def()
+            },
+            EntityDef {
+                flags: MOB,
+                speeches: speeches(&[&["get me a chest?"], &["a chest!", "have this."], &["thanks!"]]),
+                tile_sprite: 12,
+                wants: vec![0],
+                ..def()
+            },
+            EntityDef {
+                flags: MOB,
+                speeches: speeches(&[&["get me some dollars?"], &["dollars!", "have this."], &["thanks!"]]),
+                tile_sprite: 13,
+                wants: vec![3],
+                ..def()
+            },
+            EntityDef {
+                flags: ITEM,
+                inventory_description: speeches(&[&["some dollars."]]),
+                tile_sprite: 19,
+                ..def()
+            },
+        ];
+
+        let open_end_door_id = entities.len() as DefId;
+        entities.push(EntityDef { flags: END_DOOR, tile_sprite: 4, ..def() });
+
+        let open_door_id = entities.len() as DefId;
+        entities.push(EntityDef { flags: OPEN_DOOR, tile_sprite: 3, ..def() });
+
+        for i in 0..9 {
+            let to = if i == 0 { open_end_door_id } else { open_door_id };
+
+            let door_id = entities.len() as DefId;
+            entities.push(EntityDef {
+                flags: LOCKED_DOOR,
+                tile_sprite: 24 + i,
+                speeches: speeches(&[&["a locked door."]]),
+                ..def()
+            });
+            entities.push(EntityDef {
+                flags: ITEM,
+                tile_sprite: 30 + i,
+                inventory_description: speeches(&[&["a key."]]),
+                on_collect: vec![CollectAction::Transform(Transform { from: door_id, to })],
+                ..def()
+            });
+        }
+
+        for (i, entity) in entities.iter_mut().enumerate() {
+            entity.id = i as DefId;
+        }
+
+        Config {
+            segments: vec1![
+                segment(14, &[
+                    F, F, F, F, F, F, F, F, D, D, D, D, D, D,
+                    F, W, W, F, W, W, F, F, W, W, W, W, W, D,
+                    F, W, B, A, B, W, A, F, W, I, I, I, W, D,
+                    F, F, A, W, A, F, A, F, F, B, B, B, F, D,
+                    F, W, B, A, B, W, A, F, F, F, N, F, F, D,
+                    F, W, W, I, W, W, F, F, F, N, F, N, F, D,
+                    F, F, F, F, F, F, F, F, F, F, F, F, F, D,
+                ]),
+                segment(7, &[
+                    W, W, W, W, W, W, W,
+                    W, D, F, B, W, D, W,
+                    W, W, W, B, A, F, W,
+                    W, D, B, F, A, F, W,
+                    W, W, W, B, A, F, W,
+                    W, D, F, F, W, D, W,
+                    W, W, W, W, W, W, W,
+                ]),
+                segment(7, &[
+                    W, W, W, W, W, W, W,
+                    W, D, W, W, W, D, W,
+                    W, F, A, A, A, F, W,
+                    W, F, A, B, A, F, W,
+                    W, F, A, A, A, F, W,
+                    W, F, F, F, F, F, W,
+                    W, F, A, A, A, F, W,
+                    W, F, A, B, A, F, W,
+                    W, F, A, A, A, F, W,
+                    W, D, W, W, W, D, W,
+                    W, W, W, W, W, W, W,
+                ]),
+                segment(6, &[
+                    W, W, W, W, W, W,
+                    W, D, F, F, F, W,
+                    W, F, F, F, D, W,
+                    W, F, A, A, F, W,
+                    W, F, A, A, F, W,
+                    W, D, F, F, F, W,
+                    W, F, F, F, D, W,
+                    W, W, W, W, W, W,
+                ]),
+            ],
+            entities: entities.try_into().expect("entities should not be empty"),
+            hallways,
+            spheres: <_>::default(),
+            music: <_>::default(),
+            scripts: <_>::default(),
+        }
+    }
+
+    use models::consts::{FLOOR, DOOR_START, ITEM_START, NPC_START, PLAYER_START, WALL};
+    const A: TileFlags = FLOOR | ITEM_START | NPC_START;
+    const B: TileFlags = FLOOR;
+    const D: TileFlags = FLOOR | DOOR_START;
+    const F: TileFlags = FLOOR | PLAYER_START;
+    const I: TileFlags = FLOOR | ITEM_START;
+    const N: TileFlags = FLOOR | NPC_START;
+    const W: TileFlags = WALL;
+
+    fn segment(width: usize, tiles: &[TileFlags]) -> WorldSegment {
+        WorldSegment {
+            width: width.try_into().expect("Test segment widths should not be zero"),
+            cells: tiles.to_vec().try_into().expect("Test segments should not be empty"),
+        }
+    }
+
+    fn def() -> EntityDef {
+        EntityDef {
+            speeches: <_>::default(),
+            inventory_description: <_>::default(),
+            id: <_>::default(),
+            flags: <_>::default(),
+            tile_sprite: <_>::default(),
+            wants: <_>::default(),
+            on_collect: <_>::default(),
+            scripts: <_>::default(),
+        }
+    }
+}
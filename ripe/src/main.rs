@@ -14,6 +14,31 @@ mod wasm {
 
 fn run() {
     let params = platform::get_state_params();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = std::env::var_os("RIPE_REPLAY") {
+        match platform::replay(path.as_ref(), params) {
+            Ok(()) => println!("Replay finished without errors."),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            },
+        }
+        return
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = std::env::var_os("RIPE_HEADLESS") {
+        match platform::headless(path.as_ref(), params) {
+            Ok(()) => println!("Wrote the last frame to {}", path.to_string_lossy()),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            },
+        }
+        return
+    }
+
     let state = app::State::new(params);
     platform::run(state);
 }
\ No newline at end of file
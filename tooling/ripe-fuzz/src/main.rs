@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+
+enum Problem {
+    Load(String),
+    Error(String),
+    Panic(String),
+    Unsolvable,
+    GaveUp,
+}
+
+impl core::fmt::Display for Problem {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        use Problem::*;
+        match self {
+            Load(e) => write!(f, "load error: {e}"),
+            Error(e) => write!(f, "generation error: {e}"),
+            Panic(message) => write!(f, "panicked: {message}"),
+            Unsolvable => write!(f, "unsolvable"),
+            GaveUp => write!(f, "gave up; try a larger --state-limit"),
+        }
+    }
+}
+
+fn check_seed(pak_bytes: &[u8], seed: xs::Seed, state_limit: usize) -> Result<(), Problem> {
+    // Configs can use the seed, so each seed gets its own load of the pak.
+    let pak: pak_types::Pak = pak::from_reader(std::io::Cursor::new(pak_bytes), seed)
+        .map_err(|e| Problem::Load(e.to_string()))?;
+
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        let mut rng = xs::from_seed(seed);
+
+        world::generate(&mut rng, &pak.config, &pak.specs)
+            .map(|generated| world::solve::solve_with_limit(&generated, state_limit))
+    }));
+
+    match result {
+        Ok(Ok(world::solve::Outcome::Solvable(_))) => Ok(()),
+        Ok(Ok(world::solve::Outcome::Unsolvable)) => Err(Problem::Unsolvable),
+        Ok(Ok(world::solve::Outcome::GaveUp)) => Err(Problem::GaveUp),
+        Ok(Err(e)) => Err(Problem::Error(format!("{e:?}"))),
+        Err(payload) => Err(Problem::Panic(
+            payload.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "<non-string payload>".to_string())
+        )),
+    }
+}
+
+fn pak_paths_in(examples_dir: &Path) -> Result<Vec<PathBuf>, std::io::Error> {
+    let mut output = Vec::new();
+
+    for entry in std::fs::read_dir(examples_dir)? {
+        let dir = entry?.path();
+        if !dir.is_dir() {
+            continue
+        }
+
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "pak") {
+                output.push(path);
+            }
+        }
+    }
+
+    output.sort();
+
+    Ok(output)
+}
+
+fn inner_main() -> Result<bool, Box<dyn std::error::Error>> {
+    let flags = xflags::parse_or_exit! {
+        /// How many seeds to check per pak. Defaults to 1000.
+        optional -n,--seeds seeds: u128
+        /// The first seed to check. Later seeds count up from this one. Defaults to 0.
+        optional --start start: u128
+        /// How many distinct world states the solver may look at before giving up.
+        optional --state-limit state_limit: usize
+        /// Directory containing a subdirectory per example, each containing a .pak file.
+        /// Defaults to ./examples. Ignored if any paks are passed.
+        optional --examples examples: PathBuf
+        /// Paths to .pak files to check, instead of the ones in the examples directory.
+        repeated paks: PathBuf
+    };
+
+    let seeds = flags.seeds.unwrap_or(1000);
+    let start = flags.start.unwrap_or(0);
+    let state_limit = flags.state_limit.unwrap_or(world::solve::DEFAULT_STATE_LIMIT);
+
+    let pak_paths = if flags.paks.is_empty() {
+        let examples_dir = flags.examples.unwrap_or_else(|| PathBuf::from("examples"));
+        pak_paths_in(&examples_dir)
+            .map_err(|e| format!("{}: {e}", examples_dir.display()))?
+    } else {
+        flags.paks
+    };
+
+    if pak_paths.is_empty() {
+        return Err("No .pak files found to check".into());
+    }
+
+    // We report panics ourselves, with the seed that caused them.
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let mut all_passed = true;
+
+    for pak_path in pak_paths {
+        let pak_bytes = std::fs::read(&pak_path)
+            .map_err(|e| format!("{}: {e}", pak_path.display()))?;
+
+        let mut problem_count = 0;
+
+        for seed_index in start..start.saturating_add(seeds) {
+            let seed = seed_index.to_le_bytes();
+
+            if let Err(problem) = check_seed(&pak_bytes, seed, state_limit) {
+                println!("{} seed {seed_index}: {problem}", pak_path.display());
+                problem_count += 1;
+            }
+        }
+
+        println!(
+            "{}: {problem_count} problem(s) in {seeds} seed(s) starting at {start}",
+            pak_path.display()
+        );
+
+        all_passed &= problem_count == 0;
+    }
+
+    Ok(all_passed)
+}
+
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match inner_main() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error:\n{e}");
+            ExitCode::FAILURE
+        },
+    }
+}
@@ -5,7 +5,7 @@
 // TODO? Make a Game of Life implementation using these tiles, just for fun?
 
 use gfx::{Commands};
-use models::{sprite, Spritesheet};
+use models::{sprite, Spritesheet, Spritesheets};
 use platform_types::{command, unscaled, Button, Input};
 use sword::{NeighborFlag, NeighborMask, TileIndex};
 
@@ -13,12 +13,12 @@ use sword::{NeighborFlag, NeighborMask, TileIndex};
 struct State {
     commands: Commands,
     input: Input,
-    spritesheet: Spritesheet,
+    spritesheets: Spritesheets,
     specs: sprite::Specs,
     tile_index: TileIndex,
 }
 
-fn frame(state: &mut State) -> (&[platform_types::Command], (&[gfx_sizes::ARGB], usize)) {
+fn frame(state: &mut State) -> (&[platform_types::Command], &[Spritesheet]) {
     use TileIndex::{Wall, Floor};
     //
     // Update
@@ -129,7 +129,7 @@ fn frame(state: &mut State) -> (&[platform_types::Command], (&[gfx_sizes::ARGB],
 
     state.input.previous_gamepad = state.input.gamepad;
 
-    (state.commands.slice(), state.spritesheet.slice())
+    (state.commands.slice(), state.spritesheets.slice())
 }
 
 fn neighboring_demo_indexes(tile_index: TileIndex) -> [TileIndex; 9] {
@@ -279,13 +279,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::fs::File::open(
             args.next()
             .ok_or("A .pak filename is required!")?
-        )?
+        )?,
+        // Only the sprites are shown, so which config the seed picks does not matter.
+        <_>::default(),
     )?;
 
     run(State{
         commands: Commands::new(new_seed(), pak.specs.base_font.clone(), pak.specs.base_ui.clone()),
         input: <_>::default(),
-        spritesheet: pak.spritesheet,
+        spritesheets: pak.spritesheets,
         specs: pak.specs,
         tile_index: TileIndex::default(),
     });
@@ -390,7 +392,7 @@ fn run(mut state: State) {
                 just_gained_focus = true;
             }
             Event::MainEventsCleared => {
-                let (commands, gfx) = frame(&mut state);
+                let (commands, spritesheets) = frame(&mut state);
 
                 {
                     let size = window.inner_size();
@@ -401,7 +403,7 @@ fn run(mut state: State) {
                 let needs_redraw = render::render(
                     &mut output_frame_buffer,
                     commands,
-                    gfx,
+                    spritesheets,
                 );
 
                 if NeedsRedraw::Yes == needs_redraw